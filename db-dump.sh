@@ -0,0 +1,46 @@
+#!/bin/bash
+# dump the public tables to a versioned parquet snapshot
+# requires the duckdb cli and DATABASE_URL to be set
+# usage: ./db-dump.sh [output directory]
+
+SNAPSHOT_VERSION=1
+# every table in schema.sql, except suggestions since those are private messages to moderators
+PRIVATE_TABLES="suggestion"
+TABLES=$(grep -oP '^CREATE TABLE \K\w+' schema.sql | grep -vxF -f <(tr ' ' '\n' <<<"$PRIVATE_TABLES"))
+OUT_DIR=${1:-"snapshot-$(date -u +%Y%m%d)"}
+
+if [ -z "$DATABASE_URL" ]; then
+    echo "Required environment variable DATABASE_URL not set."
+    exit 1
+fi
+mkdir -p "$OUT_DIR" || exit
+
+# export each table to its own file
+for table in $TABLES; do
+    duckdb -c "
+        INSTALL postgres; LOAD postgres;
+        ATTACH '$DATABASE_URL' AS pg (TYPE postgres, READ_ONLY);
+        COPY (SELECT * FROM pg.$table) TO '$OUT_DIR/$table.parquet' (FORMAT parquet, COMPRESSION zstd);
+    " || exit
+done
+
+# write a manifest so restores can check compatibility
+{
+    echo "{"
+    echo "  \"snapshot_version\": $SNAPSHOT_VERSION,"
+    echo "  \"created\": \"$(date -u +%Y-%m-%dT%H:%M:%SZ)\","
+    echo "  \"schema_sha256\": \"$(sha256sum schema.sql | cut -d ' ' -f 1)\","
+    echo "  \"tables\": {"
+    first=1
+    for table in $TABLES; do
+        rows=$(duckdb -noheader -list -c "SELECT COUNT(*) FROM '$OUT_DIR/$table.parquet'")
+        [ $first -eq 1 ] || echo ","
+        printf '    "%s": { "file": "%s.parquet", "rows": %s }' "$table" "$table" "$rows"
+        first=0
+    done
+    echo ""
+    echo "  }"
+    echo "}"
+} >"$OUT_DIR/manifest.json"
+
+echo "Saved snapshot to $OUT_DIR"
@@ -0,0 +1,57 @@
+#!/bin/bash
+# restore a parquet snapshot made by db-dump.sh into an empty database
+# requires the duckdb and psql clis and DATABASE_URL to be set
+# WARNING: this recreates all tables from schema.sql
+# usage: ./db-restore.sh <snapshot directory>
+
+SNAPSHOT_VERSION=1
+# tables are created in schema.sql order, which already satisfies references
+TABLES=$(grep -oP '^CREATE TABLE \K\w+' schema.sql)
+# tables with an id sequence to move past the restored rows
+SERIAL_TABLES=$(awk '/^CREATE TABLE/ { table = $3 } /^ *id SERIAL/ { print table }' schema.sql)
+IN_DIR=$1
+
+if [ -z "$DATABASE_URL" ]; then
+    echo "Required environment variable DATABASE_URL not set."
+    exit 1
+fi
+if [ ! -f "$IN_DIR/manifest.json" ]; then
+    echo "Usage: ./db-restore.sh <snapshot directory>"
+    exit 1
+fi
+
+# check the snapshot was made by a compatible version
+version=$(duckdb -noheader -list -c "SELECT snapshot_version FROM read_json_auto('$IN_DIR/manifest.json')")
+if [ "$version" != "$SNAPSHOT_VERSION" ]; then
+    echo "Snapshot version $version is not supported (expected $SNAPSHOT_VERSION)."
+    exit 1
+fi
+schema=$(duckdb -noheader -list -c "SELECT schema_sha256 FROM read_json_auto('$IN_DIR/manifest.json')")
+if [ "$schema" != "$(sha256sum schema.sql | cut -d ' ' -f 1)" ]; then
+    echo "Warning: snapshot was taken with a different schema.sql, columns may not match."
+fi
+
+# recreate the tables and clear the default platform rows
+psql "$DATABASE_URL" -q -f schema.sql || exit
+psql "$DATABASE_URL" -q -c "DELETE FROM platform;" || exit
+
+# load each table in order so references are satisfied
+for table in $TABLES; do
+    if [ ! -f "$IN_DIR/$table.parquet" ]; then
+        echo "Skipping $table, which is not in the snapshot."
+        continue
+    fi
+    duckdb -c "
+        INSTALL postgres; LOAD postgres;
+        ATTACH '$DATABASE_URL' AS pg (TYPE postgres);
+        INSERT INTO pg.$table SELECT * FROM '$IN_DIR/$table.parquet';
+    " || exit
+done
+
+# move the id sequences past the restored rows
+for table in $SERIAL_TABLES; do
+    psql "$DATABASE_URL" -q -c \
+        "SELECT setval(pg_get_serial_sequence('$table', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM $table;" || exit
+done
+
+echo "Restored snapshot from $IN_DIR"
@@ -1,8 +1,123 @@
 //! This library is primarily for bulk-downloading data from several prediction market platforms.
 //! It also exposes `get_markets_all` and `get_market_by_id` for individual use.
+//!
+//! To standardize data that was already downloaded, without any network or database access,
+//! use `standardize_bytes`. It and the types re-exported here are the stable API and only
+//! change in a breaking way with a new major version. Everything else in `platforms` is
+//! used by the fetch tool and may change at any time.
 
 pub mod platforms;
-use platforms::{OutputMethod, Platform};
+use futures::FutureExt;
+pub use platforms::{
+    CloseMethod, MarketConvertError, MarketStandard, Platform, ProbStorage, StandardizeOptions,
+    SCHEMA_VERSION,
+};
+use platforms::{OutputMethod, RunConfig};
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+/// Standardize the markets in one bundle of downloaded platform data.
+/// The bundle format for each platform is listed in the README. Most are a JSON object
+/// holding each API response needed for one market, since that's what the fetch tool
+/// downloads. Platforms that split a market into several (such as multiple-choice answers or
+/// contracts) return one item for each, and markets a normal run would skip return none.
+/// The options set the close method and which probability series are filled in, the same as
+/// the fetch tool's `--close-method`, `--prob-storage`, and `--hourly-max-days`.
+pub fn standardize_bytes(
+    platform: Platform,
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    match platform {
+        Platform::Betfair => platforms::betfair::standardize_bytes(bytes, options),
+        Platform::Infer => platforms::infer::standardize_bytes(bytes, options),
+        Platform::Kalshi => platforms::kalshi::standardize_bytes(bytes, options),
+        Platform::Manifold => platforms::manifold::standardize_bytes(bytes, options),
+        Platform::Metaculus => platforms::metaculus::standardize_bytes(bytes, options),
+        Platform::Polymarket => platforms::polymarket::standardize_bytes(bytes, options),
+        Platform::Predictit => platforms::predictit::standardize_bytes(bytes, options),
+        Platform::Smarkets => platforms::smarkets::standardize_bytes(bytes, options),
+    }
+}
+
+/// Get the list of platforms to process.
+/// If the user requested a specific platform, format it into a list,
+/// otherwise return the default platform list.
+/// PredictIt is left out by default since its resolutions are inferred from the final price.
+fn get_platform_list(platform: Option<Platform>) -> Vec<Platform> {
+    match platform {
+        Some(platform) => Vec::from([platform]),
+        None => Vec::from([
+            Platform::Kalshi,
+            Platform::Manifold,
+            Platform::Metaculus,
+            Platform::Polymarket,
+            Platform::Smarkets,
+        ]),
+    }
+}
+
+/// Process all markets from a platform, or a single market if an ID is given.
+async fn process_platform(
+    platform: Platform,
+    id: Option<String>,
+    output: OutputMethod,
+    config: Arc<RunConfig>,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    match (platform, id.as_deref()) {
+        (Platform::Betfair, None) => {
+            platforms::betfair::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Betfair, Some(id)) => {
+            platforms::betfair::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Infer, None) => {
+            platforms::infer::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Infer, Some(id)) => {
+            platforms::infer::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Kalshi, None) => {
+            platforms::kalshi::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Kalshi, Some(id)) => {
+            platforms::kalshi::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Manifold, None) => {
+            platforms::manifold::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Manifold, Some(id)) => {
+            platforms::manifold::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Metaculus, None) => {
+            platforms::metaculus::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Metaculus, Some(id)) => {
+            platforms::metaculus::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Polymarket, None) => {
+            platforms::polymarket::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Polymarket, Some(id)) => {
+            platforms::polymarket::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Predictit, None) => {
+            platforms::predictit::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Predictit, Some(id)) => {
+            platforms::predictit::get_market_by_id(id, output, &config, verbose).await
+        }
+        (Platform::Smarkets, None) => {
+            platforms::smarkets::get_markets_all(output, &config, sample, verbose).await
+        }
+        (Platform::Smarkets, Some(id)) => {
+            platforms::smarkets::get_market_by_id(id, output, &config, verbose).await
+        }
+    }
+}
 
 /// The main path for processing markets by platform.
 #[tokio::main(flavor = "current_thread")]
@@ -10,60 +125,208 @@ pub async fn run(
     platform: Option<Platform>,
     id: Option<String>,
     output: OutputMethod,
+    config: RunConfig,
+    sample: Option<f32>,
     verbose: bool,
 ) {
-    // if the user requested a specific platform, format it into a list
-    // otherwise, return the default platform list
-    let platforms: Vec<Platform> = match platform {
-        Some(platform) => Vec::from([platform]),
-        None => Vec::from([
-            Platform::Kalshi,
-            Platform::Manifold,
-            Platform::Metaculus,
-            Platform::Polymarket,
-        ]),
+    let config = Arc::new(config);
+
+    // record the run so it shows up in the job list and can't overlap another one
+    let job_id = match output {
+        OutputMethod::Database => match platforms::start_job(
+            "fetch",
+            serde_json::json!({
+                "run_id": config.run_id,
+                "platform": platform,
+                "id": id,
+                "sample": sample,
+                "prob_storage": config.options.prob_storage,
+                "hourly_max_days": config.options.hourly_max_days,
+                "trader_bets": config.trader_salt.is_some(),
+            }),
+        ) {
+            Ok(job_id) => Some(job_id),
+            Err(e) => {
+                eprintln!("Initialization: Could not start fetch: {e}");
+                std::process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
+    // a panic anywhere in the run still marks the job failed, so the stage isn't left locked
+    let outcome = AssertUnwindSafe(process_platforms(
+        platform, id, output, config, sample, verbose,
+    ))
+    .catch_unwind()
+    .await;
+    let (error, degraded) = match &outcome {
+        Ok((error, degraded)) => (error.clone(), degraded.clone()),
+        Err(payload) => (
+            Some(format!(
+                "fetch panicked: {}",
+                get_panic_message(payload.as_ref())
+            )),
+            Vec::new(),
+        ),
     };
+    if let Some(job_id) = job_id {
+        platforms::finish_job(job_id, error.clone(), degraded);
+    }
+    if let Err(payload) = outcome {
+        std::panic::resume_unwind(payload);
+    }
+    if let Some(error) = error {
+        panic!("{error}");
+    }
+}
 
+/// Process every requested platform and wait for their markets to be saved.
+/// Returns the error that stopped the run, if any, and any problems that should mark it degraded.
+async fn process_platforms(
+    platform: Option<Platform>,
+    id: Option<String>,
+    output: OutputMethod,
+    config: Arc<RunConfig>,
+    sample: Option<f32>,
+    verbose: bool,
+) -> (Option<String>, Vec<String>) {
+    let platforms = get_platform_list(platform);
     if verbose {
         println!("Initialization: Processing platforms: {:?}", &platforms);
     }
+    if output == OutputMethod::Database {
+        match platforms::remove_legacy_trader_ids() {
+            Ok(0) => (),
+            Ok(count) => println!("Initialization: Removed {count} rows with old trader IDs"),
+            Err(e) => eprintln!("Initialization: {e}"),
+        }
+        platforms::save_platform_contracts(&platforms);
+        platforms::start_save_worker(&config);
+    }
     let total_timer = std::time::Instant::now();
     let tasks: Vec<_> = platforms
         .into_iter()
         .map(|platform| {
-            let id_i = id.clone();
-            tokio::spawn(async move {
-                match (&platform, &id_i) {
-                    (Platform::Kalshi, None) => {
-                        platforms::kalshi::get_markets_all(output, verbose).await
-                    }
-                    (Platform::Kalshi, Some(id)) => {
-                        platforms::kalshi::get_market_by_id(id, output, verbose).await
-                    }
-                    (Platform::Manifold, None) => {
-                        platforms::manifold::get_markets_all(output, verbose).await
-                    }
-                    (Platform::Manifold, Some(id)) => {
-                        platforms::manifold::get_market_by_id(id, output, verbose).await
-                    }
-                    (Platform::Metaculus, None) => {
-                        platforms::metaculus::get_markets_all(output, verbose).await
-                    }
-                    (Platform::Metaculus, Some(id)) => {
-                        platforms::metaculus::get_market_by_id(id, output, verbose).await
-                    }
-                    (Platform::Polymarket, None) => {
-                        platforms::polymarket::get_markets_all(output, verbose).await
-                    }
-                    (Platform::Polymarket, Some(id)) => {
-                        platforms::polymarket::get_market_by_id(id, output, verbose).await
-                    }
-                }
-            })
+            tokio::spawn(process_platform(
+                platform,
+                id.clone(),
+                output,
+                Arc::clone(&config),
+                sample,
+                verbose,
+            ))
         })
         .collect();
-    futures::future::try_join_all(tasks)
-        .await
-        .expect("Failed to join tasks");
-    println!("All platforms complete in {:?}", total_timer.elapsed());
+    let result = futures::future::try_join_all(tasks).await;
+    let save_result = platforms::finish_save_worker(&config);
+    let mut degraded = platforms::report_response_health(&config.stats);
+    degraded.extend(platforms::report_spills(&config));
+    platforms::report_exclusions(&config);
+    let error = match (result, save_result) {
+        (Err(e), _) => Some(format!("Failed to join tasks: {e}")),
+        (_, Err(e)) => Some(format!("Failed to save markets: {e}")),
+        (Ok(_), Ok(())) => {
+            platforms::report_unrecognized(&config.stats);
+            println!("All platforms complete in {:?}", total_timer.elapsed());
+            None
+        }
+    };
+    (error, degraded)
+}
+
+/// Get the message a panic was raised with, for reporting it somewhere other than stderr.
+fn get_panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or(payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or("unknown panic".to_string())
+}
+
+/// Check each platform's API against our response types before a full run.
+/// This downloads and converts one known market per platform without saving it,
+/// and reports any platform where a response no longer deserializes or converts
+/// or the converted market breaks the platform's declared contract.
+#[tokio::main(flavor = "current_thread")]
+pub async fn probe(
+    platform: Option<Platform>,
+    id: Option<String>,
+    config: RunConfig,
+    verbose: bool,
+) {
+    let config = Arc::new(config);
+    let platforms = get_platform_list(platform);
+    let mut failures = 0;
+    for platform in platforms {
+        let Some(market_id) = id
+            .clone()
+            .or(platform.probe_market_id().map(str::to_string))
+        else {
+            println!(
+                "{:?}: SKIPPED - no known market, pass one with --id",
+                platform
+            );
+            continue;
+        };
+        // any deserialization or conversion failure panics inside the task
+        let task = tokio::spawn(process_platform(
+            platform,
+            Some(market_id.clone()),
+            OutputMethod::Check,
+            Arc::clone(&config),
+            None,
+            verbose,
+        ));
+        match task.await {
+            Ok(()) if platforms::has_deserialize_failures(&config.stats, platform) => {
+                failures += 1;
+                println!(
+                    "{:?}: FAILED ({}) - some responses failed to deserialize",
+                    platform, market_id
+                );
+            }
+            Ok(()) => println!("{:?}: OK ({})", platform, market_id),
+            Err(error) => {
+                failures += 1;
+                let message = match error.try_into_panic() {
+                    Ok(payload) => get_panic_message(payload.as_ref()),
+                    Err(error) => error.to_string(),
+                };
+                println!("{:?}: FAILED ({}) - {}", platform, market_id, message);
+            }
+        }
+    }
+    platforms::report_response_health(&config.stats);
+    platforms::report_unrecognized(&config.stats);
+    if failures > 0 {
+        println!("Probe found problems with {} platform(s).", failures);
+        std::process::exit(1);
+    }
+}
+
+/// Measure how fast each platform is processed without saving anything.
+/// Platforms are run one at a time so each one's throughput can be measured on its own,
+/// and the time spent downloading, deserializing, and converting is reported for each.
+#[tokio::main(flavor = "current_thread")]
+pub async fn bench(
+    platform: Option<Platform>,
+    config: RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    let config = Arc::new(config);
+    for platform in get_platform_list(platform) {
+        let timer = std::time::Instant::now();
+        process_platform(
+            platform,
+            None,
+            OutputMethod::Null,
+            Arc::clone(&config),
+            sample,
+            verbose,
+        )
+        .await;
+        platforms::report_bench(&config.stats, platform, timer.elapsed());
+    }
 }
@@ -1,7 +1,9 @@
 //! This binary just parses CLI arguments and passes them to the library run process.
 
 use clap::Parser;
-use themis_fetch::platforms::{OutputMethod, Platform};
+use themis_fetch::platforms::{
+    CloseMethod, OutputMethod, Platform, ProbStorage, RunConfig, StandardizeOptions,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -18,12 +20,149 @@ struct Args {
     #[arg(short, long, default_value = "database")]
     output: OutputMethod,
 
+    /// Which timestamp to use as the market close time
+    #[arg(short, long, default_value = "earliest")]
+    close_method: CloseMethod,
+
+    /// Only process a percentage of markets (e.g. `1%`), picked the same way on every run
+    #[arg(short, long, value_parser = parse_sample_pct)]
+    sample: Option<f32>,
+
+    /// How to store the daily probabilities, as a JSON map or a packed array
+    #[arg(long, default_value = "json")]
+    prob_storage: ProbStorage,
+
+    /// Also save an hourly probability series for markets open this many days or fewer
+    #[arg(long, value_name = "DAYS")]
+    hourly_max_days: Option<f32>,
+
+    /// YAML file listing markets to skip, with the reason for each
+    #[arg(long, value_name = "FILE")]
+    exclusions: Option<String>,
+
+    /// Also save each trader's bets on Manifold markets, anonymized with the salt in `TRADER_ID_SALT`
+    #[arg(long)]
+    trader_bets: bool,
+
+    /// How many times to try saving each batch of markets before giving up on it
+    #[arg(long, default_value = "3", value_name = "ATTEMPTS")]
+    save_attempts: u32,
+
+    /// Write batches that still fail to save to this directory instead of stopping the run
+    #[arg(long, value_name = "DIR")]
+    spill_dir: Option<String>,
+
+    /// Save batches of markets spilled by an earlier run, then exit
+    #[arg(long, value_name = "FILE")]
+    replay: Vec<String>,
+
+    /// Mark a fetch job left running by a killed run as failed before starting this one
+    #[arg(long)]
+    force: bool,
+
+    /// Check each platform's API against a known market and report any schema drift, without saving
+    #[arg(long)]
+    probe: bool,
+
+    /// Process markets without saving and report throughput and stage timing for each platform
+    #[arg(long)]
+    bench: bool,
+
     /// Show additional output for debugging
     #[arg(short, long)]
     verbose: bool,
 }
 
+/// Parse a sample percentage with or without the trailing percent sign.
+fn parse_sample_pct(input: &str) -> Result<f32, String> {
+    let pct: f32 = input
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("`{input}` is not a percentage"))?;
+    if (0.0..=100.0).contains(&pct) {
+        Ok(pct)
+    } else {
+        Err(format!("sample must be between 0% and 100%, got {pct}%"))
+    }
+}
+
 fn main() {
     let args = Args::parse();
-    themis_fetch::run(args.platform, args.id, args.output, args.verbose);
+    let mut options = StandardizeOptions::default();
+    options.close_method = args.close_method;
+    options.prob_storage = args.prob_storage;
+    options.hourly_max_days = args.hourly_max_days;
+    let mut config = RunConfig::new(options);
+    if args.probe {
+        themis_fetch::probe(args.platform, args.id, config, args.verbose);
+        return;
+    }
+    if args.bench {
+        themis_fetch::bench(args.platform, config, args.sample, args.verbose);
+        return;
+    }
+    if let Some(path) = args.exclusions {
+        match themis_fetch::platforms::load_exclusions(&path) {
+            Ok(exclusions) => {
+                if args.verbose {
+                    println!(
+                        "Initialization: Loaded {} market exclusions from {path}",
+                        exclusions.len()
+                    );
+                }
+                config.exclusions = exclusions;
+            }
+            Err(e) => {
+                eprintln!("Initialization: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.trader_bets {
+        match std::env::var("TRADER_ID_SALT") {
+            Ok(salt) if !salt.is_empty() => config.trader_salt = Some(salt),
+            _ => {
+                eprintln!("Initialization: --trader-bets requires TRADER_ID_SALT to be set");
+                std::process::exit(1);
+            }
+        }
+    }
+    config.save_attempts = args.save_attempts;
+    if let Some(dir) = args.spill_dir {
+        if let Err(e) = themis_fetch::platforms::create_spill_dir(&dir) {
+            eprintln!("Initialization: {e}");
+            std::process::exit(1);
+        }
+        config.spill_dir = Some(dir);
+    }
+    if !args.replay.is_empty() {
+        for path in &args.replay {
+            match themis_fetch::platforms::replay_spilled_markets(&config, path) {
+                Ok(count) => println!("Replay: Saved {count} markets from {path}"),
+                Err(e) => {
+                    eprintln!("Replay: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+    if args.force && args.output == OutputMethod::Database {
+        match themis_fetch::platforms::clear_running_jobs("fetch") {
+            Ok(0) => (),
+            Ok(count) => println!("Initialization: Cleared {count} running fetch jobs"),
+            Err(e) => {
+                eprintln!("Initialization: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    themis_fetch::run(
+        args.platform,
+        args.id,
+        args.output,
+        config,
+        args.sample,
+        args.verbose,
+    );
 }
@@ -1,12 +1,14 @@
 //! This module has all of the common utilities and market standardization tools required to query the API and convert responses into DB rows.
 
 use chrono::serde::{ts_milliseconds, ts_milliseconds_option, ts_seconds};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
 use clap::ValueEnum;
 use core::fmt;
 use diesel::upsert::excluded;
 use diesel::{pg::PgConnection, prelude::*, Connection, Insertable};
 use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use regex::Regex;
 use reqwest::header::{HeaderValue, AUTHORIZATION};
 use reqwest::StatusCode;
 use reqwest_chain::Chainer;
@@ -16,25 +18,265 @@ use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use serde_json::to_string_pretty;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use sha2::Sha256;
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, HashMap};
 use std::env::var;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex, OnceLock};
 
+pub mod betfair;
+pub mod infer;
 pub mod kalshi;
 pub mod manifold;
 pub mod metaculus;
 pub mod polymarket;
+pub mod predictit;
+pub mod smarkets;
+
+/// Version of the market format printed to the console and returned to library users.
+/// Increment it when a `MarketStandard` field is renamed, removed, or changes meaning.
+pub const SCHEMA_VERSION: u32 = 1;
 
 const DEFAULT_OPENING_PROB: f32 = 0.5;
 const SECS_PER_DAY: f32 = (60 * 60 * 24) as f32;
+const DESCRIPTION_EXCERPT_LENGTH: usize = 280;
+const VOLUME_CHANGE_THRESHOLD: f32 = 0.1;
+/// A market is confident once its probability is at least this far toward either outcome.
+const CONFIDENCE_THRESHOLD: f32 = 0.9;
+/// Size of the bet used to turn price impact into an uncertainty band, in USD.
+const STANDARD_BET_USD: f32 = 1.0;
+/// Share of a platform's responses that can fail to deserialize before the run is marked degraded.
+const DESERIALIZE_FAILURE_THRESHOLD: f32 = 0.05;
+/// Length of the example payload included in a deserialization diagnosis, in characters.
+const DIAGNOSIS_SNIPPET_LEN: usize = 500;
+/// How many times to try saving each batch of markets, if not set for the run.
+const DEFAULT_SAVE_ATTEMPTS: u32 = 3;
+/// How long to wait before retrying a failed save, doubled after each attempt.
+const SAVE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+/// Length of an anonymized trader ID, the hex digits of an HMAC-SHA256.
+const TRADER_ID_LENGTH: usize = 64;
+/// How many batches can wait for the save worker before platforms have to wait for it.
+const SAVE_QUEUE_LENGTH: usize = 4;
+/// Title patterns used to tag markets with a region, checked in order.
+/// Abbreviations are case-sensitive so they don't match ordinary words.
+const REGION_PATTERNS: [(&str, &str); 3] = [
+    (
+        "US",
+        r"(?:^|\W)(US|USA|U\.S\.|(?i:united states|american?s?|congress|senate|supreme court|federal reserve|democrats?|republicans?)|GOP|Biden|Trump|Harris)(?:\W|$)",
+    ),
+    (
+        "UK",
+        r"(?:^|\W)(UK|U\.K\.|(?i:united kingdom|britain|british|england|scotland|wales|tory|tories|labour|bank of england)|Sunak|Starmer)(?:\W|$)",
+    ),
+    (
+        "EU",
+        r"(?:^|\W)(EU|ECB|(?i:european union|european parliament|european commission|eurozone|germany|german|france|french|italy|italian|spain|spanish|netherlands|dutch|poland|polish)|Macron|Scholz)(?:\W|$)",
+    ),
+];
+
+/// How markets are converted into the standard format.
+/// The default matches the fetch tool's defaults: the earliest close time, probabilities stored
+/// as a JSON map, and no hourly series.
+#[derive(Debug, Copy, Clone, Default)]
+#[non_exhaustive]
+pub struct StandardizeOptions {
+    /// Which timestamp to use as the market close time.
+    pub close_method: CloseMethod,
+    /// How daily probabilities are stored.
+    pub prob_storage: ProbStorage,
+    /// Markets open this many days or fewer also get an hourly probability series, if set.
+    pub hourly_max_days: Option<f32>,
+}
+
+/// Settings for one run, set before it starts and passed down to every platform.
+pub struct RunConfig {
+    /// Identifier for this run, used to group logged changes.
+    pub run_id: String,
+    /// How markets are converted into the standard format.
+    pub options: StandardizeOptions,
+    /// Secret mixed into trader IDs before hashing, set only when trader bets are being saved.
+    pub trader_salt: Option<String>,
+    /// How many times to try saving each batch before giving up on it.
+    pub save_attempts: u32,
+    /// Where to write batches that couldn't be saved after every attempt, if set.
+    pub spill_dir: Option<String>,
+    /// Markets to skip by platform and ID, with the reason for each.
+    pub exclusions: HashMap<(String, String), String>,
+    /// Everything counted while the run is processed, reported once it's finished.
+    pub stats: RunStats,
+    /// The background thread saving batches to the database, if one is running.
+    save_worker: Mutex<Option<SaveWorker>>,
+}
+impl RunConfig {
+    /// Settings for a new run with the given conversion options, identified by when it started.
+    pub fn new(options: StandardizeOptions) -> RunConfig {
+        RunConfig {
+            run_id: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+            options,
+            trader_salt: None,
+            save_attempts: DEFAULT_SAVE_ATTEMPTS,
+            spill_dir: None,
+            exclusions: HashMap::new(),
+            stats: RunStats::default(),
+            save_worker: Mutex::new(None),
+        }
+    }
+}
+
+/// Everything counted while a run is processed, used to report on it once it's finished.
+#[derive(Debug, Default)]
+pub struct RunStats {
+    /// How many batches were written to the spill directory.
+    spilled: AtomicUsize,
+    /// How many markets were skipped for each exclusion reason.
+    excluded: Mutex<BTreeMap<String, usize>>,
+    /// Fields and values from platform APIs that we don't recognize, with how often each was seen.
+    unrecognized: Mutex<BTreeMap<String, usize>>,
+    /// Deserialization results for each platform, used to diagnose API changes.
+    response_health: Mutex<BTreeMap<String, ResponseHealth>>,
+    /// Throughput and time spent in each processing stage for each platform, used by `--bench`.
+    bench: Mutex<BTreeMap<String, BenchStats>>,
+}
 
 /// All possible platforms that are supported by this application.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize)]
+#[non_exhaustive]
 pub enum Platform {
+    Betfair,
+    Infer,
     Kalshi,
     Manifold,
     Metaculus,
     Polymarket,
+    Predictit,
+    Smarkets,
+}
+impl Platform {
+    /// A resolved market used to check the API with `--probe`, if there is a stable one.
+    pub fn probe_market_id(&self) -> Option<&'static str> {
+        match self {
+            Platform::Betfair => None,
+            Platform::Infer => None,
+            Platform::Kalshi => Some("PRES-2024-DJT"),
+            Platform::Manifold => Some("tI0SmMHzn0nMUbXo21rm"),
+            Platform::Metaculus => Some("19998"),
+            Platform::Polymarket => {
+                Some("0xdd22472e552920b8438158ea7238bfadfa4f736aa4cee91a6b86c39ead110917")
+            }
+            Platform::Predictit => None,
+            Platform::Smarkets => None,
+        }
+    }
+
+    /// The data this platform's adapter promises to provide for every market it saves.
+    pub fn contract(&self) -> PlatformContract {
+        // every adapter must provide a resolution and timestamped probabilities
+        let base = PlatformContract {
+            resolutions: true,
+            timestamped_probabilities: true,
+            resolved_dt: true,
+            traded_volume: true,
+            num_traders: false,
+            daily_volume: false,
+            price_impact: false,
+            linked_groups: false,
+            quantiles: false,
+        };
+        match self {
+            Platform::Betfair => base,
+            Platform::Infer => PlatformContract {
+                traded_volume: false,
+                num_traders: true,
+                ..base
+            },
+            Platform::Kalshi => PlatformContract {
+                linked_groups: true,
+                ..base
+            },
+            Platform::Manifold => PlatformContract {
+                num_traders: true,
+                linked_groups: true,
+                daily_volume: true,
+                price_impact: true,
+                ..base
+            },
+            Platform::Metaculus => PlatformContract {
+                traded_volume: false,
+                num_traders: true,
+                linked_groups: true,
+                quantiles: true,
+                ..base
+            },
+            Platform::Polymarket => PlatformContract {
+                resolved_dt: false,
+                ..base
+            },
+            Platform::Predictit => PlatformContract {
+                resolved_dt: false,
+                ..base
+            },
+            Platform::Smarkets => PlatformContract {
+                resolved_dt: false,
+                ..base
+            },
+        }
+    }
+}
+
+/// The minimum data contract for a platform, plus the optional data it can provide.
+/// Metrics built on an optional capability are only comparable between platforms that have it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct PlatformContract {
+    /// Each market resolves to a probability between 0 and 1. Required.
+    pub resolutions: bool,
+    /// Probabilities are reported with the time they changed. Required.
+    pub timestamped_probabilities: bool,
+    /// The platform reports when each market was resolved.
+    pub resolved_dt: bool,
+    /// Volume is money actually traded rather than estimated from the number of forecasts.
+    pub traded_volume: bool,
+    /// The platform reports the number of unique traders or forecasters.
+    pub num_traders: bool,
+    /// Trade sizes are reported, so volume can be broken down by day.
+    pub daily_volume: bool,
+    /// Price impact is reported, so each day gets an uncertainty band.
+    pub price_impact: bool,
+    /// Markets that are options of the same question are linked together.
+    pub linked_groups: bool,
+    /// Numeric questions have forecast quantiles.
+    pub quantiles: bool,
+}
+
+/// Totals for one platform, summed over every request and market.
+/// Requests run concurrently, so the stage times can add up to more than the wall time.
+#[derive(Debug, Default, Clone)]
+pub struct BenchStats {
+    markets: usize,
+    bytes: usize,
+    download: std::time::Duration,
+    deserialize: std::time::Duration,
+    convert: std::time::Duration,
+}
+
+/// A market that should never be saved or scored, as listed in the exclusion file.
+#[derive(Debug, Deserialize)]
+struct Exclusion {
+    platform: String,
+    platform_id: String,
+    /// Why the market is excluded, such as `bad_data`, `duplicate`, or `abusive_resolution`.
+    reason: String,
+}
+
+/// Deserialization results for one platform, summed over every request.
+#[derive(Debug, Default)]
+struct ResponseHealth {
+    responses: usize,
+    /// How often each type failed at each field, keyed by the type, field path, and error.
+    failures: BTreeMap<String, usize>,
+    /// The start of the first response that failed.
+    example: Option<String>,
 }
 
 /// All possible methods to output markets.
@@ -43,9 +285,69 @@ pub enum OutputMethod {
     Database,
     Stdout,
     Null,
+    /// Check markets against the platform contract instead of saving them, used by `--probe`.
+    #[value(skip)]
+    Check,
     //File,
 }
 
+/// All possible methods to pick the market close time from the trading end and resolution times.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+#[non_exhaustive]
+pub enum CloseMethod {
+    /// Use whichever came first, trading end or resolution.
+    #[default]
+    Earliest,
+    /// Use the time the market stopped accepting trades.
+    TradingEnd,
+    /// Use the time the market was resolved, if the platform reports it.
+    Resolution,
+}
+
+/// All possible formats to store the daily market probabilities in.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[non_exhaustive]
+pub enum ProbStorage {
+    /// A JSON map from each day to the probability, in `prob_each_date`.
+    #[default]
+    Json,
+    /// The first day and an array with one probability per day, in `prob_daily_start_dt` and `prob_daily`.
+    Packed,
+}
+
+/// All possible buckets to average the market probability over, aligned to UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProbInterval {
+    /// Each hour, starting on the hour.
+    Hour,
+    /// Each day, starting at midnight.
+    Day,
+    /// Each week, starting at midnight on Monday.
+    Week,
+}
+impl ProbInterval {
+    /// Get the length of one bucket.
+    fn duration(&self) -> Duration {
+        match self {
+            ProbInterval::Hour => Duration::hours(1),
+            ProbInterval::Day => Duration::days(1),
+            ProbInterval::Week => Duration::weeks(1),
+        }
+    }
+
+    /// Get the start of the bucket containing this time.
+    fn bucket_start(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let day_start = time.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+        match self {
+            ProbInterval::Hour => Some(day_start + Duration::hours(time.hour() as i64)),
+            ProbInterval::Day => Some(day_start),
+            ProbInterval::Week => {
+                Some(day_start - Duration::days(time.weekday().num_days_from_monday() as i64))
+            }
+        }
+    }
+}
+
 // Diesel macro to save the markets to a database table.
 table! {
     market (id) {
@@ -56,40 +358,199 @@ table! {
         url -> Varchar,
         open_dt -> Timestamptz,
         close_dt -> Timestamptz,
+        trading_end_dt -> Timestamptz,
+        resolved_dt -> Nullable<Timestamptz>,
         open_days -> Float,
         volume_usd -> Float,
         num_traders -> Integer,
         category -> Varchar,
+        region -> Varchar,
+        description -> Varchar,
+        description_excerpt -> Varchar,
+        image_url -> Nullable<Varchar>,
         prob_at_midpoint -> Float,
         prob_at_close -> Float,
         prob_each_pct -> Array<Float>,
         prob_each_date -> Jsonb,
+        prob_each_hour -> Jsonb,
+        prob_daily_start_dt -> Nullable<Timestamptz>,
+        prob_daily -> Array<Float>,
+        active_each_date -> Jsonb,
+        volume_each_date -> Jsonb,
+        prob_band_each_date -> Jsonb,
         prob_time_avg -> Float,
+        prob_min -> Float,
+        prob_max -> Float,
+        prob_daily_stddev -> Float,
+        prob_midpoint_crossings -> Integer,
+        days_to_confidence -> Nullable<Float>,
         resolution -> Float,
+        question_type -> Varchar,
+        quantiles_each_date -> Jsonb,
+        linked_group -> Nullable<Varchar>,
+        linked_order -> Nullable<Integer>,
+    }
+}
+
+// Diesel macro to log changes to existing markets.
+table! {
+    market_change (id) {
+        id -> Int4,
+        run_id -> Varchar,
+        platform -> Varchar,
+        platform_id -> Varchar,
+        field -> Varchar,
+        old_value -> Float,
+        new_value -> Float,
+    }
+}
+
+/// A material change to a market that was already saved, logged before it is overwritten.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = market_change)]
+struct MarketChange {
+    run_id: String,
+    platform: String,
+    platform_id: String,
+    field: String,
+    old_value: f32,
+    new_value: f32,
+}
+
+// Diesel macro to track pipeline runs.
+table! {
+    job (id) {
+        id -> Int4,
+        stage -> Varchar,
+        params -> Jsonb,
+        status -> Varchar,
+        finished_dt -> Nullable<Timestamptz>,
+        error -> Nullable<Varchar>,
+    }
+}
+
+// Diesel macro to record what each platform provides.
+table! {
+    platform (name) {
+        name -> Varchar,
+        capabilities -> Jsonb,
+    }
+}
+
+// Diesel macro to save each trader's bets on a market.
+table! {
+    trader_bet (id) {
+        id -> Int4,
+        platform -> Varchar,
+        platform_id -> Varchar,
+        trader_id -> Varchar,
+        bet_probs -> Array<Float>,
+        last_bet_dt -> Timestamptz,
     }
 }
 
+/// A pipeline run that is about to start.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = job)]
+struct NewJob {
+    stage: String,
+    params: serde_json::Value,
+    status: String,
+}
+
+/// Markets printed to the console, tagged with the schema version.
+#[derive(Debug, Serialize)]
+struct MarketOutput<'a> {
+    schema_version: u32,
+    markets: &'a [MarketStandard],
+}
+
+/// Markets read back from a spilled batch, in the same format as `MarketOutput`.
+#[derive(Debug, Deserialize)]
+struct MarketInput {
+    schema_version: u32,
+    markets: Vec<MarketStandard>,
+}
+
 /// The central market type that all platform-specific objects are converted into.
 /// This is the object type that is sent to the database, file, or console.
-#[derive(Debug, Serialize, Insertable, AsChangeset)]
+#[derive(Debug, Serialize, Deserialize, Insertable, AsChangeset)]
 #[diesel(table_name = market)]
+#[non_exhaustive]
 pub struct MarketStandard {
-    title: String,
-    platform: String,
-    platform_id: String,
-    url: String,
-    open_dt: DateTime<Utc>,
-    close_dt: DateTime<Utc>,
-    open_days: f32,
-    volume_usd: f32,
-    num_traders: i32,
-    category: String,
-    prob_at_midpoint: f32,
-    prob_at_close: f32,
-    prob_each_pct: Vec<f32>,
-    prob_each_date: serde_json::Value,
-    prob_time_avg: f32,
-    resolution: f32,
+    pub title: String,
+    pub platform: String,
+    pub platform_id: String,
+    pub url: String,
+    pub open_dt: DateTime<Utc>,
+    pub close_dt: DateTime<Utc>,
+    pub trading_end_dt: DateTime<Utc>,
+    pub resolved_dt: Option<DateTime<Utc>>,
+    pub open_days: f32,
+    pub volume_usd: f32,
+    pub num_traders: i32,
+    pub category: String,
+    pub region: String,
+    pub description: String,
+    pub description_excerpt: String,
+    pub image_url: Option<String>,
+    pub prob_at_midpoint: f32,
+    pub prob_at_close: f32,
+    pub prob_each_pct: Vec<f32>,
+    pub prob_each_date: serde_json::Value,
+    pub prob_each_hour: serde_json::Value,
+    pub prob_daily_start_dt: Option<DateTime<Utc>>,
+    pub prob_daily: Vec<f32>,
+    pub active_each_date: serde_json::Value,
+    pub volume_each_date: serde_json::Value,
+    pub prob_band_each_date: serde_json::Value,
+    pub prob_time_avg: f32,
+    pub prob_min: f32,
+    pub prob_max: f32,
+    pub prob_daily_stddev: f32,
+    pub prob_midpoint_crossings: i32,
+    pub days_to_confidence: Option<f32>,
+    pub resolution: f32,
+    pub question_type: String,
+    pub quantiles_each_date: serde_json::Value,
+    pub linked_group: Option<String>,
+    pub linked_order: Option<i32>,
+}
+impl MarketStandard {
+    /// Get the probability at a percent of the way through the market's duration (0 to 100).
+    pub fn prob_at_pct(&self, pct: usize) -> Option<f32> {
+        self.prob_each_pct.get(pct).copied()
+    }
+    /// Get the probability on a day the market was open, given as midnight UTC.
+    /// This works with either way of storing the daily probabilities.
+    pub fn prob_on_day(&self, day: DateTime<Utc>) -> Option<f32> {
+        if let Some(start) = self.prob_daily_start_dt {
+            let index = usize::try_from((day - start).num_days()).ok()?;
+            return self.prob_daily.get(index).copied();
+        }
+        let key = serde_json::to_value(day).ok()?;
+        self.prob_each_date
+            .get(key.as_str()?)?
+            .as_f64()
+            .map(|prob| prob as f32)
+    }
+    /// Get the Brier score of a probability against the market's resolution.
+    pub fn brier_score(&self, prob: f32) -> f32 {
+        (self.resolution - prob).powi(2)
+    }
+}
+
+/// The prices one trader bought at on one market, keyed by an anonymized trader ID.
+#[derive(Debug, Serialize, Insertable)]
+#[diesel(table_name = trader_bet)]
+pub struct TraderBets {
+    pub platform: String,
+    pub platform_id: String,
+    /// A salted hash of the platform's user ID, so the trader can't be looked up.
+    pub trader_id: String,
+    /// The market probability just after each of the trader's buys, oldest first.
+    pub bet_probs: Vec<f32>,
+    pub last_bet_dt: DateTime<Utc>,
 }
 
 /// Simple struct for market events. The timestamp declares when the probability became that value.
@@ -99,6 +560,29 @@ pub struct ProbUpdate {
     prob: f32,
 }
 
+/// Simple struct for trades with a known size. The timestamp declares when the trade was made.
+#[derive(Debug, Clone)]
+pub struct VolumeUpdate {
+    time: DateTime<Utc>,
+    volume_usd: f32,
+}
+
+/// Simple struct for how far one trade moved the price, per USD traded.
+/// The timestamp declares when the trade was made, and the value holds until the next trade.
+#[derive(Debug, Clone)]
+pub struct ImpactUpdate {
+    time: DateTime<Utc>,
+    prob_change_per_usd: f32,
+}
+
+/// Simple struct for numeric forecast events. The timestamp declares when the forecast changed.
+/// The quartiles (25th, 50th, and 75th percentile) are positions in the question range from 0 to 1.
+#[derive(Debug, Clone)]
+pub struct QuantileUpdate {
+    time: DateTime<Utc>,
+    quartiles: [f32; 3],
+}
+
 /// Common traits used to standardize platform-specific market objects into the standard types.
 pub trait MarketStandardizer {
     /// Get the string representation of the market for debug pruposes.
@@ -119,10 +603,29 @@ pub trait MarketStandardizer {
     /// Get the time the market openend.
     fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError>;
 
-    /// Get the time the market closed.
-    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError>;
+    /// Get the options the market is being converted with.
+    fn options(&self) -> StandardizeOptions;
+
+    /// Get the time the market stopped accepting trades.
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError>;
 
-    /// Get the total duration of the market in days.
+    /// Get the time the market was resolved, if the platform reports it.
+    fn resolved_dt(&self) -> Option<DateTime<Utc>>;
+
+    /// Get the time the market closed according to the selected close method.
+    /// Falls back to the trading end if the platform does not report a resolution time.
+    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        let trading_end = self.trading_end_dt()?;
+        match (self.options().close_method, self.resolved_dt()) {
+            (CloseMethod::TradingEnd, _) | (_, None) => Ok(trading_end),
+            (CloseMethod::Resolution, Some(resolved)) => Ok(resolved),
+            (CloseMethod::Earliest, Some(resolved)) => Ok(cmp::min(trading_end, resolved)),
+        }
+    }
+
+    /// Get the total duration of the market in days (from open to the selected close time).
+    /// This is fractional and computed from the exact timestamps, so it does not depend on
+    /// how many UTC calendar days the market happened to touch.
     fn open_days(&self) -> Result<f32, MarketConvertError> {
         Ok((self.close_dt()? - self.open_dt()?).num_seconds() as f32 / SECS_PER_DAY)
     }
@@ -136,12 +639,73 @@ pub trait MarketStandardizer {
     /// Get which category the market is in.
     fn category(&self) -> String;
 
+    /// Get the region the market is about: `US`, `UK`, `EU`, or `Global` if none is detected.
+    /// This is inferred from the title unless the platform provides something better.
+    fn region(&self) -> String {
+        get_region(&self.title())
+    }
+
+    /// Get the market description as provided by the platform (plain text, Markdown, or HTML).
+    fn description(&self) -> String;
+
+    /// Get the market description normalized into clean Markdown.
+    fn description_markdown(&self) -> String {
+        normalize_markdown(&self.description())
+    }
+
+    /// Get a short plaintext excerpt of the description for search and previews.
+    fn description_excerpt(&self) -> String {
+        get_plaintext_excerpt(&self.description_markdown(), DESCRIPTION_EXCERPT_LENGTH)
+    }
+
+    /// Get a cover or preview image for the market, if the platform provides one.
+    fn image_url(&self) -> Option<String> {
+        None
+    }
+
     /// Get a list of probability-affecting events during the market (derived from bets/trades).
     fn events(&self) -> Vec<ProbUpdate>;
 
-    /// Get the actual resolved value (0 for no, 1 for yes, or in-between)
+    /// Get the actual resolved value (0 for no, 1 for yes, or in-between).
+    /// For numeric and date questions this is the position of the outcome in the question range.
     fn resolution(&self) -> Result<f32, MarketConvertError>;
 
+    /// Get the kind of question, either `binary`, `numeric`, or `date`.
+    /// For numeric and date questions the probability fields hold the median forecast instead.
+    fn question_type(&self) -> String {
+        "binary".to_string()
+    }
+
+    /// Get a list of trades during the market with their size in USD.
+    /// Platforms that don't report per-trade sizes leave this empty.
+    fn volume_events(&self) -> Vec<VolumeUpdate> {
+        Vec::new()
+    }
+
+    /// Get a list of trades during the market with how much each one moved the price.
+    /// This is for automated market makers where depth can be measured from the trades.
+    /// Other platforms leave this empty.
+    fn impact_events(&self) -> Vec<ImpactUpdate> {
+        Vec::new()
+    }
+
+    /// Get an identifier shared by every market that is one option of the same
+    /// mutually-exclusive question on this platform, if there is one.
+    fn linked_group(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the position of this option among the others in its linked group, if the options
+    /// are ordered (such as ranges of a number), so they can be scored as a distribution.
+    fn linked_order(&self) -> Option<i32> {
+        None
+    }
+
+    /// Get a list of forecast distribution events during the market, for numeric and date questions.
+    fn quantile_events(&self) -> Vec<QuantileUpdate> {
+        Vec::new()
+    }
+
     /// Get the market's probability at a specific time.
     /// If a time before the first event is requested, we use a default opening of 50%.
     /// Returns an error if a time before market open is requested.
@@ -237,10 +801,7 @@ pub trait MarketStandardizer {
         let all_events = self.events();
 
         // get the probability at the start of the window
-        let last_event_before_window = all_events
-            .iter()
-            .filter(|event| event.time <= window_start)
-            .last();
+        let last_event_before_window = all_events.iter().rfind(|event| event.time <= window_start);
         let prob_at_window_start = match last_event_before_window {
             Some(event) => event.prob,
             None => DEFAULT_OPENING_PROB,
@@ -324,96 +885,613 @@ pub trait MarketStandardizer {
         self.prob_time_avg_between(self.open_dt()?, self.close_dt()?)
     }
 
+    /// Get the lowest and highest probabilities the market reached while it was open,
+    /// starting from its probability at open.
+    fn prob_extremes(&self) -> Result<(f32, f32), MarketConvertError> {
+        let (open_dt, close_dt) = (self.open_dt()?, self.close_dt()?);
+        let open_prob = self.prob_at_time(open_dt)?;
+        Ok(self
+            .events()
+            .iter()
+            .filter(|event| open_dt < event.time && event.time <= close_dt)
+            .fold((open_prob, open_prob), |(min, max), event| {
+                (min.min(event.prob), max.max(event.prob))
+            }))
+    }
+
+    /// Get the lowest probability the market reached while it was open.
+    fn prob_min(&self) -> Result<f32, MarketConvertError> {
+        Ok(self.prob_extremes()?.0)
+    }
+
+    /// Get the highest probability the market reached while it was open.
+    fn prob_max(&self) -> Result<f32, MarketConvertError> {
+        Ok(self.prob_extremes()?.1)
+    }
+
+    /// Get the standard deviation of the daily probabilities, as a measure of how much the market moved.
+    fn prob_daily_stddev(&self) -> Result<f32, MarketConvertError> {
+        let probs: Vec<f32> = self
+            .prob_each_day()?
+            .into_iter()
+            .map(|(_, prob)| prob)
+            .collect();
+        if probs.is_empty() {
+            return Ok(0.0);
+        }
+        let mean = probs.iter().sum::<f32>() / probs.len() as f32;
+        let variance =
+            probs.iter().map(|prob| (prob - mean).powi(2)).sum::<f32>() / probs.len() as f32;
+        Ok(variance.sqrt())
+    }
+
+    /// Get the number of times the daily probability crossed from one side of 50% to the other.
+    /// Days exactly at 50% don't count as a side, so moving from 40% to 50% to 60% is one crossing.
+    fn prob_midpoint_crossings(&self) -> Result<i32, MarketConvertError> {
+        let mut crossings = 0;
+        let mut last_side: Option<bool> = None;
+        for (_, prob) in self.prob_each_day()? {
+            if prob == 0.5 {
+                continue;
+            }
+            let side = prob > 0.5;
+            if last_side.is_some_and(|last_side| last_side != side) {
+                crossings += 1;
+            }
+            last_side = Some(side);
+        }
+        Ok(crossings)
+    }
+
+    /// Get the number of days from open until the market became confident and stayed that way.
+    /// Confident means at or above the threshold, or at or below its complement, on the same side
+    /// until close. Returns None if the market was not confident when it closed.
+    fn days_to_confidence(&self) -> Result<Option<f32>, MarketConvertError> {
+        let side = |prob: f32| {
+            if prob >= CONFIDENCE_THRESHOLD {
+                Some(true)
+            } else if prob <= 1.0 - CONFIDENCE_THRESHOLD {
+                Some(false)
+            } else {
+                None
+            }
+        };
+        let (open_dt, close_dt) = (self.open_dt()?, self.close_dt()?);
+        // the side the market is confident on and when that started
+        let mut streak = side(self.prob_at_time(open_dt)?).map(|side| (side, open_dt));
+        for event in self
+            .events()
+            .iter()
+            .filter(|event| open_dt < event.time && event.time <= close_dt)
+        {
+            streak = match (streak, side(event.prob)) {
+                (Some((current, start)), Some(new)) if current == new => Some((current, start)),
+                (_, Some(new)) => Some((new, event.time)),
+                (_, None) => None,
+            };
+        }
+        Ok(streak.map(|(_, start)| (start - open_dt).num_seconds() as f32 / SECS_PER_DAY))
+    }
+
+    /// Get the start of each interval the market was open, in order.
+    /// The first and last intervals contain the market open and close, so they may be partial.
+    fn market_intervals(
+        &self,
+        interval: ProbInterval,
+    ) -> Result<Vec<DateTime<Utc>>, MarketConvertError> {
+        let bucket_start = |time: DateTime<Utc>| {
+            interval.bucket_start(time).ok_or(MarketConvertError {
+                data: self.debug(),
+                message: format!("General: Could not get the start of {interval:?} {time}."),
+                level: 4,
+            })
+        };
+        let market_start = bucket_start(self.open_dt()?)?;
+        let market_end = bucket_start(self.close_dt()?)?;
+
+        let step = interval.duration();
+        let num_intervals = ((market_end - market_start).num_seconds() / step.num_seconds()) as i32;
+        Ok((0..=num_intervals)
+            .map(|i| market_start + step * i)
+            .collect())
+    }
+
+    /// Get the start of each day the market was open, in order.
+    fn market_days(&self) -> Result<Vec<DateTime<Utc>>, MarketConvertError> {
+        self.market_intervals(ProbInterval::Day)
+    }
+
+    /// Get the time-averaged probability throughout each interval the market was open, in order.
+    fn prob_each_interval(
+        &self,
+        interval: ProbInterval,
+    ) -> Result<Vec<(DateTime<Utc>, f32)>, MarketConvertError> {
+        self.market_intervals(interval)?
+            .into_iter()
+            .map(|interval_start| {
+                let interval_end = interval_start + interval.duration();
+                Ok((
+                    interval_start,
+                    self.prob_time_avg_between(interval_start, interval_end)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Get the time-averaged probability throughout each day the market was open, in order.
+    fn prob_each_day(&self) -> Result<Vec<(DateTime<Utc>, f32)>, MarketConvertError> {
+        self.prob_each_interval(ProbInterval::Day)
+    }
+
+    /// Get a map of the market probability on each hour the market was open, for short markets
+    /// where the daily series loses most of the signal. The key is the timestamp at the start
+    /// of the hour (UTC) and the value is the time-averaged probability throughout the hour.
+    /// This is empty unless the market was open no longer than the `--hourly-max-days` option.
+    fn prob_each_hour_map(&self) -> Result<serde_json::Value, MarketConvertError> {
+        match self.options().hourly_max_days {
+            Some(max_days) if self.open_days()? <= max_days => {
+                let result: HashMap<DateTime<Utc>, f32> = self
+                    .prob_each_interval(ProbInterval::Hour)?
+                    .into_iter()
+                    .collect();
+                Ok(serde_json::json!(result))
+            }
+            _ => Ok(serde_json::json!({})),
+        }
+    }
+
     /// Get a map of the market probability on each day the market was open.
     /// The key is the timestamp at the start of the day (UTC) and the value is
     /// the time-averaged probability throughout the day.
+    /// This is empty when the probabilities are stored packed instead.
     fn prob_each_date_map(&self) -> Result<serde_json::Value, MarketConvertError> {
-        // Ensure both dates are at the start of their day, including seconds
-        let market_start_morning: DateTime<Utc> =
-            match self.open_dt()?.date_naive().and_hms_milli_opt(0, 0, 0, 0) {
-                Some(dt) => dt.and_utc(),
-                None => {
-                    return Err(MarketConvertError {
-                        data: self.debug(),
-                        message: format!(
-                            "General: Could not get the start of day {}.",
-                            self.open_dt()?
-                        ),
-                        level: 4,
-                    })
-                }
-            };
-        let market_end_morning: DateTime<Utc> =
-            match self.close_dt()?.date_naive().and_hms_milli_opt(0, 0, 0, 0) {
-                Some(dt) => dt.and_utc(),
-                None => {
-                    return Err(MarketConvertError {
-                        data: self.debug(),
-                        message: format!(
-                            "General: Could not get the start of day {}.",
-                            self.close_dt()?
-                        ),
-                        level: 4,
-                    })
-                }
-            };
+        if self.options().prob_storage == ProbStorage::Packed {
+            return Ok(serde_json::json!({}));
+        }
+        let result: HashMap<DateTime<Utc>, f32> = self.prob_each_day()?.into_iter().collect();
+        Ok(serde_json::json!(result))
+    }
+
+    /// Get the first day of `prob_daily_list`, if the probabilities are stored packed.
+    fn prob_daily_start_dt(&self) -> Result<Option<DateTime<Utc>>, MarketConvertError> {
+        if self.options().prob_storage == ProbStorage::Json {
+            return Ok(None);
+        }
+        Ok(self.market_days()?.first().copied())
+    }
+
+    /// Get the market probability on each consecutive day the market was open,
+    /// starting from `prob_daily_start_dt`. This holds the same values as `prob_each_date_map`
+    /// in a fraction of the space, and is empty unless the probabilities are stored packed.
+    fn prob_daily_list(&self) -> Result<Vec<f32>, MarketConvertError> {
+        if self.options().prob_storage == ProbStorage::Json {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .prob_each_day()?
+            .into_iter()
+            .map(|(_, prob)| prob)
+            .collect())
+    }
+
+    /// Get a map of whether the market had any activity on each day the market was open.
+    /// The keys match `prob_each_date_map`, and a day is active if the probability
+    /// changed at least once during it. Inactive days carry forward a stale probability.
+    fn active_each_date_map(&self) -> Result<serde_json::Value, MarketConvertError> {
+        let events = self.events();
+        let market_days = self.market_days()?;
+        let mut result: HashMap<DateTime<Utc>, bool> = HashMap::with_capacity(market_days.len());
+        for date_start in market_days {
+            let date_end = date_start + Duration::days(1);
+            let active = events
+                .iter()
+                .any(|event| event.time >= date_start && event.time < date_end);
+            result.insert(date_start, active);
+        }
+        Ok(serde_json::json!(result))
+    }
 
-        // Calculate the number of days between the two dates
-        let market_open_days = (market_end_morning - market_start_morning).num_days() as usize;
+    /// Get a map of the volume traded on each day the market was open, in USD.
+    /// The keys match `prob_each_date_map`. This is empty if the platform doesn't
+    /// report per-trade sizes, so a missing day is different from a day with no trades.
+    fn volume_each_date_map(&self) -> Result<serde_json::Value, MarketConvertError> {
+        let events = self.volume_events();
+        if events.is_empty() {
+            return Ok(serde_json::json!({}));
+        }
+        let market_days = self.market_days()?;
+        let mut result: HashMap<DateTime<Utc>, f32> = HashMap::with_capacity(market_days.len());
+        for date_start in market_days {
+            let date_end = date_start + Duration::days(1);
+            let volume_over_day = events
+                .iter()
+                .filter(|event| event.time >= date_start && event.time < date_end)
+                .map(|event| event.volume_usd)
+                .sum();
+            result.insert(date_start, volume_over_day);
+        }
+        Ok(serde_json::json!(result))
+    }
+
+    /// Get a map of the implied uncertainty in the probability on each day the market was open.
+    /// Each value is how far a standard-size bet would have moved the price at the end of the day,
+    /// so thin markets get wide bands and deep ones get narrow bands.
+    /// The keys match `prob_each_date_map`, and days before the first trade are skipped.
+    /// This is empty if the platform doesn't report price impact.
+    fn prob_band_each_date_map(&self) -> Result<serde_json::Value, MarketConvertError> {
+        let events = self.impact_events();
+        let market_days = self.market_days()?;
+        let mut result: HashMap<DateTime<Utc>, f32> = HashMap::new();
+        for date_start in market_days {
+            let date_end = date_start + Duration::days(1);
+            if let Some(event) = events.iter().rfind(|event| event.time < date_end) {
+                let band = (event.prob_change_per_usd * STANDARD_BET_USD).min(0.5);
+                result.insert(date_start, band);
+            }
+        }
+        Ok(serde_json::json!(result))
+    }
 
-        // Generate a vector of DateTime<Utc> for each day, including seconds
-        let mut result: HashMap<DateTime<Utc>, f32> = HashMap::with_capacity(market_open_days);
-        for i in 0..=market_open_days {
-            let date_start = market_start_morning + Duration::days(i as i64);
+    /// Get a map of the forecast quartiles at the end of each day the market was open.
+    /// The keys match `prob_each_date_map`, and days before the first forecast are skipped.
+    /// This is empty for binary questions.
+    fn quantiles_each_date_map(&self) -> Result<serde_json::Value, MarketConvertError> {
+        let events = self.quantile_events();
+        let market_days = self.market_days()?;
+        let mut result: HashMap<DateTime<Utc>, [f32; 3]> =
+            HashMap::with_capacity(market_days.len());
+        for date_start in market_days {
             let date_end = date_start + Duration::days(1);
-            let prob_over_day = self.prob_time_avg_between(date_start, date_end)?;
-            result.insert(date_start, prob_over_day);
+            if let Some(event) = events.iter().rfind(|event| event.time < date_end) {
+                result.insert(date_start, event.quartiles);
+            }
         }
         Ok(serde_json::json!(result))
     }
 }
 
-fn save_markets(markets: Vec<MarketStandard>, method: OutputMethod) {
+/// A batch of rows waiting to be written to the database.
+enum SaveTask {
+    Markets(Vec<MarketStandard>),
+    TraderBets(Vec<TraderBets>),
+}
+impl SaveTask {
+    /// Write the batch, reusing the connection if it's still open.
+    fn save(self, config: &RunConfig, conn: &mut Option<PgConnection>) {
+        match self {
+            SaveTask::Markets(markets) => save_markets_to_database(config, &markets, conn),
+            SaveTask::TraderBets(bets) => save_trader_bets_to_database(config, &bets, conn),
+        }
+    }
+}
+
+/// The channel to the save worker and the thread itself, so the run can wait for it to finish.
+#[derive(Debug)]
+struct SaveWorker {
+    sender: SyncSender<SaveTask>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+/// Start saving batches on a background thread, so platforms can keep downloading and
+/// converting markets while earlier batches are written. The queue is bounded, so a slow
+/// database makes platforms wait instead of holding every converted market in memory.
+pub fn start_save_worker(config: &Arc<RunConfig>) {
+    let (sender, receiver) = sync_channel::<SaveTask>(SAVE_QUEUE_LENGTH);
+    let worker_config = Arc::clone(config);
+    let thread = std::thread::spawn(move || {
+        let mut conn = None;
+        for task in receiver {
+            task.save(&worker_config, &mut conn);
+        }
+    });
+    *config.save_worker.lock().unwrap() = Some(SaveWorker { sender, thread });
+}
+
+/// Wait for the save worker to write every queued batch and stop it.
+/// Returns an error if it stopped early because a batch couldn't be saved.
+pub fn finish_save_worker(config: &RunConfig) -> Result<(), String> {
+    let Some(worker) = config.save_worker.lock().unwrap().take() else {
+        return Ok(());
+    };
+    drop(worker.sender);
+    worker.thread.join().map_err(|e| {
+        let message = e
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()));
+        format!(
+            "save worker failed: {}",
+            message.unwrap_or_else(|| "unknown error".to_string())
+        )
+    })
+}
+
+/// Send a batch to the save worker if one is running, or save it now otherwise.
+fn queue_save(config: &RunConfig, task: SaveTask) {
+    let sender = config
+        .save_worker
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|worker| worker.sender.clone());
+    match sender {
+        Some(sender) => sender
+            .send(task)
+            .expect("Save worker stopped, see the error above."),
+        None => task.save(config, &mut None),
+    }
+}
+
+fn save_markets(config: &RunConfig, markets: Vec<MarketStandard>, method: OutputMethod) {
+    let markets: Vec<MarketStandard> = markets
+        .into_iter()
+        .filter(|market| !is_excluded(config, market))
+        .collect();
     match method {
-        OutputMethod::Database => {
-            use crate::platforms::market::dsl::*;
-            let mut conn = PgConnection::establish(
+        OutputMethod::Database => queue_save(config, SaveTask::Markets(markets)),
+        OutputMethod::Stdout => {
+            let output = MarketOutput {
+                schema_version: SCHEMA_VERSION,
+                markets: &markets,
+            };
+            println!("{}", to_string_pretty(&output).unwrap())
+        }
+        OutputMethod::Null => (),
+        OutputMethod::Check => {
+            let violations: Vec<String> = markets.iter().flat_map(check_contract).collect();
+            if !violations.is_empty() {
+                panic!("Platform contract violated: {}", violations.join("; "));
+            }
+        }
+    }
+}
+
+/// Save markets in batches of 1000, spilling any batch that fails every attempt.
+fn save_markets_to_database(
+    config: &RunConfig,
+    markets: &[MarketStandard],
+    conn: &mut Option<PgConnection>,
+) {
+    for chunk in markets.chunks(1000) {
+        if let Err(e) = save_with_retries(config, "markets", conn, |conn| {
+            conn.transaction(|conn| save_market_chunk(conn, &config.run_id, chunk))
+        }) {
+            spill_markets(config, chunk, &e);
+        }
+    }
+}
+
+/// Log any material changes to a batch of markets, then insert or update them.
+/// This should run in a transaction so a retried batch doesn't log its changes twice.
+fn save_market_chunk(
+    conn: &mut PgConnection,
+    run_id: &str,
+    chunk: &[MarketStandard],
+) -> QueryResult<()> {
+    use crate::platforms::market::dsl::*;
+    let changes = get_market_changes(conn, run_id, chunk)?;
+    if !changes.is_empty() {
+        diesel::insert_into(market_change::table)
+            .values(&changes)
+            .execute(conn)?;
+    }
+    diesel::insert_into(market)
+        .values(chunk)
+        .on_conflict((platform, platform_id))
+        .do_update()
+        .set((
+            url.eq(excluded(url)),
+            open_dt.eq(excluded(open_dt)),
+            close_dt.eq(excluded(close_dt)),
+            trading_end_dt.eq(excluded(trading_end_dt)),
+            resolved_dt.eq(excluded(resolved_dt)),
+            open_days.eq(excluded(open_days)),
+            volume_usd.eq(excluded(volume_usd)),
+            num_traders.eq(excluded(num_traders)),
+            category.eq(excluded(category)),
+            region.eq(excluded(region)),
+            description.eq(excluded(description)),
+            description_excerpt.eq(excluded(description_excerpt)),
+            image_url.eq(excluded(image_url)),
+            prob_at_midpoint.eq(excluded(prob_at_midpoint)),
+            prob_at_close.eq(excluded(prob_at_close)),
+            prob_each_pct.eq(excluded(prob_each_pct)),
+            prob_each_date.eq(excluded(prob_each_date)),
+            prob_each_hour.eq(excluded(prob_each_hour)),
+            prob_daily_start_dt.eq(excluded(prob_daily_start_dt)),
+            prob_daily.eq(excluded(prob_daily)),
+            active_each_date.eq(excluded(active_each_date)),
+            volume_each_date.eq(excluded(volume_each_date)),
+            prob_band_each_date.eq(excluded(prob_band_each_date)),
+            prob_time_avg.eq(excluded(prob_time_avg)),
+            prob_min.eq(excluded(prob_min)),
+            prob_max.eq(excluded(prob_max)),
+            prob_daily_stddev.eq(excluded(prob_daily_stddev)),
+            prob_midpoint_crossings.eq(excluded(prob_midpoint_crossings)),
+            days_to_confidence.eq(excluded(days_to_confidence)),
+            resolution.eq(excluded(resolution)),
+            question_type.eq(excluded(question_type)),
+            quantiles_each_date.eq(excluded(quantiles_each_date)),
+            linked_group.eq(excluded(linked_group)),
+            linked_order.eq(excluded(linked_order)),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Run a database write, retrying with exponential backoff if it fails.
+/// The connection is opened on first use and reopened after a failure, since a dropped
+/// connection is the most common transient error. Returns the last error if every attempt fails.
+fn save_with_retries<T>(
+    config: &RunConfig,
+    label: &str,
+    conn: &mut Option<PgConnection>,
+    mut write: impl FnMut(&mut PgConnection) -> QueryResult<T>,
+) -> Result<T, String> {
+    let attempts = config.save_attempts.max(1);
+    let mut delay = SAVE_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let result = match conn.take() {
+            Some(existing) => Ok(existing),
+            None => PgConnection::establish(
                 &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
             )
-            .expect("Error connecting to datbase.");
-            for chunk in markets.chunks(1000) {
-                diesel::insert_into(market)
-                    .values(chunk)
-                    .on_conflict((platform, platform_id))
-                    .do_update()
-                    .set((
-                        url.eq(excluded(url)),
-                        open_dt.eq(excluded(open_dt)),
-                        close_dt.eq(excluded(close_dt)),
-                        open_days.eq(excluded(open_days)),
-                        volume_usd.eq(excluded(volume_usd)),
-                        num_traders.eq(excluded(num_traders)),
-                        category.eq(excluded(category)),
-                        prob_at_midpoint.eq(excluded(prob_at_midpoint)),
-                        prob_at_close.eq(excluded(prob_at_close)),
-                        prob_each_pct.eq(excluded(prob_each_pct)),
-                        prob_each_date.eq(excluded(prob_each_date)),
-                        prob_time_avg.eq(excluded(prob_time_avg)),
-                        resolution.eq(excluded(resolution)),
-                    ))
-                    .execute(&mut conn)
-                    .expect("Failed to insert rows into table.");
-            }
+            .map_err(|e| format!("failed to connect to database: {e}")),
         }
-        OutputMethod::Stdout => {
-            println!("{}", to_string_pretty(&markets).unwrap())
+        .and_then(|mut existing| {
+            let result = write(&mut existing).map_err(|e| e.to_string());
+            if result.is_ok() {
+                *conn = Some(existing);
+            }
+            result
+        });
+        match result {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= attempts => return Err(e),
+            Err(e) => {
+                eprintln!(
+                    "Save: Failed to save {label} (attempt {attempt} of {attempts}), retrying in {delay:?}: {e}"
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
         }
-        OutputMethod::Null => (),
+    }
+}
+
+/// Write a batch of markets that couldn't be saved to the spill directory, so it can be
+/// saved later with `--replay` instead of losing the rest of the run.
+/// Without a spill directory, this stops the run like any other database error.
+fn spill_markets(config: &RunConfig, markets: &[MarketStandard], error: &str) {
+    let Some(dir) = &config.spill_dir else {
+        panic!("Failed to insert rows into table: {error}");
+    };
+    let index = config.stats.spilled.fetch_add(1, AtomicOrdering::Relaxed);
+    let path = std::path::Path::new(dir).join(format!("markets-{}-{index}.json", config.run_id));
+    let output = MarketOutput {
+        schema_version: SCHEMA_VERSION,
+        markets,
+    };
+    if let Err(e) = std::fs::write(&path, to_string_pretty(&output).unwrap()) {
+        panic!(
+            "Failed to insert rows into table: {error}; also failed to spill them to {}: {e}",
+            path.display()
+        );
+    }
+    eprintln!(
+        "Save: Spilled {} markets to {} after error: {error}",
+        markets.len(),
+        path.display()
+    );
+}
+
+/// Save a batch of markets spilled by an earlier run and return how many there were.
+/// The file is removed once its markets are saved, or spilled again under a new name.
+pub fn replay_spilled_markets(config: &RunConfig, path: &str) -> Result<usize, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))?;
+    let input: MarketInput =
+        serde_json::from_str(&contents).map_err(|e| format!("failed to parse {path}: {e}"))?;
+    if input.schema_version != SCHEMA_VERSION {
+        return Err(format!(
+            "{path} has schema version {} but this build saves version {SCHEMA_VERSION}",
+            input.schema_version
+        ));
+    }
+    let count = input.markets.len();
+    save_markets(config, input.markets, OutputMethod::Database);
+    std::fs::remove_file(path).map_err(|e| format!("failed to remove {path}: {e}"))?;
+    Ok(count)
+}
+
+/// Save each trader's bets, replacing any saved for the same trader and market.
+/// Only database runs save them, since they aren't part of the market format.
+fn save_trader_bets(config: &RunConfig, bets: Vec<TraderBets>, method: OutputMethod) {
+    if method != OutputMethod::Database || bets.is_empty() {
+        return;
+    }
+    queue_save(config, SaveTask::TraderBets(bets));
+}
+
+/// Save trader bets in batches of 1000, stopping the run if a batch fails every attempt.
+fn save_trader_bets_to_database(
+    config: &RunConfig,
+    bets: &[TraderBets],
+    conn: &mut Option<PgConnection>,
+) {
+    use crate::platforms::trader_bet::dsl::*;
+    for chunk in bets.chunks(1000) {
+        save_with_retries(config, "trader bets", conn, |conn| {
+            diesel::insert_into(trader_bet)
+                .values(chunk)
+                .on_conflict((platform, platform_id, trader_id))
+                .do_update()
+                .set((
+                    bet_probs.eq(excluded(bet_probs)),
+                    last_bet_dt.eq(excluded(last_bet_dt)),
+                ))
+                .execute(conn)
+        })
+        .expect("Failed to insert rows into trader bet table.");
+    }
+}
+
+/// List the ways a converted market breaks the contract declared by its platform.
+/// Optional capabilities are only checked when the platform claims to have them.
+fn check_contract(market: &MarketStandard) -> Vec<String> {
+    let Ok(platform) = Platform::from_str(&market.platform, true) else {
+        return vec![format!("{}: unknown platform", market.platform)];
+    };
+    let contract = platform.contract();
+    let id = &market.platform_id;
+    let is_empty = |value: &serde_json::Value| value.as_object().iter().all(|map| map.is_empty());
+    let mut violations = Vec::new();
+    if !(0.0..=1.0).contains(&market.resolution) {
+        violations.push(format!(
+            "{id}: resolution {} out of bounds",
+            market.resolution
+        ));
+    }
+    if market.prob_each_pct.is_empty()
+        || (is_empty(&market.prob_each_date) && market.prob_daily.is_empty())
+    {
+        violations.push(format!("{id}: no timestamped probabilities"));
+    }
+    if contract.resolved_dt && market.resolved_dt.is_none() {
+        violations.push(format!("{id}: missing resolved_dt"));
+    }
+    if contract.num_traders && market.num_traders <= 0 {
+        violations.push(format!("{id}: missing num_traders"));
+    }
+    if contract.daily_volume && is_empty(&market.volume_each_date) {
+        violations.push(format!("{id}: missing daily volume"));
+    }
+    if contract.price_impact && is_empty(&market.prob_band_each_date) {
+        violations.push(format!("{id}: missing price impact"));
+    }
+    violations
+}
+
+/// Record each platform's contract in the platform table so the site can show what is comparable.
+pub fn save_platform_contracts(platforms: &[Platform]) {
+    use crate::platforms::platform::dsl;
+    let mut conn = PgConnection::establish(
+        &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
+    )
+    .expect("Error connecting to database.");
+    for platform in platforms {
+        diesel::update(platform::table.find(format!("{:?}", platform).to_lowercase()))
+            .set(dsl::capabilities.eq(serde_json::json!(platform.contract())))
+            .execute(&mut conn)
+            .expect("Failed to update platform capabilities.");
     }
 }
 
 /// Basic error type that returns the market as a debug string and a simple error message.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct MarketConvertError {
     data: String,
     message: String,
@@ -424,6 +1502,226 @@ impl fmt::Display for MarketConvertError {
         write!(f, "{}: {}", self.message, self.data)
     }
 }
+impl std::error::Error for MarketConvertError {}
+
+/// Load the list of markets to skip from a YAML file, keyed by platform and ID.
+pub fn load_exclusions(path: &str) -> Result<HashMap<(String, String), String>, String> {
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("failed to open exclusion list: {e}"))?;
+    let exclusions: Vec<Exclusion> = serde_yaml::from_reader(file)
+        .map_err(|e| format!("failed to parse exclusion list: {e}"))?;
+    Ok(exclusions
+        .into_iter()
+        .map(|e| ((e.platform, e.platform_id), e.reason))
+        .collect())
+}
+
+/// Check whether a market is on the exclusion list, counting it by reason if so.
+fn is_excluded(config: &RunConfig, market: &MarketStandard) -> bool {
+    let Some(reason) = config
+        .exclusions
+        .get(&(market.platform.clone(), market.platform_id.clone()))
+    else {
+        return false;
+    };
+    *config
+        .stats
+        .excluded
+        .lock()
+        .unwrap()
+        .entry(reason.clone())
+        .or_default() += 1;
+    true
+}
+
+/// Turn a platform user ID into an anonymous trader ID.
+/// The ID is an HMAC-SHA256 of the user ID keyed with the salt, so without the salt it can't
+/// be reversed or computed for other users, even from a known user and ID pair.
+/// The same user always gets the same ID as long as the salt doesn't change.
+fn get_trader_id(salt: &str, user_id: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any length.");
+    mac.update(user_id.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Delete trader bets and scores saved under the old trader IDs, which were an unkeyed hash
+/// that could be reversed to match every trader to their public user ID.
+/// Current IDs are 64 hex characters, so anything else is from the old scheme.
+/// Returns the number of rows that were deleted.
+pub fn remove_legacy_trader_ids() -> Result<usize, String> {
+    let mut conn = PgConnection::establish(
+        &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
+    )
+    .expect("Error connecting to database.");
+    let mut count = 0;
+    for table in ["trader_bet", "trader_score"] {
+        count += diesel::sql_query(format!(
+            "DELETE FROM {table} WHERE length(trader_id) <> {TRADER_ID_LENGTH}"
+        ))
+        .execute(&mut conn)
+        .map_err(|e| format!("failed to remove old trader IDs from {table}: {e}"))?;
+    }
+    Ok(count)
+}
+
+/// Print how many markets were skipped for each exclusion reason this run.
+pub fn report_exclusions(config: &RunConfig) {
+    let excluded = config.stats.excluded.lock().unwrap();
+    if excluded.is_empty() {
+        return;
+    }
+    println!(
+        "Skipped {} excluded markets:",
+        excluded.values().sum::<usize>()
+    );
+    for (reason, count) in excluded.iter() {
+        println!("    {} ({} markets)", reason, count);
+    }
+}
+
+/// Create the directory that batches which can't be saved are written to, if it's missing.
+pub fn create_spill_dir(dir: &str) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("failed to create spill directory: {e}"))
+}
+
+/// Print how many batches were spilled this run and where to find them.
+/// Returns a problem for the job if any were, since those markets still need to be replayed.
+pub fn report_spills(config: &RunConfig) -> Vec<String> {
+    let spilled = config.stats.spilled.load(AtomicOrdering::Relaxed);
+    let Some(dir) = config.spill_dir.as_ref().filter(|_| spilled > 0) else {
+        return Vec::new();
+    };
+    let problem = format!("{spilled} batches of markets could not be saved and were spilled to {dir}, save them with --replay");
+    println!("{problem}");
+    vec![problem]
+}
+
+/// Record the start of a pipeline stage in the job table and return its ID.
+/// Returns an error if a job for the same stage is already running,
+/// which the database enforces so overlapping runs can't clobber each other.
+pub fn start_job(stage: &str, params: serde_json::Value) -> Result<i32, String> {
+    use crate::platforms::job::dsl;
+    let mut conn = PgConnection::establish(
+        &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
+    )
+    .expect("Error connecting to database.");
+    diesel::insert_into(job::table)
+        .values(NewJob {
+            stage: stage.to_string(),
+            params,
+            status: "running".to_string(),
+        })
+        .returning(dsl::id)
+        .get_result(&mut conn)
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => format!("another {stage} job is already running"),
+            e => format!("failed to record {stage} job: {e}"),
+        })
+}
+
+/// Mark every running job for a pipeline stage as failed, to clear one left behind by a run
+/// that was killed before it could finish. Returns the number of jobs that were cleared.
+pub fn clear_running_jobs(stage: &str) -> Result<usize, String> {
+    use crate::platforms::job::dsl;
+    let mut conn = PgConnection::establish(
+        &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
+    )
+    .expect("Error connecting to database.");
+    diesel::update(
+        job::table
+            .filter(dsl::stage.eq(stage))
+            .filter(dsl::status.eq("running")),
+    )
+    .set((
+        dsl::status.eq("failed"),
+        dsl::finished_dt.eq(Some(Utc::now())),
+        dsl::error.eq(Some("cleared by a later run with --force")),
+    ))
+    .execute(&mut conn)
+    .map_err(|e| format!("failed to clear running {stage} jobs: {e}"))
+}
+
+/// Mark a pipeline stage as finished in the job table, with the error if it failed.
+/// A stage that finished with problems, like platforms whose API likely changed,
+/// is marked degraded with those problems saved as the error.
+pub fn finish_job(job_id: i32, error: Option<String>, problems: Vec<String>) {
+    use crate::platforms::job::dsl;
+    let mut conn = PgConnection::establish(
+        &var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set."),
+    )
+    .expect("Error connecting to database.");
+    let (status, error) = match error {
+        Some(error) => ("failed", Some(error)),
+        None if !problems.is_empty() => ("degraded", Some(problems.join("\n"))),
+        None => ("complete", None),
+    };
+    diesel::update(job::table.find(job_id))
+        .set((
+            dsl::status.eq(status),
+            dsl::finished_dt.eq(Some(Utc::now())),
+            dsl::error.eq(error),
+        ))
+        .execute(&mut conn)
+        .expect("Failed to update job status.");
+}
+
+/// Compare markets about to be saved against the stored versions and list any material changes.
+/// This catches platforms retroactively editing resolutions or volume.
+fn get_market_changes(
+    conn: &mut PgConnection,
+    run_id: &str,
+    markets: &[MarketStandard],
+) -> QueryResult<Vec<MarketChange>> {
+    let ids: Vec<&String> = markets.iter().map(|m| &m.platform_id).collect();
+    let existing: HashMap<(String, String), (f32, f32)> = market::table
+        .filter(market::platform_id.eq_any(ids))
+        .select((
+            market::platform,
+            market::platform_id,
+            market::resolution,
+            market::volume_usd,
+        ))
+        .load::<(String, String, f32, f32)>(conn)?
+        .into_iter()
+        .map(|(p, id, res, vol)| ((p, id), (res, vol)))
+        .collect();
+
+    let mut changes = Vec::new();
+    for new in markets {
+        let Some((old_resolution, old_volume)) =
+            existing.get(&(new.platform.clone(), new.platform_id.clone()))
+        else {
+            continue;
+        };
+        let mut log_change = |field: &str, old_value: f32, new_value: f32| {
+            changes.push(MarketChange {
+                run_id: run_id.to_string(),
+                platform: new.platform.clone(),
+                platform_id: new.platform_id.clone(),
+                field: field.to_string(),
+                old_value,
+                new_value,
+            })
+        };
+        if *old_resolution != new.resolution {
+            log_change("resolution", *old_resolution, new.resolution);
+        }
+        if *old_volume > 0.0
+            && ((new.volume_usd - old_volume) / old_volume).abs() > VOLUME_CHANGE_THRESHOLD
+        {
+            log_change("volume_usd", *old_volume, new.volume_usd);
+        }
+    }
+    Ok(changes)
+}
 
 /// A default API client with middleware to ratelimit and retry on failure.
 /// If no period is supplied, the rate limit is per second.
@@ -449,6 +1747,7 @@ fn get_reqwest_client_ratelimited(
 }
 
 async fn send_request<T: for<'de> serde::Deserialize<'de>>(
+    stats: &RunStats,
     req: reqwest_middleware::RequestBuilder,
 ) -> Result<T, MarketConvertError> {
     // this only panics if the body is a stream (we don't use streams)
@@ -456,6 +1755,7 @@ async fn send_request<T: for<'de> serde::Deserialize<'de>>(
     let final_url = cloned_req.url();
 
     // send the request
+    let download_timer = std::time::Instant::now();
     let response = match req.send().await {
         Ok(r) => Ok(r),
         Err(e) => Err(MarketConvertError {
@@ -482,14 +1782,228 @@ async fn send_request<T: for<'de> serde::Deserialize<'de>>(
         });
     }
 
-    // parse the text as json
-    serde_json::from_str(&response_text).map_err(|e| MarketConvertError {
-        data: response_text.to_owned(),
-        message: format!("Failed to deserialize: {e}."),
-        level: 4,
+    let download_time = download_timer.elapsed();
+
+    // parse the text as json, noting any fields our types don't have
+    let type_name = std::any::type_name::<T>();
+    let deserialize_timer = std::time::Instant::now();
+    let deserializer = &mut serde_json::Deserializer::from_str(&response_text);
+    let mut on_ignored = |path: serde_ignored::Path| {
+        record_unrecognized(
+            stats,
+            format!("{} field {}", type_name, format_ignored_path(&path)),
+        )
+    };
+    let deserializer = serde_ignored::Deserializer::new(deserializer, &mut on_ignored);
+    let result = serde_path_to_error::deserialize(deserializer);
+    let result = match result {
+        Ok(value) => {
+            record_response_health(stats, type_name, |health| health.responses += 1);
+            Ok(value)
+        }
+        Err(e) => {
+            // only show the first failure at each field, the rest are summarized at the end
+            let failure = format!("{} at {}: {}", type_name, e.path(), e.inner());
+            let mut first_seen = false;
+            record_response_health(stats, type_name, |health| {
+                health.responses += 1;
+                let count = health.failures.entry(failure.clone()).or_default();
+                first_seen = *count == 0;
+                *count += 1;
+                health.example.get_or_insert_with(|| {
+                    response_text.chars().take(DIAGNOSIS_SNIPPET_LEN).collect()
+                });
+            });
+            Err(MarketConvertError {
+                data: response_text.to_owned(),
+                message: format!("Failed to deserialize {failure}."),
+                level: if first_seen { 3 } else { 0 },
+            })
+        }
+    };
+    record_bench(stats, type_name, |bench| {
+        bench.bytes += response_text.len();
+        bench.download += download_time;
+        bench.deserialize += deserialize_timer.elapsed();
+    });
+    result
+}
+
+/// Parse a bundle of saved API responses for `standardize_bytes`.
+/// Unlike a live response this isn't counted towards the response health of the run.
+fn parse_bundle<T: for<'de> serde::Deserialize<'de>>(
+    platform_name: &str,
+    bytes: &[u8],
+) -> Result<T, MarketConvertError> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| MarketConvertError {
+        data: String::from_utf8_lossy(bytes)
+            .chars()
+            .take(DIAGNOSIS_SNIPPET_LEN)
+            .collect(),
+        message: format!(
+            "{platform_name}: Failed to deserialize market bundle at {}: {}",
+            e.path(),
+            e.inner()
+        ),
+        level: 3,
     })
 }
 
+/// Format a path to an ignored field, collapsing list indices so each field is only counted once.
+fn format_ignored_path(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, .. } => format_ignored_path(parent) + "[]",
+        serde_ignored::Path::Map { parent, key } => match parent {
+            serde_ignored::Path::Root => key.to_owned(),
+            _ => format!("{}.{}", format_ignored_path(parent), key),
+        },
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => format_ignored_path(parent),
+    }
+}
+
+/// Note a field or value from a platform API that we don't recognize.
+fn record_unrecognized(stats: &RunStats, item: String) {
+    *stats.unrecognized.lock().unwrap().entry(item).or_default() += 1;
+}
+
+/// Note an enum-like value that isn't in the list we know about.
+/// These are strings in our types so new values don't fail to deserialize, but
+/// they would otherwise be silently filtered out.
+fn check_known_value(stats: &RunStats, source: &str, field: &str, value: &str, known: &[&str]) {
+    if !known.contains(&value) {
+        record_unrecognized(stats, format!("{} value {} = {}", source, field, value));
+    }
+}
+
+/// Get the lowercase platform name that a response or market type belongs to.
+fn get_type_platform(type_name: &str) -> String {
+    type_name
+        .split("platforms::")
+        .nth(1)
+        .and_then(|path| path.split("::").next())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Add to the bench stats for the platform that a response or market type belongs to.
+fn record_bench(stats: &RunStats, type_name: &str, update: impl FnOnce(&mut BenchStats)) {
+    update(
+        stats
+            .bench
+            .lock()
+            .unwrap()
+            .entry(get_type_platform(type_name))
+            .or_default(),
+    );
+}
+
+/// Add to the deserialization results for the platform that a response type belongs to.
+fn record_response_health(
+    stats: &RunStats,
+    type_name: &str,
+    update: impl FnOnce(&mut ResponseHealth),
+) {
+    update(
+        stats
+            .response_health
+            .lock()
+            .unwrap()
+            .entry(get_type_platform(type_name))
+            .or_default(),
+    );
+}
+
+/// Check whether any responses from a platform failed to deserialize this run.
+pub fn has_deserialize_failures(stats: &RunStats, platform: Platform) -> bool {
+    let name = format!("{:?}", platform).to_lowercase();
+    stats
+        .response_health
+        .lock()
+        .unwrap()
+        .get(&name)
+        .is_some_and(|health| !health.failures.is_empty())
+}
+
+/// Print a single diagnosis for each platform where responses failed to deserialize,
+/// with the most common failing fields and an example payload.
+/// Returns the diagnoses for platforms over `DESERIALIZE_FAILURE_THRESHOLD`, which
+/// likely changed their API and leave the run degraded.
+pub fn report_response_health(stats: &RunStats) -> Vec<String> {
+    let mut degraded = Vec::new();
+    for (platform, health) in stats.response_health.lock().unwrap().iter() {
+        let num_failures: usize = health.failures.values().sum();
+        if num_failures == 0 {
+            continue;
+        }
+        let failure_rate = num_failures as f32 / health.responses as f32;
+        let mut failures: Vec<(&String, &usize)> = health.failures.iter().collect();
+        failures.sort_by(|a, b| b.1.cmp(a.1));
+        let mut diagnosis = format!(
+            "{}: {} of {} responses ({:.1}%) failed to deserialize. Most common failures:",
+            platform,
+            num_failures,
+            health.responses,
+            failure_rate * 100.0
+        );
+        for (failure, count) in failures.iter().take(5) {
+            diagnosis += &format!("\n    {} ({} times)", failure, count);
+        }
+        if let Some(example) = &health.example {
+            diagnosis += &format!("\n    Example payload: {}", example);
+        }
+        println!("{}", diagnosis);
+        if failure_rate > DESERIALIZE_FAILURE_THRESHOLD {
+            degraded.push(diagnosis);
+        }
+    }
+    degraded
+}
+
+/// Print the throughput and stage timing for a platform, given how long it took overall.
+pub fn report_bench(stats: &RunStats, platform: Platform, elapsed: std::time::Duration) {
+    let name = format!("{:?}", platform).to_lowercase();
+    let stats = stats
+        .bench
+        .lock()
+        .unwrap()
+        .get(&name)
+        .cloned()
+        .unwrap_or_default();
+    let secs = elapsed.as_secs_f32().max(f32::EPSILON);
+    println!(
+        "{:?}: {} markets in {:.2?} ({:.1} markets/sec, {:.1} KB/sec)",
+        platform,
+        stats.markets,
+        elapsed,
+        stats.markets as f32 / secs,
+        stats.bytes as f32 / 1000.0 / secs,
+    );
+    println!(
+        "    download {:.2?}, deserialize {:.2?}, convert {:.2?}",
+        stats.download, stats.deserialize, stats.convert
+    );
+}
+
+/// Print every unrecognized field and value seen during this run.
+/// Fields we deliberately don't use will show up here too, so compare against previous runs.
+pub fn report_unrecognized(stats: &RunStats) {
+    let unrecognized = stats.unrecognized.lock().unwrap();
+    if unrecognized.is_empty() {
+        return;
+    }
+    println!(
+        "Found {} unrecognized fields or values in API responses:",
+        unrecognized.len()
+    );
+    for (item, count) in unrecognized.iter() {
+        println!("    {} (seen {} times)", item, count);
+    }
+}
+
 /// Evaluate processing errors based on their level.
 /// Level 0 is for expected events like market validity
 /// Level 1 is for things that probably shouldn't happen but are uncommon
@@ -510,7 +2024,397 @@ fn eval_error(error: MarketConvertError, verbose: bool) {
     }
 }
 
+/// Get a FNV-1a hash of a string, which stays the same across runs and Rust versions.
+fn get_stable_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Deterministically decide if a market is in the requested sample (a percentage from 0 to 100).
+/// This uses a stable hash of the market ID, so the same markets are picked on every run.
+fn is_in_sample(id: &str, sample: Option<f32>) -> bool {
+    let Some(sample_pct) = sample else {
+        return true;
+    };
+    ((get_stable_hash(id) % 10_000) as f32) < sample_pct * 100.0
+}
+
+/// Convert a batch of downloaded markets for platforms where one download can hold several markets.
+/// Any download or conversion errors are logged and the failed markets are skipped.
+fn convert_markets<T>(
+    stats: &RunStats,
+    market_data_results: Vec<Result<Vec<T>, MarketConvertError>>,
+    verbose: bool,
+) -> Vec<MarketStandard>
+where
+    T: TryInto<MarketStandard, Error = MarketConvertError> + Send,
+{
+    let markets: Vec<T> = market_data_results
+        .into_iter()
+        .filter_map(|market_downloaded_result| match market_downloaded_result {
+            // market downloaded successfully
+            Ok(markets) => Some(markets),
+            Err(error) => {
+                // market failed downloadng
+                eval_error(error, verbose);
+                None
+            }
+        })
+        .flatten()
+        .collect();
+    convert_markets_parallel(stats, markets)
+        .into_iter()
+        .filter_map(|result| match result {
+            // market processed successfully
+            Ok(market_converted) => Some(market_converted),
+            // market failed processing
+            Err(error) => {
+                eval_error(error, verbose);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Convert markets on every available core, since each conversion computes every criterion
+/// from the full price history. The results stay in the original order, so errors are
+/// still logged one at a time by the caller.
+fn convert_markets_parallel<T>(
+    stats: &RunStats,
+    markets: Vec<T>,
+) -> Vec<Result<MarketStandard, MarketConvertError>>
+where
+    T: TryInto<MarketStandard, Error = MarketConvertError> + Send,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads <= 1 || markets.len() <= 1 {
+        return markets
+            .into_iter()
+            .map(|market| convert_market(stats, market))
+            .collect();
+    }
+    let chunk_size = markets.len().div_ceil(threads);
+    let mut remaining = markets.into_iter();
+    let mut chunks: Vec<Vec<T>> = Vec::with_capacity(threads);
+    loop {
+        let chunk: Vec<T> = remaining.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk);
+    }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .into_iter()
+                        .map(|market| convert_market(stats, market))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Market conversion thread panicked."))
+            .collect()
+    })
+}
+
+/// Convert a downloaded market into the standard fields, noting how long it took.
+fn convert_market<T>(stats: &RunStats, market: T) -> Result<MarketStandard, MarketConvertError>
+where
+    T: TryInto<MarketStandard, Error = MarketConvertError>,
+{
+    let convert_timer = std::time::Instant::now();
+    let result = market.try_into();
+    record_bench(stats, std::any::type_name::<T>(), |bench| {
+        bench.convert += convert_timer.elapsed();
+        bench.markets += result.is_ok() as usize;
+    });
+    result
+}
+
+/// Compile a regex the first time it's needed and reuse it after that, since these run on
+/// every market.
+fn get_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Convert a description from any of the platform formats into clean Markdown.
+/// Line breaks are normalized, simple HTML is converted or stripped, common
+/// entities are decoded, and runs of blank lines are collapsed.
+fn normalize_markdown(text: &str) -> String {
+    static LINE_BREAK: OnceLock<Regex> = OnceLock::new();
+    static PARAGRAPH_END: OnceLock<Regex> = OnceLock::new();
+    static HTML_TAG: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES: OnceLock<Regex> = OnceLock::new();
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let text = get_regex(&LINE_BREAK, r"(?i)<br\s*/?>").replace_all(&text, "\n");
+    let text = get_regex(&PARAGRAPH_END, r"(?i)</p>").replace_all(&text, "\n\n");
+    let text = get_regex(&HTML_TAG, r"<[^>]+>").replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let text = text
+        .lines()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    get_regex(&BLANK_LINES, r"\n{3,}")
+        .replace_all(&text, "\n\n")
+        .trim()
+        .to_string()
+}
+
+/// Infer the region a market is about from its title, based on `REGION_PATTERNS`.
+fn get_region(title: &str) -> String {
+    static REGION_REGEXES: OnceLock<Vec<(&str, Regex)>> = OnceLock::new();
+    REGION_REGEXES
+        .get_or_init(|| {
+            REGION_PATTERNS
+                .iter()
+                .map(|(region, pattern)| (*region, Regex::new(pattern).unwrap()))
+                .collect()
+        })
+        .iter()
+        .find(|(_, regex)| regex.is_match(title))
+        .map(|(region, _)| region.to_string())
+        .unwrap_or("Global".to_string())
+}
+
+/// Strip Markdown syntax from a description and cap it at `max_chars` characters.
+/// If the text is too long it is cut at the last word boundary and an ellipsis is added.
+fn get_plaintext_excerpt(markdown: &str, max_chars: usize) -> String {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static LINE_MARKER: OnceLock<Regex> = OnceLock::new();
+    static INLINE_MARKER: OnceLock<Regex> = OnceLock::new();
+    static WHITESPACE: OnceLock<Regex> = OnceLock::new();
+    // keep the text from links and images but drop the targets
+    let text = get_regex(&LINK, r"!?\[([^\]]*)\]\([^)]*\)").replace_all(markdown, "$1");
+    // drop heading, quote, and list markers at the start of lines
+    let text = get_regex(&LINE_MARKER, r"(?m)^\s*(#{1,6}|>|[-*+]|\d+\.)\s+").replace_all(&text, "");
+    // drop emphasis and code markers
+    let text = get_regex(&INLINE_MARKER, r"[*`~]").replace_all(&text, "");
+    // collapse all whitespace into single spaces
+    let text = get_regex(&WHITESPACE, r"\s+").replace_all(&text, " ");
+    let text = text.trim();
+
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars - 1).collect();
+    let truncated = match truncated.rfind(' ') {
+        Some(index) => &truncated[..index],
+        None => &truncated,
+    };
+    truncated.trim_end().to_string() + "…"
+}
+
 /// Print a standard log line with the current datetime.
 fn log_to_stdout(message: &str) {
     println!("{:?} - {}", chrono::offset::Local::now(), message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// A market with every field set, so a renamed, removed, or reformatted field changes the output.
+    fn sample_market() -> MarketStandard {
+        let day = |d: u32| Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap();
+        MarketStandard {
+            title: "Will it rain?".to_string(),
+            platform: "manifold".to_string(),
+            platform_id: "abc123".to_string(),
+            url: "https://manifold.markets/user/will-it-rain".to_string(),
+            open_dt: day(1),
+            close_dt: day(3),
+            trading_end_dt: day(3),
+            resolved_dt: Some(day(4)),
+            open_days: 2.0,
+            volume_usd: 12.5,
+            num_traders: 3,
+            category: "Climate".to_string(),
+            region: "US".to_string(),
+            description: "Resolves YES if it rains.".to_string(),
+            description_excerpt: "Resolves YES if it rains.".to_string(),
+            image_url: None,
+            prob_at_midpoint: 0.5,
+            prob_at_close: 0.75,
+            prob_each_pct: vec![0.5, 0.75],
+            prob_each_date: serde_json::json!({"2024-01-01T00:00:00Z": 0.5}),
+            prob_each_hour: serde_json::json!({}),
+            prob_daily_start_dt: None,
+            prob_daily: Vec::new(),
+            active_each_date: serde_json::json!({"2024-01-01T00:00:00Z": true}),
+            volume_each_date: serde_json::json!({"2024-01-01T00:00:00Z": 12.5}),
+            prob_band_each_date: serde_json::json!({}),
+            prob_time_avg: 0.625,
+            prob_min: 0.5,
+            prob_max: 0.75,
+            prob_daily_stddev: 0.125,
+            prob_midpoint_crossings: 1,
+            days_to_confidence: Some(1.5),
+            resolution: 1.0,
+            question_type: "binary".to_string(),
+            quantiles_each_date: serde_json::json!({}),
+            linked_group: Some("group".to_string()),
+            linked_order: Some(0),
+        }
+    }
+
+    /// The console output is read by other tools and replayed from spill files, so its format
+    /// should only change along with `SCHEMA_VERSION`.
+    #[test]
+    fn market_output_matches_snapshot() {
+        let markets = [sample_market()];
+        let output = MarketOutput {
+            schema_version: SCHEMA_VERSION,
+            markets: &markets,
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "markets": [{
+                "title": "Will it rain?",
+                "platform": "manifold",
+                "platform_id": "abc123",
+                "url": "https://manifold.markets/user/will-it-rain",
+                "open_dt": "2024-01-01T00:00:00Z",
+                "close_dt": "2024-01-03T00:00:00Z",
+                "trading_end_dt": "2024-01-03T00:00:00Z",
+                "resolved_dt": "2024-01-04T00:00:00Z",
+                "open_days": 2.0,
+                "volume_usd": 12.5,
+                "num_traders": 3,
+                "category": "Climate",
+                "region": "US",
+                "description": "Resolves YES if it rains.",
+                "description_excerpt": "Resolves YES if it rains.",
+                "image_url": null,
+                "prob_at_midpoint": 0.5,
+                "prob_at_close": 0.75,
+                "prob_each_pct": [0.5, 0.75],
+                "prob_each_date": {"2024-01-01T00:00:00Z": 0.5},
+                "prob_each_hour": {},
+                "prob_daily_start_dt": null,
+                "prob_daily": [],
+                "active_each_date": {"2024-01-01T00:00:00Z": true},
+                "volume_each_date": {"2024-01-01T00:00:00Z": 12.5},
+                "prob_band_each_date": {},
+                "prob_time_avg": 0.625,
+                "prob_min": 0.5,
+                "prob_max": 0.75,
+                "prob_daily_stddev": 0.125,
+                "prob_midpoint_crossings": 1,
+                "days_to_confidence": 1.5,
+                "resolution": 1.0,
+                "question_type": "binary",
+                "quantiles_each_date": {},
+                "linked_group": "group",
+                "linked_order": 0,
+            }],
+        });
+        assert_eq!(serde_json::to_value(&output).unwrap(), expected);
+    }
+
+    /// Spilled batches are saved with `--replay`, which reads the same format back.
+    #[test]
+    fn market_input_reads_market_output() {
+        let markets = [sample_market()];
+        let output = MarketOutput {
+            schema_version: SCHEMA_VERSION,
+            markets: &markets,
+        };
+        let input: MarketInput = serde_json::from_str(&to_string_pretty(&output).unwrap()).unwrap();
+        assert_eq!(input.schema_version, SCHEMA_VERSION);
+        assert_eq!(
+            serde_json::to_value(&input.markets).unwrap(),
+            serde_json::to_value(&markets).unwrap()
+        );
+    }
+
+    /// Standardize a checked-in bundle of API responses and check each market it returns
+    /// against the platform contract, so every adapter is held to it without a live run.
+    fn assert_meets_contract(result: Result<Vec<MarketStandard>, MarketConvertError>) {
+        let markets = result.unwrap_or_else(|e| panic!("{e}"));
+        assert!(!markets.is_empty(), "bundle produced no markets");
+        for market in &markets {
+            assert_eq!(check_contract(market), Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn betfair_meets_contract() {
+        assert_meets_contract(betfair::standardize_bytes(
+            include_bytes!("../tests/data/betfair.jsonl"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn infer_meets_contract() {
+        assert_meets_contract(infer::standardize_bytes(
+            include_bytes!("../tests/data/infer.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn kalshi_meets_contract() {
+        assert_meets_contract(kalshi::standardize_bytes(
+            include_bytes!("../tests/data/kalshi.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn manifold_meets_contract() {
+        assert_meets_contract(manifold::standardize_bytes(
+            include_bytes!("../tests/data/manifold.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn metaculus_meets_contract() {
+        assert_meets_contract(metaculus::standardize_bytes(
+            include_bytes!("../tests/data/metaculus.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn polymarket_meets_contract() {
+        assert_meets_contract(polymarket::standardize_bytes(
+            include_bytes!("../tests/data/polymarket.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn predictit_meets_contract() {
+        assert_meets_contract(predictit::standardize_bytes(
+            include_bytes!("../tests/data/predictit.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+
+    #[test]
+    fn smarkets_meets_contract() {
+        assert_meets_contract(smarkets::standardize_bytes(
+            include_bytes!("../tests/data/smarkets.json"),
+            StandardizeOptions::default(),
+        ));
+    }
+}
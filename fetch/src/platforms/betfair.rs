@@ -0,0 +1,452 @@
+//! Tools to process markets from Betfair Exchange historical data files.
+//! The Exchange API only has live prices, so market history is read from the stream files
+//! sold at https://historicdata.betfair.com. Each runner is saved as its own binary market.
+
+use super::*;
+use bzip2::read::BzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+const BETFAIR_SITE_BASE: &str = "https://www.betfair.com/exchange/plus/market/";
+const BETFAIR_GBP_TO_USD: f32 = 1.27;
+const BETFAIR_FILES_PER_BATCH: usize = 100;
+
+/// A single line from a historical stream file.
+#[derive(Deserialize, Debug)]
+struct StreamMessage {
+    #[serde(with = "ts_milliseconds")]
+    pt: DateTime<Utc>,
+    #[serde(default)]
+    mc: Vec<MarketChange>,
+}
+
+/// (Indirect) A change to one market in a stream message.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct MarketChange {
+    id: String,
+    marketDefinition: Option<MarketDefinition>,
+    #[serde(default)]
+    rc: Vec<RunnerChange>,
+}
+
+/// (Indirect) The full market definition, sent whenever anything about the market changes.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+struct MarketDefinition {
+    status: String,
+    eventTypeId: String,
+    #[serde(default)]
+    eventName: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    settledTime: Option<DateTime<Utc>>,
+    runners: Vec<RunnerDefinition>,
+}
+
+/// (Indirect) Info about one runner (selection) in a market definition.
+#[derive(Deserialize, Debug, Clone)]
+struct RunnerDefinition {
+    id: u64,
+    #[serde(default)]
+    name: Option<String>,
+    status: String,
+}
+
+/// (Indirect) A price or volume update for one runner.
+#[derive(Deserialize, Debug)]
+struct RunnerChange {
+    id: u64,
+    /// Last traded price, in decimal odds.
+    ltp: Option<f32>,
+    /// Total traded volume in GBP, only in PRO files.
+    tv: Option<f32>,
+}
+
+/// Container for market data and events, used to hold data for conversion.
+#[derive(Debug)]
+struct MarketFull {
+    market_id: String,
+    definition: MarketDefinition,
+    runner: RunnerDefinition,
+    events: Vec<ProbUpdate>,
+    volume_gbp: f32,
+    trading_end: Option<DateTime<Utc>>,
+    options: StandardizeOptions,
+}
+
+impl MarketStandardizer for MarketFull {
+    fn debug(&self) -> String {
+        format!("{:?}", self)
+    }
+    fn title(&self) -> String {
+        let market_name = match (&self.definition.eventName, &self.definition.name) {
+            (Some(event), Some(market)) => format!("{event}: {market}"),
+            (Some(name), None) | (None, Some(name)) => name.to_owned(),
+            (None, None) => self.market_id.to_owned(),
+        };
+        let runner_name = self
+            .runner
+            .name
+            .clone()
+            .unwrap_or(self.runner.id.to_string());
+        format!("{market_name} - {runner_name}")
+    }
+    fn platform(&self) -> String {
+        "betfair".to_string()
+    }
+    fn platform_id(&self) -> String {
+        format!("{}-{}", self.market_id, self.runner.id)
+    }
+    fn url(&self) -> String {
+        BETFAIR_SITE_BASE.to_owned() + &self.market_id
+    }
+    fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        if let Some(first_event) = self.events().first() {
+            Ok(first_event.time)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "Betfair: No events in event list (cannot get market bounds).".to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        if let Some(trading_end) = self.trading_end {
+            Ok(trading_end)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "Betfair: Market was never open for trading.".to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        self.definition.settledTime
+    }
+    fn volume_usd(&self) -> f32 {
+        self.volume_gbp * BETFAIR_GBP_TO_USD
+    }
+    fn num_traders(&self) -> i32 {
+        0 // not available in the data files
+    }
+    fn category(&self) -> String {
+        match self.definition.eventTypeId.as_str() {
+            "2378961" => "Politics".to_string(),
+            "6231" => "Economics".to_string(),
+            "3503" => "Culture".to_string(),
+            _ => "Sports".to_string(),
+        }
+    }
+    fn description(&self) -> String {
+        // market rules are not included in the data files
+        String::new()
+    }
+    fn events(&self) -> Vec<ProbUpdate> {
+        self.events.to_owned()
+    }
+    fn resolution(&self) -> Result<f32, MarketConvertError> {
+        match self.runner.status.as_str() {
+            "WINNER" => Ok(1.0),
+            "LOSER" => Ok(0.0),
+            _ => Err(MarketConvertError {
+                data: self.debug(),
+                message: format!(
+                    "Betfair: Runner status `{}` is not a binary resolution.",
+                    self.runner.status
+                ),
+                level: 1,
+            }),
+        }
+    }
+}
+
+/// Standard conversion setup (would move this up to `platforms` if I could).
+impl TryInto<MarketStandard> for MarketFull {
+    type Error = MarketConvertError;
+    fn try_into(self) -> Result<MarketStandard, MarketConvertError> {
+        Ok(MarketStandard {
+            title: self.title(),
+            platform: self.platform(),
+            platform_id: self.platform_id(),
+            url: self.url(),
+            open_dt: self.open_dt()?,
+            close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
+            open_days: self.open_days()?,
+            volume_usd: self.volume_usd(),
+            num_traders: self.num_traders(),
+            category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
+            prob_at_midpoint: self.prob_at_percent(0.5)?,
+            prob_at_close: self.prob_at_percent(1.0)?,
+            prob_each_pct: self.prob_each_pct_list()?,
+            prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
+            prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
+            resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
+        })
+    }
+}
+
+/// Test if a market is suitable for analysis.
+fn is_valid(definition: &MarketDefinition) -> bool {
+    definition.status == "CLOSED"
+}
+
+/// Get the directory holding the historical data files.
+fn get_data_dir() -> PathBuf {
+    PathBuf::from(
+        var("BETFAIR_DATA_DIR").expect("Required environment variable BETFAIR_DATA_DIR not set."),
+    )
+}
+
+/// Recursively list every file under a directory.
+/// The historical data downloads are nested by year, month, day, and event.
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(list_files(&path));
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Get the market ID from a data file name, which is the ID with an optional `.bz2` extension.
+/// Market IDs contain a period, so `file_stem` can't be used on uncompressed files.
+fn get_market_id_from_path(path: &Path) -> Option<&str> {
+    let file_name = path.file_name()?.to_str()?;
+    Some(file_name.strip_suffix(".bz2").unwrap_or(file_name))
+}
+
+/// List and read files on the blocking thread pool, since file reads and bz2 decoding would
+/// otherwise stall every other platform sharing the single-threaded runtime.
+async fn run_blocking<T: Send + 'static>(task: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(task)
+        .await
+        .expect("Betfair: Data file reader panicked.")
+}
+
+/// Read one historical data file (plain or bz2) and build a container for each settled runner.
+fn read_market_file(
+    path: &Path,
+    options: StandardizeOptions,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
+    let file = File::open(path).map_err(|e| MarketConvertError {
+        data: path.display().to_string(),
+        message: format!("Betfair: Failed to open data file: {e}"),
+        level: 4,
+    })?;
+    let reader: Box<dyn Read> = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("bz2") => Box::new(BzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    read_market_stream(reader, &path.display().to_string(), options)
+}
+
+/// Read the stream messages of one market and build a container for each settled runner.
+/// The source is only used to label errors.
+fn read_market_stream(
+    reader: impl Read,
+    source: &str,
+    options: StandardizeOptions,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
+    let mut market_id: Option<String> = None;
+    let mut definition: Option<MarketDefinition> = None;
+    let mut trading_end: Option<DateTime<Utc>> = None;
+    let mut events_by_runner: HashMap<u64, Vec<ProbUpdate>> = HashMap::new();
+    let mut volume_by_runner: HashMap<u64, f32> = HashMap::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line.map_err(|e| MarketConvertError {
+            data: source.to_string(),
+            message: format!("Betfair: Failed to read data file: {e}"),
+            level: 3,
+        })?;
+        let message: StreamMessage =
+            serde_json::from_str(&line).map_err(|e| MarketConvertError {
+                data: line.clone(),
+                message: format!("Betfair: Failed to parse stream message: {e}"),
+                level: 3,
+            })?;
+        for change in message.mc {
+            market_id = Some(change.id);
+            if let Some(new_definition) = change.marketDefinition {
+                definition = Some(new_definition);
+            }
+            if definition.as_ref().is_some_and(|d| d.status == "OPEN") {
+                trading_end = Some(message.pt);
+            }
+            for runner_change in change.rc {
+                if let Some(ltp) = runner_change.ltp {
+                    // convert decimal odds to an implied probability
+                    let prob = (1.0 / ltp).clamp(0.0, 1.0);
+                    let events = events_by_runner.entry(runner_change.id).or_default();
+                    if events.last().map(|e| e.prob) != Some(prob) {
+                        events.push(ProbUpdate {
+                            time: message.pt,
+                            prob,
+                        });
+                    }
+                }
+                if let Some(tv) = runner_change.tv {
+                    volume_by_runner.insert(runner_change.id, tv);
+                }
+            }
+        }
+    }
+
+    let (market_id, definition) = match (market_id, definition) {
+        (Some(market_id), Some(definition)) => (market_id, definition),
+        _ => {
+            return Err(MarketConvertError {
+                data: source.to_string(),
+                message: "Betfair: Data file has no market definition.".to_string(),
+                level: 3,
+            })
+        }
+    };
+    if !is_valid(&definition) {
+        return Ok(Vec::new());
+    }
+
+    Ok(definition
+        .runners
+        .iter()
+        .map(|runner| MarketFull {
+            market_id: market_id.clone(),
+            definition: definition.clone(),
+            runner: runner.clone(),
+            events: events_by_runner.remove(&runner.id).unwrap_or_default(),
+            volume_gbp: volume_by_runner.get(&runner.id).copied().unwrap_or(0.0),
+            trading_end,
+            options,
+        })
+        .collect())
+}
+
+/// Standardize one market from its historical data file, plain or bz2, without reading the data directory.
+/// Each settled runner is returned as its own market, and markets that would be skipped by a
+/// normal run return an empty list.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let reader: Box<dyn Read + '_> = match bytes.starts_with(b"BZh") {
+        true => Box::new(BzDecoder::new(bytes)),
+        false => Box::new(bytes),
+    };
+    read_market_stream(reader, "Betfair market bundle", options)?
+        .into_iter()
+        .map(|market| market.try_into())
+        .collect()
+}
+
+/// Process and store all valid markets from the historical data directory.
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    log_to_stdout("Betfair: Processing started...");
+    let data_dir = get_data_dir();
+    let dir = data_dir.clone();
+    let files: Vec<PathBuf> = run_blocking(move || list_files(&dir))
+        .await
+        .into_iter()
+        .filter(|path| is_in_sample(get_market_id_from_path(path).unwrap_or_default(), sample))
+        .collect();
+    if verbose {
+        println!(
+            "Betfair: Found {} data files in {}",
+            files.len(),
+            data_dir.display()
+        )
+    }
+    let options = config.options;
+    for batch in files.chunks(BETFAIR_FILES_PER_BATCH) {
+        let batch = batch.to_vec();
+        let market_files = run_blocking(move || {
+            batch
+                .iter()
+                .map(|path| read_market_file(path, options))
+                .collect()
+        })
+        .await;
+        let market_data = convert_markets(&config.stats, market_files, verbose);
+        if verbose {
+            println!(
+                "Betfair: Saving {} processed markets to {:?}...",
+                market_data.len(),
+                output_method
+            )
+        }
+        save_markets(config, market_data, output_method);
+    }
+    log_to_stdout("Betfair: Processing complete.");
+}
+
+/// Process and store one market from the historical data directory.
+/// The ID is the Betfair market ID (e.g. `1.123456789`), and every runner is saved.
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
+    let data_dir = get_data_dir();
+    let path = run_blocking(move || list_files(&data_dir))
+        .await
+        .into_iter()
+        .find(|path| get_market_id_from_path(path) == Some(id))
+        .expect("Betfair: Market file not found in data directory.");
+    if verbose {
+        println!("Betfair: Reading market from {}", path.display())
+    }
+    let options = config.options;
+    let market_file = run_blocking(move || read_market_file(&path, options)).await;
+    let market_data = convert_markets(&config.stats, Vec::from([market_file]), verbose);
+    if market_data.is_empty() {
+        println!("Betfair: Market is not valid for processing, nothing was saved.")
+    }
+    if verbose {
+        println!(
+            "Betfair: Saving {} processed markets to {:?}...",
+            market_data.len(),
+            output_method
+        )
+    }
+    save_markets(config, market_data, output_method);
+}
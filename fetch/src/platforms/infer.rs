@@ -0,0 +1,386 @@
+//! Tools to download and process questions from the INFER (formerly CSET Foretell) API.
+//! INFER runs on the Cultivate Labs platform, which requires an API key for access.
+
+use super::*;
+
+const INFER_API_BASE: &str = "https://www.infer-pub.com/api/v1";
+const INFER_SITE_BASE: &str = "https://www.infer-pub.com/questions/";
+const INFER_USD_PER_FORECAST: f32 = 0.10;
+const INFER_RATELIMIT: usize = 5;
+
+#[derive(Deserialize, Debug, Clone)]
+struct BulkMarketResponse {
+    questions: Vec<MarketInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct MarketInfo {
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    question_type: String,
+    state: String,
+    #[serde(default)]
+    description: Option<String>,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    ends_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    resolved_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    predictions_count: u32,
+    #[serde(default)]
+    predictors_count: i32,
+    #[serde(default)]
+    topics: Vec<TopicInfo>,
+    answers: Vec<AnswerInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct TopicInfo {
+    name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct AnswerInfo {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    resolved_probability: Option<f32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ConsensusHistoryResponse {
+    consensus_history: Vec<ConsensusPoint>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ConsensusPoint {
+    answer_id: u32,
+    probability: f32,
+    created_at: DateTime<Utc>,
+}
+
+/// Container for market data and events, used to hold data for conversion.
+#[derive(Debug)]
+struct MarketFull {
+    market: MarketInfo,
+    events: Vec<ProbUpdate>,
+    options: StandardizeOptions,
+}
+
+impl MarketStandardizer for MarketFull {
+    fn debug(&self) -> String {
+        format!("{:?}", self)
+    }
+    fn title(&self) -> String {
+        self.market.name.to_owned()
+    }
+    fn platform(&self) -> String {
+        "infer".to_string()
+    }
+    fn platform_id(&self) -> String {
+        self.market.id.to_string()
+    }
+    fn url(&self) -> String {
+        INFER_SITE_BASE.to_owned() + &self.market.id.to_string()
+    }
+    fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        Ok(self.market.created_at)
+    }
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        if let Some(close_time) = self.market.ends_at {
+            Ok(close_time)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "INFER: ends_at is missing from resolved question".to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        self.market.resolved_at
+    }
+    fn volume_usd(&self) -> f32 {
+        self.market.predictions_count as f32 * INFER_USD_PER_FORECAST
+    }
+    fn num_traders(&self) -> i32 {
+        self.market.predictors_count
+    }
+    fn category(&self) -> String {
+        for topic in &self.market.topics {
+            match topic.name.as_str() {
+                "Artificial Intelligence" => return "AI".to_string(),
+                "Biotechnology" => return "Science".to_string(),
+                "Climate" => return "Climate".to_string(),
+                "Economics" => return "Economics".to_string(),
+                "Geopolitics" => return "Politics".to_string(),
+                "Science & Technology" => return "Technology".to_string(),
+                "Security & Conflict" => return "Politics".to_string(),
+                "Semiconductors" => return "Technology".to_string(),
+                "Space" => return "Science".to_string(),
+                _ => continue,
+            }
+        }
+        "None".to_string()
+    }
+    fn description(&self) -> String {
+        self.market.description.clone().unwrap_or_default()
+    }
+    fn events(&self) -> Vec<ProbUpdate> {
+        self.events.to_owned()
+    }
+    fn resolution(&self) -> Result<f32, MarketConvertError> {
+        match get_yes_answer(&self.market).and_then(|answer| answer.resolved_probability) {
+            Some(resolution) if (0.0..=1.0).contains(&resolution) => Ok(resolution),
+            Some(_) => Err(MarketConvertError {
+                data: self.debug(),
+                message: "INFER: Question resolution value out of bounds".to_string(),
+                level: 3,
+            }),
+            None => Err(MarketConvertError {
+                data: self.debug(),
+                message: "INFER: Question resolution value is null".to_string(),
+                level: 3,
+            }),
+        }
+    }
+}
+
+/// Standard conversion setup (would move this up to `platforms` if I could).
+impl TryInto<MarketStandard> for MarketFull {
+    type Error = MarketConvertError;
+    fn try_into(self) -> Result<MarketStandard, MarketConvertError> {
+        Ok(MarketStandard {
+            title: self.title(),
+            platform: self.platform(),
+            platform_id: self.platform_id(),
+            url: self.url(),
+            open_dt: self.open_dt()?,
+            close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
+            open_days: self.open_days()?,
+            volume_usd: self.volume_usd(),
+            num_traders: self.num_traders(),
+            category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
+            prob_at_midpoint: self.prob_at_percent(0.5)?,
+            prob_at_close: self.prob_at_percent(1.0)?,
+            prob_each_pct: self.prob_each_pct_list()?,
+            prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
+            prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
+            resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
+        })
+    }
+}
+
+/// Get the answer that represents YES on a binary question.
+fn get_yes_answer(market: &MarketInfo) -> Option<&AnswerInfo> {
+    market.answers.iter().find(|answer| answer.name == "Yes")
+}
+
+/// Test if a market is suitable for analysis.
+/// Only binary questions are supported for now.
+fn is_valid(market: &MarketInfo) -> bool {
+    market.state == "resolved"
+        && market.question_type == "Forecast::YesNoQuestion"
+        && get_yes_answer(market).is_some()
+}
+
+/// Get the API key used to authenticate with the platform.
+fn get_api_key() -> String {
+    var("INFER_API_KEY").expect("Required environment variable INFER_API_KEY not set.")
+}
+
+/// Convert API consensus points for the YES answer into standard events.
+fn get_prob_updates(mut points: Vec<ConsensusPoint>, answer_id: u32) -> Vec<ProbUpdate> {
+    let mut result: Vec<ProbUpdate> = Vec::new();
+    points.retain(|point| point.answer_id == answer_id);
+    points.sort_unstable_by_key(|point| point.created_at);
+    for point in points {
+        if let Some(last_event) = result.last() {
+            if last_event.prob == point.probability {
+                // skip adding to the list if the prob is the same
+                continue;
+            }
+        }
+        result.push(ProbUpdate {
+            time: point.created_at,
+            prob: point.probability,
+        });
+    }
+    result
+}
+
+/// Download the consensus history and store events in the container.
+async fn get_extended_data(
+    client: &ClientWithMiddleware,
+    api_key: &str,
+    market: &MarketInfo,
+    config: &RunConfig,
+) -> Result<MarketFull, MarketConvertError> {
+    let Some(yes_answer) = get_yes_answer(market) else {
+        return Err(MarketConvertError {
+            data: format!("{:?}", market),
+            message: "INFER: Binary question has no YES answer".to_string(),
+            level: 3,
+        });
+    };
+    let api_url = format!(
+        "{}/questions/{}/consensus_history",
+        INFER_API_BASE, market.id
+    );
+    let history: ConsensusHistoryResponse =
+        send_request(&config.stats, client.get(&api_url).bearer_auth(api_key)).await?;
+    Ok(MarketFull {
+        market: market.clone(),
+        events: get_prob_updates(history.consensus_history, yes_answer.id),
+        options: config.options,
+    })
+}
+
+/// Every API response needed to standardize one market, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct MarketBundle {
+    /// The question, same as from `/questions/{id}`.
+    question: MarketInfo,
+    /// The consensus history, same as from `/questions/{id}/consensus_history`.
+    history: ConsensusHistoryResponse,
+}
+
+/// Standardize one question from a JSON bundle of its API responses without downloading anything.
+/// Returns an empty list if the question would be skipped by a normal run.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let bundle: MarketBundle = parse_bundle("INFER", bytes)?;
+    let Some(yes_answer) = get_yes_answer(&bundle.question).filter(|_| is_valid(&bundle.question))
+    else {
+        return Ok(Vec::new());
+    };
+    let market = MarketFull {
+        events: get_prob_updates(bundle.history.consensus_history, yes_answer.id),
+        market: bundle.question,
+        options,
+    };
+    Ok(Vec::from([market.try_into()?]))
+}
+
+/// Download, process and store all valid markets from the platform.
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    log_to_stdout("INFER: Processing started...");
+    let client = get_reqwest_client_ratelimited(INFER_RATELIMIT, None);
+    let api_key = get_api_key();
+    let api_url = INFER_API_BASE.to_owned() + "/questions";
+    if verbose {
+        println!("INFER: Connecting to API at {}", api_url)
+    }
+    let mut page: usize = 1;
+    loop {
+        if verbose {
+            println!("INFER: Getting page {:?}...", page)
+        }
+        let market_response: BulkMarketResponse = send_request(
+            &config.stats,
+            client
+                .get(&api_url)
+                .bearer_auth(&api_key)
+                .query(&[("status", "resolved")])
+                .query(&[("page", page)]),
+        )
+        .await
+        .expect("INFER: API query error.");
+        if market_response.questions.is_empty() {
+            break;
+        }
+        if verbose {
+            println!(
+                "INFER: Processing {} markets...",
+                market_response.questions.len()
+            )
+        }
+        let market_data_futures: Vec<_> = market_response
+            .questions
+            .iter()
+            .filter(|market| is_valid(market))
+            .filter(|market| is_in_sample(&market.id.to_string(), sample))
+            .map(|market| async {
+                get_extended_data(&client, &api_key, market, config)
+                    .await
+                    .map(|market_full| vec![market_full])
+            })
+            .collect();
+        let market_data =
+            convert_markets(&config.stats, join_all(market_data_futures).await, verbose);
+        if verbose {
+            println!(
+                "INFER: Saving {} processed markets to {:?}...",
+                market_data.len(),
+                output_method
+            )
+        }
+        save_markets(config, market_data, output_method);
+        page += 1;
+    }
+    log_to_stdout("INFER: Processing complete.");
+}
+
+/// Download, process and store one market from the platform.
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
+    let client = get_reqwest_client_ratelimited(INFER_RATELIMIT, None);
+    let api_key = get_api_key();
+    let api_url = INFER_API_BASE.to_owned() + "/questions/" + id;
+    if verbose {
+        println!("INFER: Connecting to API at {}", api_url)
+    }
+    let market_single: MarketInfo =
+        send_request(&config.stats, client.get(&api_url).bearer_auth(&api_key))
+            .await
+            .expect("INFER: API query error.");
+    if !is_valid(&market_single) {
+        println!("INFER: Market is not valid for processing, this may fail.")
+    }
+    let market_data = get_extended_data(&client, &api_key, &market_single, config)
+        .await
+        .expect("Error getting extended market data")
+        .try_into()
+        .expect("Error converting market into standard fields");
+    if verbose {
+        println!("INFER: Saving processed market to {:?}...", output_method)
+    }
+    save_markets(config, Vec::from([market_data]), output_method);
+}
@@ -30,10 +30,14 @@ struct MarketInfo {
     title: String,
     open_time: DateTime<Utc>,
     close_time: DateTime<Utc>,
+    #[serde(default)]
+    expiration_time: Option<DateTime<Utc>>,
     status: String,
     volume: f32,
     result: String,
     category: String,
+    #[serde(default)]
+    rules_primary: Option<String>,
 }
 
 /// API response after requesting a single market from `/market`.
@@ -42,6 +46,42 @@ struct SingleMarketResponse {
     market: MarketInfo,
 }
 
+/// (Indirect) API response with the info for the event a market belongs to.
+#[derive(Deserialize, Debug)]
+struct ParentEventInfo {
+    event_ticker: String,
+    series_ticker: String,
+    #[serde(default)]
+    mutually_exclusive: bool,
+}
+
+/// API response after requesting a single event from `/events`.
+#[derive(Deserialize, Debug)]
+struct SingleEventResponse {
+    event: ParentEventInfo,
+}
+
+/// (Indirect) API response with the info for a series of recurring events.
+#[derive(Deserialize, Debug)]
+struct SeriesInfo {
+    title: String,
+}
+
+/// API response after requesting a single series from `/series`.
+#[derive(Deserialize, Debug)]
+struct SingleSeriesResponse {
+    series: SeriesInfo,
+}
+
+/// Details about the event a market belongs to, looked up once per event.
+#[derive(Debug, Clone, Default)]
+struct EventDetails {
+    /// The event ticker, if the event is mutually exclusive and its markets are linked.
+    linked_group: Option<String>,
+    /// The series ticker and the slug of its title, which make up the market page path.
+    series_path: Option<(String, String)>,
+}
+
 /// API response after requesting multiple markets from `/markets`.
 #[derive(Deserialize, Debug)]
 struct BulkMarketResponse {
@@ -72,7 +112,9 @@ struct BulkEventResponse {
 #[derive(Debug)]
 struct MarketFull {
     market: MarketInfo,
+    event_details: EventDetails,
     events: Vec<ProbUpdate>,
+    options: StandardizeOptions,
 }
 
 impl MarketStandardizer for MarketFull {
@@ -89,6 +131,16 @@ impl MarketStandardizer for MarketFull {
         self.market.ticker.to_owned()
     }
     fn url(&self) -> String {
+        if let Some((series_ticker, series_slug)) = &self.event_details.series_path {
+            return format!(
+                "{}{}/{}#{}",
+                KALSHI_SITE_BASE,
+                series_ticker.to_lowercase(),
+                series_slug,
+                self.market.event_ticker.to_lowercase()
+            );
+        }
+        // without the series slug the site redirects to the first event in the series
         let ticker_regex = Regex::new(r"^(\w+)-").unwrap();
         let ticker_prefix =
             if let Some(ticker_regex_result) = ticker_regex.captures(&self.market.event_ticker) {
@@ -108,9 +160,15 @@ impl MarketStandardizer for MarketFull {
     fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         Ok(self.market.open_time)
     }
-    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         Ok(self.market.close_time)
     }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        self.market.expiration_time
+    }
     fn volume_usd(&self) -> f32 {
         self.market.volume / KALSHI_EXCHANGE_RATE
     }
@@ -133,9 +191,15 @@ impl MarketStandardizer for MarketFull {
             _ => "None".to_string(),
         }
     }
+    fn description(&self) -> String {
+        self.market.rules_primary.clone().unwrap_or_default()
+    }
     fn events(&self) -> Vec<ProbUpdate> {
         self.events.to_owned()
     }
+    fn linked_group(&self) -> Option<String> {
+        self.event_details.linked_group.clone()
+    }
     fn resolution(&self) -> Result<f32, MarketConvertError> {
         match self.market.result.as_str() {
             "yes" => Ok(1.0),
@@ -160,27 +224,55 @@ impl TryInto<MarketStandard> for MarketFull {
             url: self.url(),
             open_dt: self.open_dt()?,
             close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
             open_days: self.open_days()?,
             volume_usd: self.volume_usd(),
             num_traders: self.num_traders(),
             category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
             prob_at_midpoint: self.prob_at_percent(0.5)?,
             prob_at_close: self.prob_at_percent(1.0)?,
             prob_each_pct: self.prob_each_pct_list()?,
             prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
             prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
             resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
         })
     }
 }
 
 /// Test if a market is suitable for analysis.
-fn is_valid(market: &MarketInfo) -> bool {
+fn is_valid(stats: &RunStats, market: &MarketInfo) -> bool {
+    check_known_value(
+        stats,
+        "Kalshi",
+        "market_type",
+        &market.market_type,
+        &["binary", "scalar"],
+    );
     market.status == "finalized" && market.market_type == "binary"
 }
 
 /// Request an authorization token from email & password.
-async fn get_login_token(client_opt: Option<ClientWithMiddleware>) -> String {
+async fn get_login_token(stats: &RunStats, client_opt: Option<ClientWithMiddleware>) -> String {
     let client = match client_opt {
         Some(client) => client,
         None => get_reqwest_client_ratelimited(KALSHI_RATELIMIT, None),
@@ -193,7 +285,7 @@ async fn get_login_token(client_opt: Option<ClientWithMiddleware>) -> String {
         password: var("KALSHI_PASSWORD")
             .expect("Required environment variable KALSHI_PASSWORD not set."),
     };
-    let response: LoginResponse = send_request(client.post(api_url).json(&credentials))
+    let response: LoginResponse = send_request(stats, client.post(api_url).json(&credentials))
         .await
         .expect("Kalshi: Login failed.");
     response.token
@@ -217,11 +309,142 @@ fn get_prob_updates(mut events: Vec<EventInfo>) -> Result<Vec<ProbUpdate>, Marke
     Ok(result)
 }
 
+/// Get the event a market belongs to.
+async fn get_event(
+    stats: &RunStats,
+    client: &ClientWithMiddleware,
+    token: &String,
+    event_ticker: &str,
+) -> Result<ParentEventInfo, MarketConvertError> {
+    let api_url = KALSHI_API_BASE.to_owned() + "/events/" + event_ticker;
+    let response: SingleEventResponse =
+        send_request(stats, client.get(&api_url).bearer_auth(token)).await?;
+    Ok(response.event)
+}
+
+/// Get the slug the site uses for a series in market URLs, which is made from its title.
+async fn get_series_slug(
+    stats: &RunStats,
+    client: &ClientWithMiddleware,
+    token: &String,
+    series_ticker: &str,
+) -> Result<String, MarketConvertError> {
+    let api_url = KALSHI_API_BASE.to_owned() + "/series/" + series_ticker;
+    let response: SingleSeriesResponse =
+        send_request(stats, client.get(&api_url).bearer_auth(token)).await?;
+    Ok(slugify(&response.series.title))
+}
+
+/// Convert a title into a URL slug, e.g. `Fed rate: March?` becomes `fed-rate-march`.
+fn slugify(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Get the details needed from an event, given the slug of its series if it was found.
+fn get_event_details(event: &ParentEventInfo, series_slug: Option<String>) -> EventDetails {
+    EventDetails {
+        linked_group: event.mutually_exclusive.then(|| event.event_ticker.clone()),
+        series_path: series_slug.map(|slug| (event.series_ticker.clone(), slug)),
+    }
+}
+
+/// Details for each event and series seen so far, kept across pages
+/// so each one is only requested once per run.
+#[derive(Debug, Default)]
+struct EventCache {
+    events: HashMap<String, EventDetails>,
+    series_slugs: HashMap<String, Option<String>>,
+}
+
+impl EventCache {
+    /// Look up each event that isn't cached yet, along with any new series, and cache them.
+    /// Events or series that fail to download are cached as empty so the markets still save.
+    async fn update(
+        &mut self,
+        stats: &RunStats,
+        client: &ClientWithMiddleware,
+        token: &String,
+        event_tickers: Vec<&String>,
+        verbose: bool,
+    ) {
+        let mut new_tickers: Vec<&String> = event_tickers
+            .into_iter()
+            .filter(|ticker| !self.events.contains_key(*ticker))
+            .collect();
+        new_tickers.sort_unstable();
+        new_tickers.dedup();
+        let events: Vec<(&String, Option<ParentEventInfo>)> = new_tickers
+            .iter()
+            .copied()
+            .zip(
+                join_all(
+                    new_tickers
+                        .iter()
+                        .map(|ticker| get_event(stats, client, token, ticker)),
+                )
+                .await,
+            )
+            .map(|(ticker, result)| match result {
+                Ok(event) => (ticker, Some(event)),
+                Err(error) => {
+                    eval_error(error, verbose);
+                    (ticker, None)
+                }
+            })
+            .collect();
+
+        let mut new_series: Vec<&String> = events
+            .iter()
+            .filter_map(|(_, event)| event.as_ref())
+            .map(|event| &event.series_ticker)
+            .filter(|ticker| !self.series_slugs.contains_key(*ticker))
+            .collect();
+        new_series.sort_unstable();
+        new_series.dedup();
+        let slugs = join_all(
+            new_series
+                .iter()
+                .map(|ticker| get_series_slug(stats, client, token, ticker)),
+        )
+        .await;
+        for (ticker, result) in new_series.into_iter().zip(slugs) {
+            let slug = result.map_err(|error| eval_error(error, verbose)).ok();
+            self.series_slugs.insert(ticker.clone(), slug);
+        }
+
+        for (ticker, event) in events {
+            let details = match event {
+                Some(event) => get_event_details(
+                    &event,
+                    self.series_slugs
+                        .get(&event.series_ticker)
+                        .cloned()
+                        .flatten(),
+                ),
+                None => EventDetails::default(),
+            };
+            self.events.insert(ticker.clone(), details);
+        }
+    }
+
+    /// Get the cached details for an event, or empty details if it was never looked up.
+    fn get(&self, event_ticker: &str) -> EventDetails {
+        self.events.get(event_ticker).cloned().unwrap_or_default()
+    }
+}
+
 /// Download full market history and store events in the container.
 async fn get_extended_data(
     client: &ClientWithMiddleware,
     token: &String,
     market: &MarketInfo,
+    event_details: EventDetails,
+    config: &RunConfig,
 ) -> Result<MarketFull, MarketConvertError> {
     let ticker_urlencoded = Regex::new(r"%").unwrap().replace_all(&market.ticker, "%25");
     let api_url = KALSHI_API_BASE.to_owned() + "/markets/" + &ticker_urlencoded + "/history";
@@ -230,6 +453,7 @@ async fn get_extended_data(
     let mut all_bet_data = Vec::new();
     loop {
         let response: BulkEventResponse = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .bearer_auth(token)
@@ -247,26 +471,76 @@ async fn get_extended_data(
     }
     Ok(MarketFull {
         market: market.clone(),
+        event_details,
         events: get_prob_updates(all_bet_data)?,
+        options: config.options,
     })
 }
 
+/// Every API response needed to standardize one market, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct MarketBundle {
+    /// The market, same as from `/markets/{ticker}`.
+    market: MarketInfo,
+    /// Every price history point from `/markets/{ticker}/history`.
+    history: Vec<EventInfo>,
+    /// The event the market belongs to, from `/events/{event_ticker}`.
+    #[serde(default)]
+    event: Option<ParentEventInfo>,
+    /// The series the event belongs to, from `/series/{series_ticker}`.
+    #[serde(default)]
+    series: Option<SeriesInfo>,
+}
+
+/// Standardize one market from a JSON bundle of its API responses without downloading anything.
+/// Returns an empty list if the market would be skipped by a normal run.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let bundle: MarketBundle = parse_bundle("Kalshi", bytes)?;
+    if !is_valid(&RunStats::default(), &bundle.market) {
+        return Ok(Vec::new());
+    }
+    let event_details = match &bundle.event {
+        Some(event) => get_event_details(
+            event,
+            bundle.series.as_ref().map(|series| slugify(&series.title)),
+        ),
+        None => EventDetails::default(),
+    };
+    let market = MarketFull {
+        market: bundle.market,
+        event_details,
+        events: get_prob_updates(bundle.history)?,
+        options,
+    };
+    Ok(Vec::from([market.try_into()?]))
+}
+
 /// Download, process and store all valid markets from the platform.
-pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
     log_to_stdout("Kalshi: Processing started...");
     let client = get_reqwest_client_ratelimited(KALSHI_RATELIMIT, None);
-    let token = get_login_token(Some(client.clone())).await;
+    let token = get_login_token(&config.stats, Some(client.clone())).await;
     let api_url = KALSHI_API_BASE.to_owned() + "/markets";
     if verbose {
         println!("Kalshi: Connecting to API at {}", api_url)
     }
     let limit: usize = 1000;
     let mut cursor: Option<String> = None;
+    let mut event_cache = EventCache::default();
     loop {
         if verbose {
             println!("Kalshi: Getting markets starting at {:?}...", cursor)
         }
         let response: BulkMarketResponse = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .bearer_auth(&token)
@@ -278,11 +552,27 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
         if verbose {
             println!("Kalshi: Processing {} markets...", response.markets.len())
         }
-        let market_data_futures: Vec<_> = response
+        let markets: Vec<&MarketInfo> = response
             .markets
             .iter()
-            .filter(|market| is_valid(market))
-            .map(|market| get_extended_data(&client, &token, market))
+            .filter(|market| is_valid(&config.stats, market))
+            .filter(|market| is_in_sample(&market.ticker, sample))
+            .collect();
+        event_cache
+            .update(
+                &config.stats,
+                &client,
+                &token,
+                markets.iter().map(|market| &market.event_ticker).collect(),
+                verbose,
+            )
+            .await;
+        let market_data_futures: Vec<_> = markets
+            .into_iter()
+            .map(|market| {
+                let event_details = event_cache.get(&market.event_ticker);
+                get_extended_data(&client, &token, market, event_details, config)
+            })
             .collect();
         let market_data: Vec<MarketStandard> = join_all(market_data_futures)
             .await
@@ -290,7 +580,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             .filter_map(|market_downloaded_result| match market_downloaded_result {
                 Ok(market_downloaded) => {
                     // market downloaded successfully
-                    match market_downloaded.try_into() {
+                    match convert_market(&config.stats, market_downloaded) {
                         // market processed successfully
                         Ok(market_converted) => Some(market_converted),
                         // market failed processing
@@ -314,7 +604,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
                 output_method
             )
         }
-        save_markets(market_data, output_method);
+        save_markets(config, market_data, output_method);
         if response.cursor.len() > 1 {
             cursor = Some(response.cursor);
         } else {
@@ -325,32 +615,56 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
 }
 
 /// Download, process and store one market from the platform.
-pub async fn get_market_by_id(id: &String, output_method: OutputMethod, verbose: bool) {
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
     let client = get_reqwest_client_ratelimited(KALSHI_RATELIMIT, None);
-    let token = get_login_token(Some(client.clone())).await;
+    let token = get_login_token(&config.stats, Some(client.clone())).await;
     let api_url = KALSHI_API_BASE.to_owned() + "/markets/";
     if verbose {
         println!("Kalshi: Connecting to API at {}", api_url)
     }
-    let market_single: SingleMarketResponse =
-        send_request(client.get(api_url.clone() + id).bearer_auth(&token))
-            .await
-            .expect("Kalshi: API query error.");
-    if !is_valid(&market_single.market) {
+    let market_single: SingleMarketResponse = send_request(
+        &config.stats,
+        client.get(api_url.clone() + id).bearer_auth(&token),
+    )
+    .await
+    .expect("Kalshi: API query error.");
+    if !is_valid(&config.stats, &market_single.market) {
         println!("Kalshi: Market is not valid for processing, this may fail.")
     }
-    let market_data: MarketStandard = get_extended_data(&client, &token, &market_single.market)
-        .await
-        .expect("Error getting extended market data")
-        .try_into()
-        .expect("Error converting market into standard fields");
+    let mut event_cache = EventCache::default();
+    event_cache
+        .update(
+            &config.stats,
+            &client,
+            &token,
+            Vec::from([&market_single.market.event_ticker]),
+            verbose,
+        )
+        .await;
+    let market_data: MarketStandard = get_extended_data(
+        &client,
+        &token,
+        &market_single.market,
+        event_cache.get(&market_single.market.event_ticker),
+        config,
+    )
+    .await
+    .expect("Error getting extended market data")
+    .try_into()
+    .expect("Error converting market into standard fields");
     if verbose {
         println!("Kalshi: Saving processed market to {:?}...", output_method)
     }
-    save_markets(Vec::from([market_data]), output_method);
+    save_markets(config, Vec::from([market_data]), output_method);
 }
 
 /// Get a new token if the old one expired.
+#[allow(dead_code)]
 struct FetchTokenMiddleware;
 
 #[async_trait::async_trait]
@@ -369,8 +683,11 @@ impl Chainer for FetchTokenMiddleware {
         };
         request.headers_mut().insert(
             AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", get_login_token(None).await))
-                .expect("invalid header value"),
+            HeaderValue::from_str(&format!(
+                "Bearer {}",
+                get_login_token(&RunStats::default(), None).await
+            ))
+            .expect("invalid header value"),
         );
         Ok(None)
     }
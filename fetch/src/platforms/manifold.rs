@@ -1,12 +1,31 @@
 //! Tools to download and process markets from the Manifold API.
 
 use super::*;
-use std::cmp;
 
 const MANIFOLD_API_BASE: &str = "https://api.manifold.markets/v0";
 const MANIFOLD_SITE_BASE: &str = "https://manifold.markets/";
 const MANIFOLD_EXCHANGE_RATE: f32 = 100.0;
 const MANIFOLD_RATELIMIT: usize = 15;
+/// Every outcome type we know about, used to flag new ones.
+const MANIFOLD_KNOWN_OUTCOME_TYPES: [&str; 13] = [
+    "BINARY",
+    "BOUNTIED_QUESTION",
+    "CERT",
+    "DATE",
+    "FREE_RESPONSE",
+    "MULTIPLE_CHOICE",
+    "MULTI_NUMERIC",
+    "NUMBER",
+    "NUMERIC",
+    "POLL",
+    "PSEUDO_NUMERIC",
+    "QUADRATIC_FUNDING",
+    "STONK",
+];
+/// Outcome types that are multiple-choice under the hood, where each answer is saved as its own market.
+const MANIFOLD_MULTI_OUTCOME_TYPES: [&str; 3] = ["DATE", "MULTI_NUMERIC", "NUMBER"];
+/// Market resolutions that split the payout between several answers by weight.
+const MANIFOLD_SPLIT_RESOLUTIONS: [&str; 2] = ["CHOOSE_MULTIPLE", "MKT"];
 
 /// API response with standard market info from `/markets`.
 #[allow(non_snake_case)]
@@ -37,6 +56,34 @@ struct MarketInfo {
 #[derive(Deserialize, Debug, Clone)]
 struct MarketInfoExtra {
     groupSlugs: Option<Vec<String>>,
+    #[serde(default)]
+    textDescription: Option<String>,
+    #[serde(default)]
+    coverImageUrl: Option<String>,
+    #[serde(default)]
+    answers: Vec<AnswerInfo>,
+    /// Weight given to each answer ID when a multiple-choice market resolves to more than one.
+    #[serde(default)]
+    resolutions: Option<HashMap<String, f32>>,
+    /// Whether the answers of a multiple-choice market are linked so exactly one can win.
+    #[serde(default)]
+    shouldAnswersSumToOne: bool,
+}
+
+/// (Indirect) API response with the info for each answer in a multiple-choice market.
+/// For NUMBER, MULTI_NUMERIC, and DATE markets each answer is a bucket covering a range of values.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+struct AnswerInfo {
+    id: String,
+    text: String,
+    /// The position of the answer in the market, which for buckets is their numeric order.
+    #[serde(default)]
+    index: Option<i32>,
+    #[serde(default)]
+    resolution: Option<String>,
+    #[serde(default)]
+    resolutionProbability: Option<f32>,
 }
 
 /// API response with standard bet info from `/bets`.
@@ -47,9 +94,13 @@ struct Bet {
     userId: String,
     #[serde(with = "ts_milliseconds")]
     createdTime: DateTime<Utc>,
-    //probBefore: Option<f32>,
+    #[serde(default)]
+    answerId: Option<String>,
+    probBefore: Option<f32>,
     probAfter: Option<f32>,
-    //amount: f32,
+    /// Mana spent on the bet, negative when selling shares.
+    #[serde(default)]
+    amount: f32,
     //shares: f32,
     //outcome: f32,
 }
@@ -59,8 +110,11 @@ struct Bet {
 struct MarketFull {
     market: MarketInfo,
     market_extra: MarketInfoExtra,
+    /// The answer this container tracks, if the market is multiple-choice.
+    answer: Option<AnswerInfo>,
     bets: Vec<Bet>,
     events: Vec<ProbUpdate>,
+    options: StandardizeOptions,
 }
 
 impl MarketStandardizer for MarketFull {
@@ -68,13 +122,19 @@ impl MarketStandardizer for MarketFull {
         format!("{:?}", self)
     }
     fn title(&self) -> String {
-        self.market.question.to_owned()
+        match &self.answer {
+            Some(answer) => format!("{}: {}", self.market.question, answer.text),
+            None => self.market.question.to_owned(),
+        }
     }
     fn platform(&self) -> String {
         "manifold".to_string()
     }
     fn platform_id(&self) -> String {
-        self.market.id.to_owned()
+        match &self.answer {
+            Some(answer) => format!("{}-{}", self.market.id, answer.id),
+            None => self.market.id.to_owned(),
+        }
     }
     fn url(&self) -> String {
         MANIFOLD_SITE_BASE.to_owned() + &self.market.creatorUsername + "/" + &self.market.slug
@@ -82,7 +142,10 @@ impl MarketStandardizer for MarketFull {
     fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         Ok(self.market.createdTime)
     }
-    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         match (self.market.closeTime, self.market.resolutionTime) {
             // both close and resolution times are present
             (Some(close_time), Some(resolution_time)) => {
@@ -90,13 +153,12 @@ impl MarketStandardizer for MarketFull {
                     // close time was set in the past, use resolution time instead
                     Ok(resolution_time)
                 } else {
-                    // close time and resolution time were both after created time, take whichever came first
-                    Ok(cmp::min(close_time, resolution_time))
+                    Ok(close_time)
                 }
             }
-            // only resolution time is present
-            (Some(close_time), None) => Ok(close_time),
             // only close time is present
+            (Some(close_time), None) => Ok(close_time),
+            // only resolution time is present
             (None, Some(resolution_time)) => Ok(resolution_time),
             // neither is present
             (None, None) => Err(MarketConvertError {
@@ -107,6 +169,9 @@ impl MarketStandardizer for MarketFull {
             }),
         }
     }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        self.market.resolutionTime
+    }
     fn volume_usd(&self) -> f32 {
         self.market.volume / MANIFOLD_EXCHANGE_RATE
     }
@@ -208,10 +273,67 @@ impl MarketStandardizer for MarketFull {
         }
         "None".to_string()
     }
+    fn description(&self) -> String {
+        // the rich-text `description` is TipTap JSON, so use the plain text rendering instead
+        self.market_extra
+            .textDescription
+            .clone()
+            .unwrap_or_default()
+    }
+    fn image_url(&self) -> Option<String> {
+        self.market_extra.coverImageUrl.clone()
+    }
     fn events(&self) -> Vec<ProbUpdate> {
         self.events.to_owned()
     }
+    fn linked_group(&self) -> Option<String> {
+        // only answers where exactly one can win make up a distribution
+        self.answer.as_ref()?;
+        (self.market_extra.shouldAnswersSumToOne
+            || MANIFOLD_MULTI_OUTCOME_TYPES.contains(&self.market.outcomeType.as_str()))
+        .then(|| self.market.id.clone())
+    }
+    fn linked_order(&self) -> Option<i32> {
+        let answer = self.answer.as_ref()?;
+        if !MANIFOLD_MULTI_OUTCOME_TYPES.contains(&self.market.outcomeType.as_str()) {
+            return None;
+        }
+        answer.index.or_else(|| {
+            self.market_extra
+                .answers
+                .iter()
+                .position(|a| a.id == answer.id)
+                .map(|position| position as i32)
+        })
+    }
+    fn impact_events(&self) -> Vec<ImpactUpdate> {
+        let mut bets: Vec<&Bet> = self.bets.iter().filter(|bet| bet.amount != 0.0).collect();
+        bets.sort_unstable_by_key(|bet| bet.createdTime);
+        bets.into_iter()
+            .filter_map(|bet| match (bet.probBefore, bet.probAfter) {
+                (Some(before), Some(after)) => Some(ImpactUpdate {
+                    time: bet.createdTime,
+                    prob_change_per_usd: (after - before).abs()
+                        / (bet.amount.abs() / MANIFOLD_EXCHANGE_RATE),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+    fn volume_events(&self) -> Vec<VolumeUpdate> {
+        self.bets
+            .iter()
+            .filter(|bet| bet.amount != 0.0)
+            .map(|bet| VolumeUpdate {
+                time: bet.createdTime,
+                volume_usd: bet.amount.abs() / MANIFOLD_EXCHANGE_RATE,
+            })
+            .collect()
+    }
     fn resolution(&self) -> Result<f32, MarketConvertError> {
+        if let Some(answer) = &self.answer {
+            return get_answer_resolution(self, answer);
+        }
         match &self.market.resolution {
             Some(resolution_text) => match resolution_text.as_str() {
                 "YES" => Ok(1.0),
@@ -244,6 +366,32 @@ impl MarketStandardizer for MarketFull {
     }
 }
 
+impl MarketFull {
+    /// Group the buys on this market by trader, with the price each one moved the market to.
+    /// Sells are left out since they close a position instead of stating a belief.
+    /// Each trader is identified by their ID anonymized with the salt.
+    fn trader_bets(&self, salt: &str) -> Vec<TraderBets> {
+        let mut bets: Vec<&Bet> = self.bets.iter().filter(|bet| bet.amount > 0.0).collect();
+        bets.sort_unstable_by_key(|bet| bet.createdTime);
+        let mut by_trader: BTreeMap<&str, TraderBets> = BTreeMap::new();
+        for bet in bets {
+            let Some(prob) = bet.probAfter else {
+                continue;
+            };
+            let entry = by_trader.entry(&bet.userId).or_insert_with(|| TraderBets {
+                platform: self.platform(),
+                platform_id: self.platform_id(),
+                trader_id: get_trader_id(salt, &bet.userId),
+                bet_probs: Vec::new(),
+                last_bet_dt: bet.createdTime,
+            });
+            entry.bet_probs.push(prob);
+            entry.last_bet_dt = bet.createdTime;
+        }
+        by_trader.into_values().collect()
+    }
+}
+
 /// Standard conversion setup (would move this up to `platforms` if I could).
 impl TryInto<MarketStandard> for MarketFull {
     type Error = MarketConvertError;
@@ -255,32 +403,138 @@ impl TryInto<MarketStandard> for MarketFull {
             url: self.url(),
             open_dt: self.open_dt()?,
             close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
             open_days: self.open_days()?,
             volume_usd: self.volume_usd(),
             num_traders: self.num_traders(),
             category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
             prob_at_midpoint: self.prob_at_percent(0.5)?,
             prob_at_close: self.prob_at_percent(1.0)?,
             prob_each_pct: self.prob_each_pct_list()?,
             prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
             prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
             resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
         })
     }
 }
 
+/// Get the resolution of a single answer in a multiple-choice market.
+fn get_answer_resolution(
+    market: &MarketFull,
+    answer: &AnswerInfo,
+) -> Result<f32, MarketConvertError> {
+    // answers that resolve independently report their own resolution
+    match answer.resolution.as_deref() {
+        Some("YES") => return Ok(1.0),
+        Some("NO") => return Ok(0.0),
+        Some("MKT") => {
+            return answer.resolutionProbability.ok_or(MarketConvertError {
+                data: market.debug(),
+                message: "Manifold: Answer resolved to MKT but is missing resolutionProbability"
+                    .to_string(),
+                level: 3,
+            })
+        }
+        _ => (),
+    }
+    // otherwise the market resolution is the ID of the winning answer
+    match market.market.resolution.as_deref() {
+        Some(resolution) if resolution == answer.id => Ok(1.0),
+        Some(resolution) if MANIFOLD_SPLIT_RESOLUTIONS.contains(&resolution) => {
+            get_split_resolution(&market.market_extra, &answer.id).ok_or(MarketConvertError {
+                data: market.debug(),
+                message: "Manifold: Market resolved to multiple answers but is missing resolutions"
+                    .to_string(),
+                level: 3,
+            })
+        }
+        Some(_) => Ok(0.0),
+        None => Err(MarketConvertError {
+            data: market.debug(),
+            message: "Manifold: Market resolved without `resolution` value".to_string(),
+            level: 3,
+        }),
+    }
+}
+
+/// Get an answer's share of a split resolution, as a fraction of the total weight.
+/// Answers left out of the split get nothing.
+fn get_split_resolution(market_extra: &MarketInfoExtra, answer_id: &str) -> Option<f32> {
+    let resolutions = market_extra.resolutions.as_ref()?;
+    let total_weight: f32 = resolutions.values().sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    Some(resolutions.get(answer_id).copied().unwrap_or(0.0) / total_weight)
+}
+
+/// Test if a market resolved by splitting the payout between several answers.
+fn is_split_resolution(market: &MarketInfo) -> bool {
+    market
+        .resolution
+        .as_deref()
+        .is_some_and(|resolution| MANIFOLD_SPLIT_RESOLUTIONS.contains(&resolution))
+}
+
+/// Test if a market is multiple-choice, where each answer is tracked as its own market.
+/// Regular multiple-choice markets are only included when the market itself was resolved,
+/// either to a single answer or split between several.
+fn is_multi(market: &MarketInfo) -> bool {
+    market.mechanism == "cpmm-multi-1"
+        && (MANIFOLD_MULTI_OUTCOME_TYPES.contains(&market.outcomeType.as_str())
+            || (market.outcomeType == "MULTIPLE_CHOICE" && market.resolution.is_some()))
+}
+
+/// Test if an answer won any share of a multiple-choice market's resolution.
+fn is_winning_answer(market: &MarketInfo, market_extra: &MarketInfoExtra, answer_id: &str) -> bool {
+    market.resolution.as_deref() == Some(answer_id)
+        || (is_split_resolution(market)
+            && get_split_resolution(market_extra, answer_id).is_some_and(|r| r > 0.0))
+}
+
 /// Test if a market is suitable for analysis.
-fn is_valid(market: &MarketInfo) -> bool {
+fn is_valid(stats: &RunStats, market: &MarketInfo) -> bool {
+    check_known_value(
+        stats,
+        "Manifold",
+        "outcomeType",
+        &market.outcomeType,
+        &MANIFOLD_KNOWN_OUTCOME_TYPES,
+    );
     market.isResolved
-        && market.mechanism == "cpmm-1"
-        && market.outcomeType == "BINARY"
+        && ((market.mechanism == "cpmm-1" && market.outcomeType == "BINARY") || is_multi(market))
         && market.volume > 0.0
         && market.resolution != Some("CANCEL".to_string())
 }
 
 /// Convert API events into standard events.
-fn get_prob_updates(mut bets: Vec<Bet>) -> Result<Vec<ProbUpdate>, MarketConvertError> {
+/// If an answer ID is given, only bets on that answer are included.
+fn get_prob_updates(
+    mut bets: Vec<Bet>,
+    answer_id: Option<&str>,
+) -> Result<Vec<ProbUpdate>, MarketConvertError> {
     let mut result = Vec::new();
+    bets.retain(|bet| answer_id.is_none() || bet.answerId.as_deref() == answer_id);
     bets.sort_unstable_by_key(|b| b.createdTime);
     for bet in bets {
         if let Some(prob) = bet.probAfter {
@@ -295,10 +549,12 @@ fn get_prob_updates(mut bets: Vec<Bet>) -> Result<Vec<ProbUpdate>, MarketConvert
 }
 
 /// Download full market history and store events in the container.
+/// Multiple-choice markets are split into one container per answer.
 async fn get_extended_data(
     client: &ClientWithMiddleware,
     market: &MarketInfo,
-) -> Result<MarketFull, MarketConvertError> {
+    config: &RunConfig,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
     // get trade info from /bets
     let api_url = MANIFOLD_API_BASE.to_owned() + "/bets";
     let limit = 1000;
@@ -306,6 +562,7 @@ async fn get_extended_data(
     let mut all_bet_data: Vec<Bet> = Vec::new();
     loop {
         let bet_data: Vec<Bet> = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .query(&[("contractId", &market.id)])
@@ -324,19 +581,103 @@ async fn get_extended_data(
 
     // get extra data from /market
     let api_url = MANIFOLD_API_BASE.to_owned() + "/market/" + &market.id;
-    let market_extra: MarketInfoExtra = send_request(client.get(&api_url)).await?;
+    let market_extra: MarketInfoExtra = send_request(&config.stats, client.get(&api_url)).await?;
 
-    // save
-    Ok(MarketFull {
-        market: market.clone(),
-        market_extra,
-        bets: all_bet_data.clone(),
-        events: get_prob_updates(all_bet_data)?,
-    })
+    split_by_answer(market, market_extra, all_bet_data, config.options)
+}
+
+/// Build the containers for a market from its downloaded data.
+/// Multiple-choice markets are split into one container per answer.
+fn split_by_answer(
+    market: &MarketInfo,
+    market_extra: MarketInfoExtra,
+    all_bet_data: Vec<Bet>,
+    options: StandardizeOptions,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
+    if !is_multi(market) {
+        return Ok(Vec::from([MarketFull {
+            market: market.clone(),
+            market_extra,
+            answer: None,
+            bets: all_bet_data.clone(),
+            events: get_prob_updates(all_bet_data, None)?,
+            options,
+        }]));
+    }
+    let mut result = Vec::with_capacity(market_extra.answers.len());
+    for answer in &market_extra.answers {
+        // every answer of a linked multiple-choice market is saved, with the losers resolving NO,
+        // but only the winners of an unlinked one since the rest may not have been resolved
+        if market.outcomeType == "MULTIPLE_CHOICE"
+            && !market_extra.shouldAnswersSumToOne
+            && !is_winning_answer(market, &market_extra, &answer.id)
+        {
+            continue;
+        }
+        let answer_bets: Vec<Bet> = all_bet_data
+            .iter()
+            .filter(|bet| bet.answerId.as_ref() == Some(&answer.id))
+            .cloned()
+            .collect();
+        result.push(MarketFull {
+            market: market.clone(),
+            market_extra: market_extra.clone(),
+            answer: Some(answer.clone()),
+            events: get_prob_updates(answer_bets.clone(), Some(&answer.id))?,
+            bets: answer_bets,
+            options,
+        });
+    }
+    Ok(result)
+}
+
+/// The full market from `/market/{id}`, which has both the standard and extended info.
+#[derive(Deserialize, Debug)]
+struct MarketResponse {
+    #[serde(flatten)]
+    info: MarketInfo,
+    #[serde(flatten)]
+    extra: MarketInfoExtra,
+}
+
+/// Every API response needed to standardize one market, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct MarketBundle {
+    /// The market, same as from `/market/{id}`.
+    market: MarketResponse,
+    /// Every bet on the market from `/bets`, in any order.
+    bets: Vec<Bet>,
+}
+
+/// Standardize one market from a JSON bundle of its API responses without downloading anything.
+/// Multiple-choice markets return one market per answer, and markets that would be skipped
+/// by a normal run return an empty list.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let bundle: MarketBundle = parse_bundle("Manifold", bytes)?;
+    if !is_valid(&RunStats::default(), &bundle.market.info) {
+        return Ok(Vec::new());
+    }
+    split_by_answer(
+        &bundle.market.info,
+        bundle.market.extra,
+        bundle.bets,
+        options,
+    )?
+    .into_iter()
+    .map(|market| market.try_into())
+    .collect()
 }
 
 /// Download, process and store all valid markets from the platform.
-pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
     log_to_stdout("Manifold: Processing started...");
     let client = get_reqwest_client_ratelimited(MANIFOLD_RATELIMIT, None);
     let api_url = MANIFOLD_API_BASE.to_owned() + "/markets";
@@ -350,6 +691,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             println!("Manifold: Getting markets starting at {:?}...", before)
         }
         let market_response: Vec<MarketInfo> = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .query(&[("limit", limit)])
@@ -362,32 +704,21 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
         }
         let market_data_futures: Vec<_> = market_response
             .iter()
-            .filter(|market| is_valid(market))
-            .map(|market| get_extended_data(&client, market))
-            .collect();
-        let market_data: Vec<MarketStandard> = join_all(market_data_futures)
-            .await
-            .into_iter()
-            .filter_map(|market_downloaded_result| match market_downloaded_result {
-                Ok(market_downloaded) => {
-                    // market downloaded successfully
-                    match market_downloaded.try_into() {
-                        // market processed successfully
-                        Ok(market_converted) => Some(market_converted),
-                        // market failed processing
-                        Err(error) => {
-                            eval_error(error, verbose);
-                            None
-                        }
-                    }
-                }
-                Err(error) => {
-                    // market failed downloadng
-                    eval_error(error, verbose);
-                    None
-                }
-            })
+            .filter(|market| is_valid(&config.stats, market))
+            .filter(|market| is_in_sample(&market.id, sample))
+            .map(|market| get_extended_data(&client, market, config))
             .collect();
+        let market_data_results = join_all(market_data_futures).await;
+        if let Some(salt) = &config.trader_salt {
+            let trader_bets = market_data_results
+                .iter()
+                .flatten()
+                .flatten()
+                .flat_map(|market| market.trader_bets(salt))
+                .collect();
+            save_trader_bets(config, trader_bets, output_method);
+        }
+        let market_data = convert_markets(&config.stats, market_data_results, verbose);
         if verbose {
             println!(
                 "Manifold: Saving {} processed markets to {:?}...",
@@ -395,7 +726,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
                 output_method
             )
         }
-        save_markets(market_data, output_method);
+        save_markets(config, market_data, output_method);
         if market_response.len() == limit {
             before = Some(market_response.last().unwrap().id.clone());
         } else {
@@ -406,28 +737,47 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
 }
 
 /// Download, process and store one market from the platform.
-pub async fn get_market_by_id(id: &str, output_method: OutputMethod, verbose: bool) {
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
     let client = get_reqwest_client_ratelimited(MANIFOLD_RATELIMIT, None);
     let api_url = MANIFOLD_API_BASE.to_owned() + "/market/" + id;
     if verbose {
         println!("Manifold: Connecting to API at {}", api_url)
     }
-    let market_single: MarketInfo = send_request(client.get(&api_url))
+    let market_single: MarketInfo = send_request(&config.stats, client.get(&api_url))
         .await
         .expect("Manifold: API query error.");
-    if !is_valid(&market_single) {
+    if !is_valid(&config.stats, &market_single) {
         println!("Manifold: Market is not valid for processing, this may fail.")
     }
-    let market_data = get_extended_data(&client, &market_single)
+    let market_data_full = get_extended_data(&client, &market_single, config)
         .await
-        .expect("Error getting extended market data")
-        .try_into()
-        .expect("Error converting market into standard fields");
+        .expect("Error getting extended market data");
+    if let Some(salt) = &config.trader_salt {
+        let trader_bets = market_data_full
+            .iter()
+            .flat_map(|market| market.trader_bets(salt))
+            .collect();
+        save_trader_bets(config, trader_bets, output_method);
+    }
+    let market_data: Vec<MarketStandard> = market_data_full
+        .into_iter()
+        .map(|market| {
+            market
+                .try_into()
+                .expect("Error converting market into standard fields")
+        })
+        .collect();
     if verbose {
         println!(
-            "Manifold: Saving processed market to {:?}...",
+            "Manifold: Saving {} processed markets to {:?}...",
+            market_data.len(),
             output_method
         )
     }
-    save_markets(Vec::from([market_data]), output_method);
+    save_markets(config, market_data, output_method);
 }
@@ -24,19 +24,46 @@ struct MarketInfo {
     prediction_count: u32,
     created_time: DateTime<Utc>,
     effected_close_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    resolve_time: Option<DateTime<Utc>>,
     possibilities: MarketTypePossibilities,
     community_prediction: PredictionHistory,
     resolution: Option<f32>,
+    /// The kind of page, such as a standalone forecast or a conditional pair.
+    #[serde(rename = "type", default)]
+    page_type: Option<String>,
+    /// For conditional pairs, the questions conditioned on the parent resolving YES or NO.
+    /// For question groups, one question per option, each resolving YES or NO.
+    #[serde(default)]
+    sub_questions: Vec<serde_json::Value>,
+    /// The question group this is one option of, set when the group is split.
+    #[serde(skip)]
+    group_id: Option<u32>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct MarketInfoExtra {
     categories: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+    /// Tournaments, tags, and other projects the question is posted in.
+    #[serde(default)]
+    projects: Vec<ProjectInfo>,
+}
+
+/// (Indirect) API response with a project the question belongs to.
+#[derive(Deserialize, Debug, Clone)]
+struct ProjectInfo {
+    #[serde(default)]
+    slug: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct MarketTypePossibilities {
     r#type: Option<String>,
+    /// Whether a continuous question is over numbers or dates.
+    #[serde(default)]
+    format: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -47,14 +74,48 @@ struct PredictionHistory {
 #[derive(Deserialize, Debug, Clone)]
 struct PredictionPoint {
     t: f32,
-    x2: PredictionPointX2,
+    #[serde(default)]
+    x1: Option<PredictionPointX1>,
+    #[serde(default)]
+    x2: Option<PredictionPointX2>,
+}
+
+/// Quartiles of the community distribution, as positions in the question range.
+#[derive(Deserialize, Debug, Clone)]
+struct PredictionPointX1 {
+    q1: Option<f32>,
+    q2: Option<f32>,
+    q3: Option<f32>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct PredictionPointX2 {
     avg: Option<f32>,
     //var: f32,
-    //weighted_avg: f32,
+    #[serde(default)]
+    weighted_avg: Option<f32>,
+}
+
+/// The kinds of Metaculus questions we can standardize.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QuestionType {
+    Binary,
+    /// Continuous questions over a range of numbers, scored by their distribution.
+    Numeric,
+    /// Continuous questions over a range of dates, which are numeric on a time axis.
+    Date,
+}
+
+/// The community prediction series used as the probability on binary questions,
+/// picked with the `METACULUS_SERIES` environment variable.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum PredictionSeries {
+    /// The unweighted mean of each forecaster's latest forecast (`x2.avg`).
+    Average,
+    /// The mean weighted toward more recent forecasts (`x2.weighted_avg`).
+    Weighted,
+    /// The median of each forecaster's latest forecast (`x1.q2`).
+    Median,
 }
 
 /// Container for market data and events, used to hold data for conversion.
@@ -62,7 +123,10 @@ struct PredictionPointX2 {
 struct MarketFull {
     market: MarketInfo,
     market_extra: MarketInfoExtra,
+    question_type: QuestionType,
     events: Vec<ProbUpdate>,
+    quantile_events: Vec<QuantileUpdate>,
+    options: StandardizeOptions,
 }
 
 impl MarketStandardizer for MarketFull {
@@ -84,7 +148,10 @@ impl MarketStandardizer for MarketFull {
     fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         Ok(self.market.created_time)
     }
-    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         if let Some(close_time) = self.market.effected_close_time {
             Ok(close_time)
         } else {
@@ -95,6 +162,9 @@ impl MarketStandardizer for MarketFull {
             })
         }
     }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        self.market.resolve_time
+    }
     fn volume_usd(&self) -> f32 {
         self.market.prediction_count as f32 * METACULUS_USD_PER_FORECAST
     }
@@ -138,14 +208,41 @@ impl MarketStandardizer for MarketFull {
                 _ => continue,
             }
         }
+        // questions without a known category may still be in a topical tournament or tag
+        for project in &self.market_extra.projects {
+            match project.slug.as_deref().unwrap_or_default() {
+                "ai-2027" => return "AI".to_string(),
+                "ai-progress" => return "AI".to_string(),
+                "aibq3" => return "AI".to_string(),
+                "aibq4" => return "AI".to_string(),
+                "biosecurity" => return "Science".to_string(),
+                "climate" => return "Climate".to_string(),
+                "climate-tipping-points" => return "Climate".to_string(),
+                "economist-2021" => return "Economics".to_string(),
+                "elections" => return "Politics".to_string(),
+                "geopolitics" => return "Politics".to_string(),
+                "global-pulse" => return "Politics".to_string(),
+                "nuclear-risk" => return "Politics".to_string(),
+                "space" => return "Science".to_string(),
+                "ukraine-conflict" => return "Politics".to_string(),
+                "us-elections" => return "Politics".to_string(),
+                _ => continue,
+            }
+        }
         "None".to_string()
     }
+    fn description(&self) -> String {
+        self.market_extra.description.clone().unwrap_or_default()
+    }
     fn events(&self) -> Vec<ProbUpdate> {
         self.events.to_owned()
     }
     fn resolution(&self) -> Result<f32, MarketConvertError> {
         if let Some(resolution) = self.market.resolution {
-            if (0.0..=1.0).contains(&resolution) {
+            if self.question_type != QuestionType::Binary && resolution >= 0.0 {
+                // outcomes past an open bound are reported beyond the range, so count them at the edge
+                Ok(resolution.min(1.0))
+            } else if (0.0..=1.0).contains(&resolution) {
                 Ok(resolution)
             } else {
                 Err(MarketConvertError {
@@ -162,6 +259,19 @@ impl MarketStandardizer for MarketFull {
             })
         }
     }
+    fn question_type(&self) -> String {
+        match self.question_type {
+            QuestionType::Binary => "binary".to_string(),
+            QuestionType::Numeric => "numeric".to_string(),
+            QuestionType::Date => "date".to_string(),
+        }
+    }
+    fn quantile_events(&self) -> Vec<QuantileUpdate> {
+        self.quantile_events.to_owned()
+    }
+    fn linked_group(&self) -> Option<String> {
+        self.market.group_id.map(|id| id.to_string())
+    }
 }
 
 /// Standard conversion setup (would move this up to `platforms` if I could).
@@ -175,30 +285,170 @@ impl TryInto<MarketStandard> for MarketFull {
             url: self.url(),
             open_dt: self.open_dt()?,
             close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
             open_days: self.open_days()?,
             volume_usd: self.volume_usd(),
             num_traders: self.num_traders(),
             category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
             prob_at_midpoint: self.prob_at_percent(0.5)?,
             prob_at_close: self.prob_at_percent(1.0)?,
             prob_each_pct: self.prob_each_pct_list()?,
             prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
             prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
             resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
         })
     }
 }
 
+/// Test if a page holds several questions that should each be saved on their own.
+fn is_split_page(market: &MarketInfo) -> bool {
+    matches!(
+        market.page_type.as_deref(),
+        Some("conditional_group") | Some("group")
+    )
+}
+
+/// Get every question on a page that could be standardized.
+/// Conditional pairs are split into their branches, which are regular binary questions.
+/// A branch whose condition did not happen is annulled and gets filtered out later.
+/// Question groups (multiple-choice) are split into every option, not just the winner,
+/// and each option is titled with the group question.
+fn get_questions_from_page(stats: &RunStats, market: MarketInfo, verbose: bool) -> Vec<MarketInfo> {
+    if let Some(page_type) = &market.page_type {
+        check_known_value(
+            stats,
+            "Metaculus",
+            "type",
+            page_type,
+            &[
+                "claim",
+                "conditional_group",
+                "discussion",
+                "forecast",
+                "group",
+                "notebook",
+            ],
+        );
+    }
+    if !is_split_page(&market) {
+        return Vec::from([market]);
+    }
+    let is_group = market.page_type.as_deref() == Some("group");
+    market
+        .sub_questions
+        .iter()
+        .filter_map(
+            |branch| match serde_json::from_value::<MarketInfo>(branch.clone()) {
+                Ok(mut branch) => {
+                    if is_group {
+                        branch.title = format!("{}: {}", market.title, branch.title);
+                        branch.group_id = Some(market.id);
+                    }
+                    Some(branch)
+                }
+                Err(e) => {
+                    eval_error(
+                        MarketConvertError {
+                            data: branch.to_string(),
+                            message: format!("Metaculus: Sub-question could not be parsed: {e}"),
+                            level: 2,
+                        },
+                        verbose,
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Get the kind of question, or None if it is not supported.
+fn get_question_type(market: &MarketInfo) -> Option<QuestionType> {
+    match (
+        market.possibilities.r#type.as_deref(),
+        market.possibilities.format.as_deref(),
+    ) {
+        (Some("binary"), _) => Some(QuestionType::Binary),
+        (Some("continuous"), Some("num")) => Some(QuestionType::Numeric),
+        (Some("continuous"), Some("date")) => Some(QuestionType::Date),
+        _ => None,
+    }
+}
+
 /// Test if a market is suitable for analysis.
-fn is_valid(market: &MarketInfo) -> bool {
+fn is_valid(stats: &RunStats, market: &MarketInfo) -> bool {
+    if let Some(market_type) = &market.possibilities.r#type {
+        check_known_value(
+            stats,
+            "Metaculus",
+            "possibilities.type",
+            market_type,
+            &["binary", "continuous"],
+        );
+    }
+    if let Some(market_format) = &market.possibilities.format {
+        check_known_value(
+            stats,
+            "Metaculus",
+            "possibilities.format",
+            market_format,
+            &["date", "num"],
+        );
+    }
     market.active_state == "RESOLVED"
-        && market.possibilities.r#type == Some("binary".to_string())
+        && get_question_type(market).is_some()
         && market.resolution >= Some(0.0)
 }
 
+/// Get the community prediction series to use from the environment, defaulting to the average.
+fn get_prediction_series() -> PredictionSeries {
+    match var("METACULUS_SERIES") {
+        Ok(series) => PredictionSeries::from_str(&series, true)
+            .expect("Environment variable METACULUS_SERIES must be average, weighted, or median."),
+        Err(_) => PredictionSeries::Average,
+    }
+}
+
+/// Get the value used as the probability from a history point.
+/// For numeric and date questions this is the median of the community distribution.
+fn get_point_prob(
+    point: &PredictionPoint,
+    question_type: QuestionType,
+    series: PredictionSeries,
+) -> Option<f32> {
+    match (question_type, series) {
+        (QuestionType::Binary, PredictionSeries::Average) => point.x2.as_ref()?.avg,
+        (QuestionType::Binary, PredictionSeries::Weighted) => point.x2.as_ref()?.weighted_avg,
+        (QuestionType::Binary, PredictionSeries::Median) => point.x1.as_ref()?.q2,
+        (QuestionType::Numeric | QuestionType::Date, _) => point.x1.as_ref()?.q2,
+    }
+}
+
 /// Convert API events into standard events.
 fn get_prob_updates(
     mut points: Vec<PredictionPoint>,
+    question_type: QuestionType,
+    series: PredictionSeries,
 ) -> Result<Vec<ProbUpdate>, MarketConvertError> {
     let mut result = Vec::new();
     points.sort_unstable_by_key(|point| point.t as i64);
@@ -206,12 +456,15 @@ fn get_prob_updates(
         let dt_opt = DateTime::from_timestamp(point.t as i64, 0);
         if let Some(time) = dt_opt {
             //let time = DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc);
-            if let Some(prob) = point.x2.avg {
+            if let Some(prob) = get_point_prob(&point, question_type, series) {
                 result.push(ProbUpdate { time, prob });
             } else {
                 return Err(MarketConvertError {
                     data: format!("{:?}", point),
-                    message: "Metaculus: History event point.x2.avg is missing".to_string(),
+                    message: format!(
+                        "Metaculus: History event is missing the {:?} series",
+                        series
+                    ),
                     level: 3,
                 });
             }
@@ -228,24 +481,101 @@ fn get_prob_updates(
     Ok(result)
 }
 
+/// Convert API events into numeric forecast events.
+/// Points without all three quartiles are skipped, so this is empty for binary questions.
+fn get_quantile_updates(points: &[PredictionPoint]) -> Vec<QuantileUpdate> {
+    let mut result: Vec<QuantileUpdate> = points
+        .iter()
+        .filter_map(|point| {
+            let x1 = point.x1.as_ref()?;
+            Some(QuantileUpdate {
+                time: DateTime::from_timestamp(point.t as i64, 0)?,
+                quartiles: [x1.q1?, x1.q2?, x1.q3?],
+            })
+        })
+        .collect();
+    result.sort_unstable_by_key(|update| update.time);
+    result
+}
+
 /// Download full market history and store events in the container.
 async fn get_extended_data(
     client: &ClientWithMiddleware,
     market: &MarketInfo,
+    series: PredictionSeries,
+    config: &RunConfig,
 ) -> Result<MarketFull, MarketConvertError> {
     let api_url = METACULUS_API_BASE.to_owned() + "/questions/" + &market.id.to_string();
-    let market_extra: MarketInfoExtra = send_request(client.get(&api_url)).await?;
+    let market_extra: MarketInfoExtra = send_request(&config.stats, client.get(&api_url)).await?;
+    build_market_full(market, market_extra, series, config.options)
+}
+
+/// Build the container for a question from its downloaded data.
+fn build_market_full(
+    market: &MarketInfo,
+    market_extra: MarketInfoExtra,
+    series: PredictionSeries,
+    options: StandardizeOptions,
+) -> Result<MarketFull, MarketConvertError> {
+    let Some(question_type) = get_question_type(market) else {
+        return Err(MarketConvertError {
+            data: format!("{:?}", market),
+            message: "Metaculus: Question type is not supported".to_string(),
+            level: 3,
+        });
+    };
+    let history = &market.community_prediction.history;
     Ok(MarketFull {
         market: market.clone(),
         market_extra,
-        events: get_prob_updates(market.community_prediction.history.clone())?,
+        question_type,
+        events: get_prob_updates(history.clone(), question_type, series)?,
+        quantile_events: match question_type {
+            QuestionType::Binary => Vec::new(),
+            QuestionType::Numeric | QuestionType::Date => get_quantile_updates(history),
+        },
+        options,
     })
 }
 
+/// The full question from `/questions/{id}`, which has both the standard and extended info.
+#[derive(Deserialize, Debug)]
+struct MarketResponse {
+    #[serde(flatten)]
+    info: MarketInfo,
+    #[serde(flatten)]
+    extra: MarketInfoExtra,
+}
+
+/// Standardize one question from its `/questions/{id}` response without downloading anything.
+/// Pages with several questions return one market per question, all sharing the page's
+/// categories, and questions that would be skipped by a normal run are left out.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let response: MarketResponse = parse_bundle("Metaculus", bytes)?;
+    let series = get_prediction_series();
+    let stats = RunStats::default();
+    get_questions_from_page(&stats, response.info, false)
+        .iter()
+        .filter(|market| is_valid(&stats, market))
+        .map(|market| {
+            build_market_full(market, response.extra.clone(), series, options)?.try_into()
+        })
+        .collect()
+}
+
 /// Download, process and store all valid markets from the platform.
-pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
     log_to_stdout("Metaculus: Processing started...");
     let client = get_reqwest_client_ratelimited(METACULUS_RATELIMIT, Some(METACULUS_RATELIMIT_MS));
+    let series = get_prediction_series();
     let api_url = METACULUS_API_BASE.to_owned() + "/questions";
     if verbose {
         println!("Metaculus: Connecting to API at {}", api_url)
@@ -257,6 +587,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             println!("Metaculus: Getting markets starting at {:?}...", offset)
         }
         let market_response: BulkMarketResponse = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .query(&[("limit", limit)])
@@ -270,11 +601,17 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
                 market_response.results.len()
             )
         }
-        let market_data_futures: Vec<_> = market_response
+        let page_len = market_response.results.len();
+        let questions: Vec<MarketInfo> = market_response
             .results
+            .into_iter()
+            .flat_map(|market| get_questions_from_page(&config.stats, market, verbose))
+            .collect();
+        let market_data_futures: Vec<_> = questions
             .iter()
-            .filter(|market| is_valid(market))
-            .map(|market| get_extended_data(&client, market))
+            .filter(|market| is_valid(&config.stats, market))
+            .filter(|market| is_in_sample(&market.id.to_string(), sample))
+            .map(|market| get_extended_data(&client, market, series, config))
             .collect();
         let market_data: Vec<MarketStandard> = join_all(market_data_futures)
             .await
@@ -282,7 +619,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             .filter_map(|market_downloaded_result| match market_downloaded_result {
                 Ok(market_downloaded) => {
                     // market downloaded successfully
-                    match market_downloaded.try_into() {
+                    match convert_market(&config.stats, market_downloaded) {
                         // market processed successfully
                         Ok(market_converted) => Some(market_converted),
                         // market failed processing
@@ -306,8 +643,8 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
                 output_method
             )
         }
-        save_markets(market_data, output_method);
-        if market_response.results.len() == limit {
+        save_markets(config, market_data, output_method);
+        if page_len == limit {
             offset += limit;
         } else {
             break;
@@ -317,28 +654,49 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
 }
 
 /// Download, process and store one market from the platform.
-pub async fn get_market_by_id(id: &str, output_method: OutputMethod, verbose: bool) {
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
     let client = get_reqwest_client_ratelimited(METACULUS_RATELIMIT, Some(METACULUS_RATELIMIT_MS));
+    let series = get_prediction_series();
     let api_url = METACULUS_API_BASE.to_owned() + "/questions/" + id;
     if verbose {
         println!("Metaculus: Connecting to API at {}", api_url)
     }
-    let market_single: MarketInfo = send_request(client.get(&api_url))
+    let market_single: MarketInfo = send_request(&config.stats, client.get(&api_url))
         .await
         .expect("Metaculus: API query error.");
-    if !is_valid(&market_single) {
-        println!("Metaculus: Market is not valid for processing, this may fail.")
+    let is_split = is_split_page(&market_single);
+    let mut market_data = Vec::new();
+    for question in get_questions_from_page(&config.stats, market_single, verbose) {
+        if !is_valid(&config.stats, &question) {
+            // conditional branches that did not happen are annulled, group options may be open
+            if is_split {
+                println!(
+                    "Metaculus: Skipping sub-question {} which is not valid.",
+                    question.id
+                );
+                continue;
+            }
+            println!("Metaculus: Market is not valid for processing, this may fail.")
+        }
+        market_data.push(
+            get_extended_data(&client, &question, series, config)
+                .await
+                .expect("Error getting extended market data")
+                .try_into()
+                .expect("Error converting market into standard fields"),
+        );
     }
-    let market_data = get_extended_data(&client, &market_single)
-        .await
-        .expect("Error getting extended market data")
-        .try_into()
-        .expect("Error converting market into standard fields");
     if verbose {
         println!(
-            "Metaculus: Saving processed market to {:?}...",
+            "Metaculus: Saving {} processed markets to {:?}...",
+            market_data.len(),
             output_method
         )
     }
-    save_markets(Vec::from([market_data]), output_method);
+    save_markets(config, market_data, output_method);
 }
@@ -3,6 +3,7 @@
 use super::*;
 
 const POLYMARKET_CLOB_API_BASE: &str = "https://clob.polymarket.com";
+const POLYMARKET_GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
 const POLYMARKET_SITE_BASE: &str = "https://polymarket.com";
 const POLYMARKET_RATELIMIT: usize = 50;
 
@@ -12,7 +13,11 @@ const POLYMARKET_RATELIMIT: usize = 50;
 struct MarketInfo {
     condition_id: String,
     question: String,
+    #[serde(default)]
+    description: Option<String>,
     market_slug: String,
+    #[serde(default)]
+    image: Option<String>,
     closed: bool,
     end_date_iso: Option<DateTime<Utc>>,
     tags: Option<Vec<String>>,
@@ -26,6 +31,14 @@ struct TokenData {
     winner: bool,
 }
 
+/// API response with extended market info from the Gamma `/markets` endpoint.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone, Default)]
+struct MarketInfoExtra {
+    #[serde(default)]
+    volumeNum: Option<f32>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct CLOBResponse {
     next_cursor: String,
@@ -49,7 +62,9 @@ struct PricesHistoryResponse {
 #[derive(Debug)]
 struct MarketFull {
     market: MarketInfo,
+    market_extra: MarketInfoExtra,
     events: Vec<ProbUpdate>,
+    options: StandardizeOptions,
 }
 
 impl MarketStandardizer for MarketFull {
@@ -80,7 +95,10 @@ impl MarketStandardizer for MarketFull {
             })
         }
     }
-    fn close_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
         if let Some(close_dt) = self.market.end_date_iso {
             Ok(close_dt)
         } else {
@@ -91,9 +109,13 @@ impl MarketStandardizer for MarketFull {
             })
         }
     }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        // the CLOB API does not report when a market was resolved
+        None
+    }
     fn volume_usd(&self) -> f32 {
-        //self.market.volume
-        0.0 // TODO
+        // markets trade in USDC so no conversion is needed
+        self.market_extra.volumeNum.unwrap_or(0.0)
     }
     fn num_traders(&self) -> i32 {
         0 // TODO
@@ -117,6 +139,12 @@ impl MarketStandardizer for MarketFull {
         }
         "None".to_string()
     }
+    fn description(&self) -> String {
+        self.market.description.clone().unwrap_or_default()
+    }
+    fn image_url(&self) -> Option<String> {
+        self.market.image.clone().filter(|url| !url.is_empty())
+    }
     fn events(&self) -> Vec<ProbUpdate> {
         self.events.to_owned()
     }
@@ -156,16 +184,37 @@ impl TryInto<MarketStandard> for MarketFull {
             url: self.url(),
             open_dt: self.open_dt()?,
             close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
             open_days: self.open_days()?,
             volume_usd: self.volume_usd(),
             num_traders: self.num_traders(),
             category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
             prob_at_midpoint: self.prob_at_percent(0.5)?,
             prob_at_close: self.prob_at_percent(1.0)?,
             prob_each_pct: self.prob_each_pct_list()?,
             prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
             prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
             resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
         })
     }
 }
@@ -175,10 +224,30 @@ fn is_valid(market: &MarketInfo) -> bool {
     market.closed && market.tokens.len() == 2 && market.end_date_iso < Some(Utc::now())
 }
 
+/// Convert API history points into standard events.
+fn get_prob_updates(mut history: Vec<PricesHistoryPoint>) -> Vec<ProbUpdate> {
+    let mut events: Vec<ProbUpdate> = Vec::new();
+    history.sort_unstable_by_key(|point| point.t);
+    for point in history {
+        if let Some(last_point) = events.last() {
+            if last_point.prob == point.p {
+                // skip adding to the list if the prob is the same
+                continue;
+            }
+        }
+        events.push(ProbUpdate {
+            time: point.t,
+            prob: point.p,
+        });
+    }
+    events
+}
+
 /// Download full market history and store events in the container.
 async fn get_extended_data(
     client: &ClientWithMiddleware,
     market: &MarketInfo,
+    config: &RunConfig,
 ) -> Result<MarketFull, MarketConvertError> {
     let api_url = POLYMARKET_CLOB_API_BASE.to_owned() + "/prices-history";
     let clob_id = match market.tokens.first() {
@@ -203,6 +272,7 @@ async fn get_extended_data(
         };
         // make the request
         let response: PricesHistoryResponse = send_request(
+            &config.stats,
             client
                 .get(&api_url)
                 .query(&[("interval", "all")])
@@ -225,29 +295,81 @@ async fn get_extended_data(
     }
 
     // convert API history events into standard events
-    let mut events: Vec<ProbUpdate> = Vec::new();
-    history.sort_unstable_by_key(|point| point.t);
-    for point in history {
-        if let Some(last_point) = events.last() {
-            if last_point.prob == point.p {
-                // skip adding to the list if the prob is the same
-                continue;
-            }
+    let events = get_prob_updates(history);
+
+    // the CLOB API doesn't have volume, so get it from Gamma
+    // volume is only informational, so a failed lookup shouldn't drop the market
+    let api_url = POLYMARKET_GAMMA_API_BASE.to_owned() + "/markets";
+    let market_extra: Result<Vec<MarketInfoExtra>, MarketConvertError> = send_request(
+        &config.stats,
+        client
+            .get(&api_url)
+            .query(&[("condition_ids", &market.condition_id)]),
+    )
+    .await;
+    let market_extra = match market_extra.map(|extra| extra.into_iter().next()) {
+        Ok(Some(market_extra)) => market_extra,
+        Ok(None) => {
+            eprintln!(
+                "Polymarket: Gamma API returned no markets for condition ID {}, saving it without volume.",
+                market.condition_id
+            );
+            MarketInfoExtra::default()
         }
-        events.push(ProbUpdate {
-            time: point.t,
-            prob: point.p,
-        });
-    }
+        Err(e) => {
+            eprintln!(
+                "Polymarket: Failed to get volume for condition ID {} from the Gamma API, saving it without volume: {}",
+                market.condition_id, e.message
+            );
+            MarketInfoExtra::default()
+        }
+    };
 
     Ok(MarketFull {
         market: market.clone(),
+        market_extra,
         events,
+        options: config.options,
     })
 }
 
+/// Every API response needed to standardize one market, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct MarketBundle {
+    /// The market, same as from the CLOB `/markets/{condition_id}`.
+    market: MarketInfo,
+    /// The price history of the first token, same as from the CLOB `/prices-history`.
+    history: Vec<PricesHistoryPoint>,
+    /// The market from the Gamma `/markets`, which has the volume.
+    market_extra: MarketInfoExtra,
+}
+
+/// Standardize one market from a JSON bundle of its API responses without downloading anything.
+/// Returns an empty list if the market would be skipped by a normal run.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let bundle: MarketBundle = parse_bundle("Polymarket", bytes)?;
+    if !is_valid(&bundle.market) {
+        return Ok(Vec::new());
+    }
+    let market = MarketFull {
+        market: bundle.market,
+        market_extra: bundle.market_extra,
+        events: get_prob_updates(bundle.history),
+        options,
+    };
+    Ok(Vec::from([market.try_into()?]))
+}
+
 /// Download, process and store all valid markets from the platform.
-pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
     log_to_stdout("Polymarket: Processing started...");
     let client = get_reqwest_client_ratelimited(POLYMARKET_RATELIMIT, None);
     let api_url = POLYMARKET_CLOB_API_BASE.to_owned() + "/markets";
@@ -260,10 +382,12 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
         if verbose {
             println!("Polymarket: Getting markets starting at {:?}...", cursor)
         }
-        let response: CLOBResponse =
-            send_request(client.get(&api_url).query(&[("next_cursor", cursor)]))
-                .await
-                .expect("Polymarket: API query error.");
+        let response: CLOBResponse = send_request(
+            &config.stats,
+            client.get(&api_url).query(&[("next_cursor", cursor)]),
+        )
+        .await
+        .expect("Polymarket: API query error.");
         if verbose {
             println!("Polymarket: Processing {} markets...", response.data.len())
         }
@@ -271,7 +395,8 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             .data
             .iter()
             .filter(|market| is_valid(market))
-            .map(|market| get_extended_data(&client, market))
+            .filter(|market| is_in_sample(&market.condition_id, sample))
+            .map(|market| get_extended_data(&client, market, config))
             .collect();
         let market_data: Vec<MarketStandard> = join_all(market_data_futures)
             .await
@@ -279,7 +404,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
             .filter_map(|market_downloaded_result| match market_downloaded_result {
                 Ok(market_downloaded) => {
                     // market downloaded successfully
-                    match market_downloaded.try_into() {
+                    match convert_market(&config.stats, market_downloaded) {
                         // market processed successfully
                         Ok(market_converted) => Some(market_converted),
                         // market failed processing
@@ -303,7 +428,7 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
                 output_method
             )
         }
-        save_markets(market_data, output_method);
+        save_markets(config, market_data, output_method);
         if response.data.len() == limit {
             cursor = Some(response.next_cursor);
         } else {
@@ -314,19 +439,24 @@ pub async fn get_markets_all(output_method: OutputMethod, verbose: bool) {
 }
 
 /// Download, process and store one market from the platform.
-pub async fn get_market_by_id(id: &String, output_method: OutputMethod, verbose: bool) {
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
     let client = get_reqwest_client_ratelimited(POLYMARKET_RATELIMIT, None);
     let api_url = POLYMARKET_CLOB_API_BASE.to_owned() + "/markets/" + id;
     if verbose {
         println!("Polymarket: Connecting to API at {}", api_url)
     }
-    let single_market: MarketInfo = send_request(client.get(&api_url))
+    let single_market: MarketInfo = send_request(&config.stats, client.get(&api_url))
         .await
         .expect("Polymarket: API query error.");
     if !is_valid(&single_market) {
         println!("Polymarket: Market is not valid for processing, this may fail.")
     }
-    let market_data = get_extended_data(&client, &single_market)
+    let market_data = get_extended_data(&client, &single_market, config)
         .await
         .expect("Error getting extended market data")
         .try_into()
@@ -337,5 +467,5 @@ pub async fn get_market_by_id(id: &String, output_method: OutputMethod, verbose:
             output_method
         )
     }
-    save_markets(Vec::from([market_data]), output_method);
+    save_markets(config, Vec::from([market_data]), output_method);
 }
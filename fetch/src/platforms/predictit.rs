@@ -0,0 +1,392 @@
+//! Tools to download and process markets from the PredictIt API.
+//! Each PredictIt market holds one or more contracts, and each contract is saved as its own market.
+
+use super::*;
+use chrono::{FixedOffset, NaiveDateTime, TimeZone};
+
+const PREDICTIT_API_BASE: &str = "https://www.predictit.org/api";
+const PREDICTIT_RATELIMIT: usize = 10;
+const PREDICTIT_RATELIMIT_MS: u64 = 60_000;
+const PREDICTIT_CHART_TIMESPAN: &str = "90d";
+const PREDICTIT_RESOLVED_THRESHOLD: f32 = 0.05;
+const PREDICTIT_UTC_OFFSET_SECS: i32 = -5 * 60 * 60;
+
+/// API response with all markets from `/marketdata/all`.
+#[derive(Deserialize, Debug, Clone)]
+struct BulkMarketResponse {
+    markets: Vec<MarketInfo>,
+}
+
+/// API response with standard market info.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+struct MarketInfo {
+    id: u32,
+    name: String,
+    shortName: String,
+    #[serde(default)]
+    image: Option<String>,
+    url: String,
+    contracts: Vec<ContractInfo>,
+}
+
+/// (Indirect) API response with the info for each contract in a market.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+struct ContractInfo {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    status: String,
+    dateEnd: String,
+    //lastTradePrice: Option<f32>,
+}
+
+/// API response with a daily price point from `/Public/GetMarketChartData`.
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug, Clone)]
+struct ChartPoint {
+    contractId: u32,
+    date: String,
+    closeSharePrice: f32,
+    #[serde(default)]
+    tradeVolume: f32,
+}
+
+/// Container for market data and events, used to hold data for conversion.
+#[derive(Debug)]
+struct MarketFull {
+    market: MarketInfo,
+    contract: ContractInfo,
+    events: Vec<ProbUpdate>,
+    volume_usd: f32,
+    options: StandardizeOptions,
+}
+
+impl MarketStandardizer for MarketFull {
+    fn debug(&self) -> String {
+        format!("{:?}", self)
+    }
+    fn title(&self) -> String {
+        if self.market.contracts.len() == 1 {
+            self.market.name.to_owned()
+        } else {
+            format!("{}: {}", self.market.shortName, self.contract.name)
+        }
+    }
+    fn platform(&self) -> String {
+        "predictit".to_string()
+    }
+    fn platform_id(&self) -> String {
+        format!("{}-{}", self.market.id, self.contract.id)
+    }
+    fn url(&self) -> String {
+        self.market.url.to_owned()
+    }
+    fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        if let Some(first_event) = self.events().first() {
+            Ok(first_event.time)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "PredictIt: No events in event list (cannot get market bounds)."
+                    .to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        // contracts that close early report `NA`, so fall back to the last trading day
+        if let Some(date_end) = parse_predictit_datetime(&self.contract.dateEnd) {
+            Ok(date_end)
+        } else if let Some(last_event) = self.events().last() {
+            Ok(last_event.time)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "PredictIt: Contract has no end date and no events.".to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        // the public API does not report when a contract was settled
+        None
+    }
+    fn volume_usd(&self) -> f32 {
+        self.volume_usd
+    }
+    fn num_traders(&self) -> i32 {
+        0 // not available from the public API
+    }
+    fn category(&self) -> String {
+        // PredictIt only lists political and economic questions
+        "Politics".to_string()
+    }
+    fn description(&self) -> String {
+        // market rules are not included in the public API
+        String::new()
+    }
+    fn image_url(&self) -> Option<String> {
+        self.contract
+            .image
+            .clone()
+            .or(self.market.image.clone())
+            .filter(|url| !url.is_empty())
+    }
+    fn events(&self) -> Vec<ProbUpdate> {
+        self.events.to_owned()
+    }
+    fn resolution(&self) -> Result<f32, MarketConvertError> {
+        // the API does not have a settlement field, so infer it from the final price
+        match self.events().last() {
+            Some(last_event) if last_event.prob >= 1.0 - PREDICTIT_RESOLVED_THRESHOLD => Ok(1.0),
+            Some(last_event) if last_event.prob <= PREDICTIT_RESOLVED_THRESHOLD => Ok(0.0),
+            Some(last_event) => Err(MarketConvertError {
+                data: self.debug(),
+                message: format!(
+                    "PredictIt: Final price of {} is too far from 0 or 1 to infer a resolution.",
+                    last_event.prob
+                ),
+                level: 1,
+            }),
+            None => Err(MarketConvertError {
+                data: self.debug(),
+                message: "PredictIt: No events in event list (cannot get resolution).".to_string(),
+                level: 3,
+            }),
+        }
+    }
+}
+
+/// Standard conversion setup (would move this up to `platforms` if I could).
+impl TryInto<MarketStandard> for MarketFull {
+    type Error = MarketConvertError;
+    fn try_into(self) -> Result<MarketStandard, MarketConvertError> {
+        Ok(MarketStandard {
+            title: self.title(),
+            platform: self.platform(),
+            platform_id: self.platform_id(),
+            url: self.url(),
+            open_dt: self.open_dt()?,
+            close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
+            open_days: self.open_days()?,
+            volume_usd: self.volume_usd(),
+            num_traders: self.num_traders(),
+            category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
+            prob_at_midpoint: self.prob_at_percent(0.5)?,
+            prob_at_close: self.prob_at_percent(1.0)?,
+            prob_each_pct: self.prob_each_pct_list()?,
+            prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
+            prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
+            resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
+        })
+    }
+}
+
+/// Parse a PredictIt timestamp, which is in US Eastern time without an offset.
+/// We assume standard time, so these may be off by an hour during daylight saving.
+fn parse_predictit_datetime(input: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    FixedOffset::east_opt(PREDICTIT_UTC_OFFSET_SECS)?
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Test if a contract is suitable for analysis.
+fn is_valid(contract: &ContractInfo) -> bool {
+    contract.status == "Closed"
+}
+
+/// Download price history for a market and split it into one container per valid contract.
+/// The public chart API only goes back 90 days, so older trading history is not included.
+async fn get_extended_data(
+    client: &ClientWithMiddleware,
+    market: &MarketInfo,
+    config: &RunConfig,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
+    let api_url =
+        PREDICTIT_API_BASE.to_owned() + "/Public/GetMarketChartData/" + &market.id.to_string();
+    let history: Vec<ChartPoint> = send_request(
+        &config.stats,
+        client
+            .get(&api_url)
+            .query(&[("timespan", PREDICTIT_CHART_TIMESPAN)])
+            .query(&[("maxContracts", market.contracts.len())])
+            .query(&[("showHidden", "true")]),
+    )
+    .await?;
+    Ok(split_by_contract(market, &history, config.options))
+}
+
+/// Build one container per valid contract from a market's price history.
+fn split_by_contract(
+    market: &MarketInfo,
+    history: &[ChartPoint],
+    options: StandardizeOptions,
+) -> Vec<MarketFull> {
+    let mut result = Vec::new();
+    for contract in market.contracts.iter().filter(|c| is_valid(c)) {
+        // convert API history points into standard events
+        let mut points: Vec<(DateTime<Utc>, &ChartPoint)> = history
+            .iter()
+            .filter(|point| point.contractId == contract.id)
+            .filter_map(|point| parse_predictit_datetime(&point.date).map(|dt| (dt, point)))
+            .collect();
+        points.sort_unstable_by_key(|(dt, _)| *dt);
+        let volume_usd = points
+            .iter()
+            .map(|(_, point)| point.tradeVolume * point.closeSharePrice)
+            .sum();
+        let mut events: Vec<ProbUpdate> = Vec::new();
+        for (time, point) in points {
+            if let Some(last_event) = events.last() {
+                if last_event.prob == point.closeSharePrice {
+                    // skip adding to the list if the prob is the same
+                    continue;
+                }
+            }
+            events.push(ProbUpdate {
+                time,
+                prob: point.closeSharePrice,
+            });
+        }
+
+        result.push(MarketFull {
+            market: market.clone(),
+            contract: contract.clone(),
+            events,
+            volume_usd,
+            options,
+        });
+    }
+    result
+}
+
+/// Every API response needed to standardize one market, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct MarketBundle {
+    /// The market, same as from `/marketdata/markets/{id}`.
+    market: MarketInfo,
+    /// The price history, same as from `/Public/GetMarketChartData/{id}`.
+    history: Vec<ChartPoint>,
+}
+
+/// Standardize a market from a JSON bundle of its API responses without downloading anything.
+/// Each closed contract is returned as its own market.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let bundle: MarketBundle = parse_bundle("PredictIt", bytes)?;
+    split_by_contract(&bundle.market, &bundle.history, options)
+        .into_iter()
+        .map(|market| market.try_into())
+        .collect()
+}
+
+/// Download, process and store all valid markets from the platform.
+/// The bulk API only lists markets that are still open, so this only picks up
+/// contracts that have closed early within a larger open market.
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    log_to_stdout("PredictIt: Processing started...");
+    let client = get_reqwest_client_ratelimited(PREDICTIT_RATELIMIT, Some(PREDICTIT_RATELIMIT_MS));
+    let api_url = PREDICTIT_API_BASE.to_owned() + "/marketdata/all/";
+    if verbose {
+        println!("PredictIt: Connecting to API at {}", api_url)
+    }
+    let market_response: BulkMarketResponse = send_request(&config.stats, client.get(&api_url))
+        .await
+        .expect("PredictIt: API query error.");
+    if verbose {
+        println!(
+            "PredictIt: Processing {} markets...",
+            market_response.markets.len()
+        )
+    }
+    let market_data_futures: Vec<_> = market_response
+        .markets
+        .iter()
+        .filter(|market| market.contracts.iter().any(is_valid))
+        .filter(|market| is_in_sample(&market.id.to_string(), sample))
+        .map(|market| get_extended_data(&client, market, config))
+        .collect();
+    let market_data = convert_markets(&config.stats, join_all(market_data_futures).await, verbose);
+    if verbose {
+        println!(
+            "PredictIt: Saving {} processed markets to {:?}...",
+            market_data.len(),
+            output_method
+        )
+    }
+    save_markets(config, market_data, output_method);
+    log_to_stdout("PredictIt: Processing complete.");
+}
+
+/// Download, process and store one market from the platform.
+/// This saves every closed contract in the market.
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
+    let client = get_reqwest_client_ratelimited(PREDICTIT_RATELIMIT, Some(PREDICTIT_RATELIMIT_MS));
+    let api_url = PREDICTIT_API_BASE.to_owned() + "/marketdata/markets/" + id;
+    if verbose {
+        println!("PredictIt: Connecting to API at {}", api_url)
+    }
+    let market_single: MarketInfo = send_request(&config.stats, client.get(&api_url))
+        .await
+        .expect("PredictIt: API query error.");
+    if !market_single.contracts.iter().any(is_valid) {
+        println!("PredictIt: Market has no closed contracts, nothing will be saved.")
+    }
+    let market_data = convert_markets(
+        &config.stats,
+        Vec::from([get_extended_data(&client, &market_single, config).await]),
+        verbose,
+    );
+    if verbose {
+        println!(
+            "PredictIt: Saving {} processed markets to {:?}...",
+            market_data.len(),
+            output_method
+        )
+    }
+    save_markets(config, market_data, output_method);
+}
@@ -0,0 +1,530 @@
+//! Tools to download and process markets from the Smarkets API.
+//! Smarkets groups contracts into markets and markets into events, and each contract is saved as its own market.
+
+use super::*;
+
+const SMARKETS_API_BASE: &str = "https://api.smarkets.com/v3";
+const SMARKETS_SITE_BASE: &str = "https://smarkets.com/event/";
+const SMARKETS_RATELIMIT: usize = 5;
+const SMARKETS_EVENT_DOMAINS: [&str; 2] = ["politics", "current_affairs"];
+const SMARKETS_EVENTS_PER_PAGE: usize = 500;
+const SMARKETS_PRICE_SCALE: f32 = 10_000.0;
+const SMARKETS_GBP_TO_USD: f32 = 1.27;
+
+/// API response with a page of events from `/events/`.
+#[derive(Deserialize, Debug, Clone)]
+struct BulkEventResponse {
+    events: Vec<EventInfo>,
+    pagination: Pagination,
+}
+
+/// (Indirect) API response with the query string for the next page.
+#[derive(Deserialize, Debug, Clone)]
+struct Pagination {
+    next_page: Option<String>,
+}
+
+/// API response with standard event info.
+#[derive(Deserialize, Debug, Clone)]
+struct EventInfo {
+    id: String,
+    name: String,
+    full_slug: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(rename = "type")]
+    event_type: EventType,
+}
+
+/// (Indirect) API response with the event's domain and scope.
+#[derive(Deserialize, Debug, Clone)]
+struct EventType {
+    domain: String,
+}
+
+/// API response with all markets in a set of events from `/events/{ids}/markets/`.
+#[derive(Deserialize, Debug, Clone)]
+struct MarketResponse {
+    markets: Vec<MarketInfo>,
+}
+
+/// (Indirect) API response with standard market info.
+#[derive(Deserialize, Debug, Clone)]
+struct MarketInfo {
+    id: String,
+    name: String,
+    slug: String,
+    #[serde(with = "ts_seconds_rfc3339")]
+    created: DateTime<Utc>,
+}
+
+/// API response with all contracts in a set of markets from `/markets/{ids}/contracts/`.
+#[derive(Deserialize, Debug, Clone)]
+struct ContractResponse {
+    contracts: Vec<ContractInfo>,
+}
+
+/// (Indirect) API response with the info for each contract in a market.
+#[derive(Deserialize, Debug, Clone)]
+struct ContractInfo {
+    id: String,
+    market_id: String,
+    name: String,
+    state_or_outcome: String,
+}
+
+/// API response with traded volume from `/markets/{ids}/volumes/`.
+#[derive(Deserialize, Debug, Clone)]
+struct VolumeResponse {
+    volumes: Vec<VolumeInfo>,
+}
+
+/// (Indirect) API response with the total volume of a market in GBP.
+#[derive(Deserialize, Debug, Clone)]
+struct VolumeInfo {
+    market_id: String,
+    volume: f32,
+}
+
+/// API response with historical trades from `/markets/{id}/executions/`.
+#[derive(Deserialize, Debug, Clone)]
+struct ExecutionResponse {
+    executions: Vec<Execution>,
+}
+
+/// (Indirect) API response with a single trade. Prices are in basis points.
+#[derive(Deserialize, Debug, Clone)]
+struct Execution {
+    contract_id: String,
+    price: f32,
+    #[serde(with = "ts_seconds_rfc3339")]
+    timestamp: DateTime<Utc>,
+}
+
+/// Container for market data and events, used to hold data for conversion.
+#[derive(Debug)]
+struct MarketFull {
+    event: EventInfo,
+    market: MarketInfo,
+    contract: ContractInfo,
+    num_contracts: usize,
+    events: Vec<ProbUpdate>,
+    volume_gbp: f32,
+    options: StandardizeOptions,
+}
+
+impl MarketStandardizer for MarketFull {
+    fn debug(&self) -> String {
+        format!("{:?}", self)
+    }
+    fn title(&self) -> String {
+        if self.num_contracts == 1 {
+            format!("{}: {}", self.event.name, self.market.name)
+        } else {
+            format!(
+                "{}: {}: {}",
+                self.event.name, self.market.name, self.contract.name
+            )
+        }
+    }
+    fn platform(&self) -> String {
+        "smarkets".to_string()
+    }
+    fn platform_id(&self) -> String {
+        format!("{}-{}", self.market.id, self.contract.id)
+    }
+    fn url(&self) -> String {
+        format!(
+            "{}{}{}/{}",
+            SMARKETS_SITE_BASE, self.event.id, self.event.full_slug, self.market.slug
+        )
+    }
+    fn open_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        Ok(self.market.created)
+    }
+    fn options(&self) -> StandardizeOptions {
+        self.options
+    }
+    fn trading_end_dt(&self) -> Result<DateTime<Utc>, MarketConvertError> {
+        // the API does not report when trading stopped, so use the last trade
+        if let Some(last_event) = self.events().last() {
+            Ok(last_event.time)
+        } else {
+            Err(MarketConvertError {
+                data: self.debug(),
+                message: "Smarkets: No events in event list (cannot get market bounds)."
+                    .to_string(),
+                level: 3,
+            })
+        }
+    }
+    fn resolved_dt(&self) -> Option<DateTime<Utc>> {
+        // the public API does not report when a contract was settled
+        None
+    }
+    fn volume_usd(&self) -> f32 {
+        // volume is only reported for the whole market, so it is shared by each contract
+        self.volume_gbp * SMARKETS_GBP_TO_USD
+    }
+    fn num_traders(&self) -> i32 {
+        0 // not available from the public API
+    }
+    fn category(&self) -> String {
+        match self.event.event_type.domain.as_str() {
+            "politics" => "Politics".to_string(),
+            "current_affairs" => "Culture".to_string(),
+            "entertainment" => "Culture".to_string(),
+            "economics" => "Economics".to_string(),
+            "sport" => "Sports".to_string(),
+            _ => "None".to_string(),
+        }
+    }
+    fn description(&self) -> String {
+        self.event.description.clone().unwrap_or_default()
+    }
+    fn events(&self) -> Vec<ProbUpdate> {
+        self.events.to_owned()
+    }
+    fn resolution(&self) -> Result<f32, MarketConvertError> {
+        match self.contract.state_or_outcome.as_str() {
+            "winner" => Ok(1.0),
+            "loser" => Ok(0.0),
+            _ => Err(MarketConvertError {
+                data: self.debug(),
+                message: format!(
+                    "Smarkets: Contract outcome is unknown: {}",
+                    self.contract.state_or_outcome
+                ),
+                level: 3,
+            }),
+        }
+    }
+}
+
+/// Standard conversion setup (would move this up to `platforms` if I could).
+impl TryInto<MarketStandard> for MarketFull {
+    type Error = MarketConvertError;
+    fn try_into(self) -> Result<MarketStandard, MarketConvertError> {
+        Ok(MarketStandard {
+            title: self.title(),
+            platform: self.platform(),
+            platform_id: self.platform_id(),
+            url: self.url(),
+            open_dt: self.open_dt()?,
+            close_dt: self.close_dt()?,
+            trading_end_dt: self.trading_end_dt()?,
+            resolved_dt: self.resolved_dt(),
+            open_days: self.open_days()?,
+            volume_usd: self.volume_usd(),
+            num_traders: self.num_traders(),
+            category: self.category(),
+            region: self.region(),
+            description: self.description_markdown(),
+            description_excerpt: self.description_excerpt(),
+            image_url: self.image_url(),
+            prob_at_midpoint: self.prob_at_percent(0.5)?,
+            prob_at_close: self.prob_at_percent(1.0)?,
+            prob_each_pct: self.prob_each_pct_list()?,
+            prob_each_date: self.prob_each_date_map()?,
+            prob_each_hour: self.prob_each_hour_map()?,
+            prob_daily_start_dt: self.prob_daily_start_dt()?,
+            prob_daily: self.prob_daily_list()?,
+            active_each_date: self.active_each_date_map()?,
+            volume_each_date: self.volume_each_date_map()?,
+            prob_band_each_date: self.prob_band_each_date_map()?,
+            prob_time_avg: self.prob_time_avg_whole()?,
+            prob_min: self.prob_min()?,
+            prob_max: self.prob_max()?,
+            prob_daily_stddev: self.prob_daily_stddev()?,
+            prob_midpoint_crossings: self.prob_midpoint_crossings()?,
+            days_to_confidence: self.days_to_confidence()?,
+            resolution: self.resolution()?,
+            question_type: self.question_type(),
+            quantiles_each_date: self.quantiles_each_date_map()?,
+            linked_group: self.linked_group(),
+            linked_order: self.linked_order(),
+        })
+    }
+}
+
+/// Smarkets timestamps are RFC 3339 strings without an offset, so we assume UTC.
+mod ts_seconds_rfc3339 {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let input = String::deserialize(deserializer)?;
+        if let Ok(dt) = DateTime::parse_from_rfc3339(&input) {
+            return Ok(dt.with_timezone(&Utc));
+        }
+        NaiveDateTime::parse_from_str(&input, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|naive| naive.and_utc())
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Test if a contract is suitable for analysis.
+fn is_valid(contract: &ContractInfo) -> bool {
+    contract.state_or_outcome == "winner" || contract.state_or_outcome == "loser"
+}
+
+/// Download markets, contracts, volume, and trade history for an event,
+/// and split it into one container per valid contract.
+async fn get_extended_data(
+    client: &ClientWithMiddleware,
+    event: &EventInfo,
+    config: &RunConfig,
+) -> Result<Vec<MarketFull>, MarketConvertError> {
+    let api_url = format!("{}/events/{}/markets/", SMARKETS_API_BASE, event.id);
+    let markets = send_request::<MarketResponse>(&config.stats, client.get(&api_url))
+        .await?
+        .markets;
+    if markets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let market_ids = markets
+        .iter()
+        .map(|market| market.id.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let api_url = format!("{}/markets/{}/contracts/", SMARKETS_API_BASE, market_ids);
+    let contracts = send_request::<ContractResponse>(&config.stats, client.get(&api_url))
+        .await?
+        .contracts;
+
+    let api_url = format!("{}/markets/{}/volumes/", SMARKETS_API_BASE, market_ids);
+    let volumes: HashMap<String, f32> =
+        send_request::<VolumeResponse>(&config.stats, client.get(&api_url))
+            .await?
+            .volumes
+            .into_iter()
+            .map(|v| (v.market_id, v.volume))
+            .collect();
+
+    let mut result = Vec::new();
+    for market in markets {
+        let market_contracts: Vec<&ContractInfo> = contracts
+            .iter()
+            .filter(|contract| contract.market_id == market.id)
+            .collect();
+        if !market_contracts.iter().any(|c| is_valid(c)) {
+            continue;
+        }
+
+        let api_url = format!("{}/markets/{}/executions/", SMARKETS_API_BASE, market.id);
+        let mut executions = send_request::<ExecutionResponse>(&config.stats, client.get(&api_url))
+            .await?
+            .executions;
+        executions.sort_unstable_by_key(|execution| execution.timestamp);
+
+        result.extend(split_by_contract(
+            event,
+            &market,
+            &market_contracts,
+            &executions,
+            volumes.get(&market.id).copied().unwrap_or_default(),
+            config.options,
+        ));
+    }
+
+    Ok(result)
+}
+
+/// Build one container per valid contract in a market from its trades, sorted oldest first.
+fn split_by_contract(
+    event: &EventInfo,
+    market: &MarketInfo,
+    market_contracts: &[&ContractInfo],
+    executions: &[Execution],
+    volume_gbp: f32,
+    options: StandardizeOptions,
+) -> Vec<MarketFull> {
+    let mut result = Vec::new();
+    for contract in market_contracts.iter().filter(|c| is_valid(c)) {
+        // convert API trades into standard events
+        let mut events: Vec<ProbUpdate> = Vec::new();
+        for execution in executions
+            .iter()
+            .filter(|execution| execution.contract_id == contract.id)
+        {
+            let prob = execution.price / SMARKETS_PRICE_SCALE;
+            if let Some(last_event) = events.last() {
+                if last_event.prob == prob {
+                    // skip adding to the list if the prob is the same
+                    continue;
+                }
+            }
+            events.push(ProbUpdate {
+                time: execution.timestamp,
+                prob,
+            });
+        }
+
+        result.push(MarketFull {
+            event: event.clone(),
+            market: market.clone(),
+            contract: (*contract).clone(),
+            num_contracts: market_contracts.len(),
+            events,
+            volume_gbp,
+            options,
+        });
+    }
+    result
+}
+
+/// Every API response needed to standardize one event, for `standardize_bytes`.
+#[derive(Deserialize, Debug)]
+struct EventBundle {
+    /// The event, same as each item from `/events/{id}/`.
+    event: EventInfo,
+    /// The markets in the event, same as from `/events/{id}/markets/`.
+    markets: Vec<MarketInfo>,
+    /// The contracts in those markets, same as from `/markets/{ids}/contracts/`.
+    contracts: Vec<ContractInfo>,
+    /// The volume of those markets, same as from `/markets/{ids}/volumes/`.
+    #[serde(default)]
+    volumes: Vec<VolumeInfo>,
+    /// The trades in those markets, from each `/markets/{id}/executions/` in any order.
+    executions: Vec<Execution>,
+}
+
+/// Standardize an event from a JSON bundle of its API responses without downloading anything.
+/// Each settled contract in each market is returned as its own market.
+pub fn standardize_bytes(
+    bytes: &[u8],
+    options: StandardizeOptions,
+) -> Result<Vec<MarketStandard>, MarketConvertError> {
+    let mut bundle: EventBundle = parse_bundle("Smarkets", bytes)?;
+    bundle
+        .executions
+        .sort_unstable_by_key(|execution| execution.timestamp);
+    let mut result = Vec::new();
+    for market in &bundle.markets {
+        let market_contracts: Vec<&ContractInfo> = bundle
+            .contracts
+            .iter()
+            .filter(|contract| contract.market_id == market.id)
+            .collect();
+        let volume_gbp = bundle
+            .volumes
+            .iter()
+            .find(|volume| volume.market_id == market.id)
+            .map_or(0.0, |volume| volume.volume);
+        for market_full in split_by_contract(
+            &bundle.event,
+            market,
+            &market_contracts,
+            &bundle.executions,
+            volume_gbp,
+            options,
+        ) {
+            result.push(market_full.try_into()?);
+        }
+    }
+    Ok(result)
+}
+
+/// Download, process and store all valid markets from the platform.
+/// Only events in `SMARKETS_EVENT_DOMAINS` are included, since the sports catalog is enormous.
+pub async fn get_markets_all(
+    output_method: OutputMethod,
+    config: &RunConfig,
+    sample: Option<f32>,
+    verbose: bool,
+) {
+    log_to_stdout("Smarkets: Processing started...");
+    let client = get_reqwest_client_ratelimited(SMARKETS_RATELIMIT, None);
+    let api_url = SMARKETS_API_BASE.to_owned() + "/events/";
+    if verbose {
+        println!("Smarkets: Connecting to API at {}", api_url)
+    }
+    let mut all_events: Vec<EventInfo> = Vec::new();
+    for domain in SMARKETS_EVENT_DOMAINS {
+        let mut next_page: Option<String> = None;
+        loop {
+            let request = match &next_page {
+                // the next page is given as a full query string
+                Some(query) => client.get(api_url.to_owned() + query),
+                None => client
+                    .get(&api_url)
+                    .query(&[("state", "settled")])
+                    .query(&[("type_domain", domain)])
+                    .query(&[("limit", SMARKETS_EVENTS_PER_PAGE)]),
+            };
+            let response: BulkEventResponse = send_request(&config.stats, request)
+                .await
+                .expect("Smarkets: API query error.");
+            if verbose {
+                println!(
+                    "Smarkets: Found {} events in {}...",
+                    response.events.len(),
+                    domain
+                )
+            }
+            let page_len = response.events.len();
+            all_events.extend(response.events);
+            match response.pagination.next_page {
+                Some(query) if page_len == SMARKETS_EVENTS_PER_PAGE => next_page = Some(query),
+                _ => break,
+            }
+        }
+    }
+    if verbose {
+        println!("Smarkets: Processing {} events...", all_events.len())
+    }
+    let market_data_futures: Vec<_> = all_events
+        .iter()
+        .filter(|event| is_in_sample(&event.id, sample))
+        .map(|event| get_extended_data(&client, event, config))
+        .collect();
+    let market_data = convert_markets(&config.stats, join_all(market_data_futures).await, verbose);
+    if verbose {
+        println!(
+            "Smarkets: Saving {} processed markets to {:?}...",
+            market_data.len(),
+            output_method
+        )
+    }
+    save_markets(config, market_data, output_method);
+    log_to_stdout("Smarkets: Processing complete.");
+}
+
+/// Download, process and store one event from the platform.
+/// This saves every settled contract in every market in the event.
+pub async fn get_market_by_id(
+    id: &str,
+    output_method: OutputMethod,
+    config: &RunConfig,
+    verbose: bool,
+) {
+    let client = get_reqwest_client_ratelimited(SMARKETS_RATELIMIT, None);
+    let api_url = SMARKETS_API_BASE.to_owned() + "/events/" + id + "/";
+    if verbose {
+        println!("Smarkets: Connecting to API at {}", api_url)
+    }
+    let event_single = send_request::<BulkEventResponse>(&config.stats, client.get(&api_url))
+        .await
+        .expect("Smarkets: API query error.")
+        .events
+        .into_iter()
+        .next()
+        .expect("Smarkets: Event not found.");
+    let market_data = convert_markets(
+        &config.stats,
+        Vec::from([get_extended_data(&client, &event_single, config).await]),
+        verbose,
+    );
+    if verbose {
+        println!(
+            "Smarkets: Saving {} processed markets to {:?}...",
+            market_data.len(),
+            output_method
+        )
+    }
+    save_markets(config, market_data, output_method);
+}
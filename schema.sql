@@ -7,18 +7,162 @@ CREATE TABLE market (
     url VARCHAR NOT NULL,
     open_dt TIMESTAMPTZ NOT NULL,
     close_dt TIMESTAMPTZ NOT NULL,
+    trading_end_dt TIMESTAMPTZ NOT NULL,
+    resolved_dt TIMESTAMPTZ,
     open_days REAL NOT NULL,
     volume_usd REAL NOT NULL,
     num_traders INTEGER NOT NULL,
     category VARCHAR DEFAULT 'None' NOT NULL,
+    region VARCHAR DEFAULT 'Global' NOT NULL,
+    description VARCHAR DEFAULT '' NOT NULL,
+    description_excerpt VARCHAR DEFAULT '' NOT NULL,
+    image_url VARCHAR,
     prob_at_midpoint REAL NOT NULL,
     prob_at_close REAL NOT NULL,
     prob_each_pct REAL [] NOT NULL,
     prob_each_date JSONB NOT NULL,
+    prob_each_hour JSONB DEFAULT '{}' NOT NULL,
+    prob_daily_start_dt TIMESTAMPTZ,
+    prob_daily REAL [] DEFAULT '{}' NOT NULL,
+    active_each_date JSONB DEFAULT '{}' NOT NULL,
+    volume_each_date JSONB DEFAULT '{}' NOT NULL,
+    prob_band_each_date JSONB DEFAULT '{}' NOT NULL,
     prob_time_avg REAL NOT NULL,
+    prob_min REAL DEFAULT 0 NOT NULL,
+    prob_max REAL DEFAULT 0 NOT NULL,
+    prob_daily_stddev REAL DEFAULT 0 NOT NULL,
+    prob_midpoint_crossings INTEGER DEFAULT 0 NOT NULL,
+    days_to_confidence REAL,
     resolution REAL NOT NULL,
+    question_type VARCHAR DEFAULT 'binary' NOT NULL,
+    quantiles_each_date JSONB DEFAULT '{}' NOT NULL,
+    linked_group VARCHAR,
+    linked_order INTEGER,
     CONSTRAINT platform_unique_by_id UNIQUE (platform, platform_id)
 );
+DROP TABLE IF EXISTS market_change;
+CREATE TABLE market_change (
+    id SERIAL PRIMARY KEY,
+    run_id VARCHAR NOT NULL,
+    platform VARCHAR NOT NULL,
+    platform_id VARCHAR NOT NULL,
+    field VARCHAR NOT NULL,
+    old_value REAL NOT NULL,
+    new_value REAL NOT NULL,
+    changed_dt TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+DROP TABLE IF EXISTS market_grade;
+CREATE TABLE market_grade (
+    id SERIAL PRIMARY KEY,
+    platform VARCHAR NOT NULL,
+    platform_id VARCHAR NOT NULL,
+    methodology_version INTEGER NOT NULL,
+    content_hash BIGINT NOT NULL,
+    category VARCHAR NOT NULL,
+    close_year INTEGER NOT NULL,
+    crps_time_avg REAL,
+    graded_dt TIMESTAMPTZ NOT NULL,
+    UNIQUE (platform, platform_id, methodology_version)
+);
+DROP TABLE IF EXISTS trader_bet;
+CREATE TABLE trader_bet (
+    id SERIAL PRIMARY KEY,
+    platform VARCHAR NOT NULL,
+    platform_id VARCHAR NOT NULL,
+    trader_id VARCHAR NOT NULL,
+    bet_probs REAL [] NOT NULL,
+    last_bet_dt TIMESTAMPTZ NOT NULL,
+    UNIQUE (platform, platform_id, trader_id)
+);
+DROP TABLE IF EXISTS job;
+CREATE TABLE job (
+    id SERIAL PRIMARY KEY,
+    stage VARCHAR NOT NULL,
+    params JSONB DEFAULT '{}' NOT NULL,
+    status VARCHAR DEFAULT 'running' NOT NULL,
+    started_dt TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    finished_dt TIMESTAMPTZ,
+    error VARCHAR,
+    summary JSONB
+);
+CREATE UNIQUE INDEX job_running_stage_unique ON job (stage)
+WHERE status = 'running';
+DROP TABLE IF EXISTS platform_rating;
+CREATE TABLE platform_rating (
+    id SERIAL PRIMARY KEY,
+    scored_dt TIMESTAMPTZ NOT NULL,
+    platform VARCHAR NOT NULL,
+    category VARCHAR NOT NULL,
+    group_key VARCHAR NOT NULL,
+    rated_dt TIMESTAMPTZ NOT NULL,
+    rating REAL NOT NULL,
+    num_groups INTEGER NOT NULL,
+    methodology_version INTEGER NOT NULL
+);
+DROP TABLE IF EXISTS period_score;
+CREATE TABLE period_score (
+    id SERIAL PRIMARY KEY,
+    scored_dt TIMESTAMPTZ NOT NULL,
+    platform VARCHAR NOT NULL,
+    category VARCHAR NOT NULL,
+    year INTEGER NOT NULL,
+    num_markets INTEGER NOT NULL,
+    brier_at_midpoint REAL NOT NULL,
+    brier_at_close REAL NOT NULL,
+    brier_time_avg REAL NOT NULL,
+    methodology_version INTEGER NOT NULL,
+    UNIQUE (platform, category, year, methodology_version)
+);
+DROP TABLE IF EXISTS trader_score;
+CREATE TABLE trader_score (
+    id SERIAL PRIMARY KEY,
+    scored_dt TIMESTAMPTZ NOT NULL,
+    platform VARCHAR NOT NULL,
+    trader_id VARCHAR NOT NULL,
+    num_markets INTEGER NOT NULL,
+    num_bets INTEGER NOT NULL,
+    brier REAL NOT NULL,
+    relative_brier REAL NOT NULL,
+    methodology_version INTEGER NOT NULL,
+    UNIQUE (platform, trader_id, methodology_version)
+);
+DROP TABLE IF EXISTS cohort_score;
+DROP TABLE IF EXISTS cohort;
+CREATE TABLE cohort (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    description VARCHAR DEFAULT '' NOT NULL,
+    filters JSONB NOT NULL,
+    created_dt TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+CREATE TABLE cohort_score (
+    id SERIAL PRIMARY KEY,
+    cohort_id INTEGER NOT NULL REFERENCES cohort (id) ON DELETE CASCADE,
+    scored_dt TIMESTAMPTZ NOT NULL,
+    platform VARCHAR NOT NULL,
+    num_markets INTEGER NOT NULL,
+    brier_at_midpoint REAL NOT NULL,
+    brier_at_close REAL NOT NULL,
+    brier_time_avg REAL NOT NULL,
+    methodology_version INTEGER NOT NULL,
+    num_numeric_markets INTEGER DEFAULT 0 NOT NULL,
+    crps_time_avg REAL,
+    calibration_error REAL,
+    brier_reliability REAL,
+    brier_skill_time_avg REAL,
+    num_multi_questions INTEGER DEFAULT 0 NOT NULL,
+    multi_brier_time_avg REAL,
+    rps_time_avg REAL
+);
+DROP TABLE IF EXISTS suggestion;
+CREATE TABLE suggestion (
+    id SERIAL PRIMARY KEY,
+    kind VARCHAR NOT NULL,
+    markets JSONB NOT NULL,
+    message VARCHAR DEFAULT '' NOT NULL,
+    status VARCHAR DEFAULT 'pending' NOT NULL,
+    created_dt TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
 DROP TABLE IF EXISTS platform;
 CREATE TABLE platform (
     name VARCHAR PRIMARY KEY,
@@ -27,7 +171,8 @@ CREATE TABLE platform (
     site_url VARCHAR NOT NULL,
     avatar_url VARCHAR NOT NULL,
     color VARCHAR NOT NULL,
-    color_accent VARCHAR NOT NULL
+    color_accent VARCHAR NOT NULL,
+    capabilities JSONB DEFAULT '{}' NOT NULL
 );
 INSERT INTO platform (
         name,
@@ -73,4 +218,40 @@ VALUES (
         'images/polymarket.png',
         '#0072f9',
         '#00397c'
+    ),
+    (
+        'predictit',
+        'PredictIt',
+        'A long-running real-money exchange for political questions with small position limits.',
+        'https://www.predictit.org/',
+        'images/predictit.svg',
+        '#07a0c3',
+        '#04505f'
+    ),
+    (
+        'betfair',
+        'Betfair',
+        'A large UK-based real-money betting exchange, mostly used for sports.',
+        'https://www.betfair.com/exchange/plus/',
+        'images/betfair.svg',
+        '#ffb80c',
+        '#7f5c06'
+    ),
+    (
+        'smarkets',
+        'Smarkets',
+        'A UK-based real-money betting exchange with a wide range of political markets.',
+        'https://smarkets.com/',
+        'images/smarkets.svg',
+        '#00b073',
+        '#005839'
+    ),
+    (
+        'infer',
+        'INFER',
+        'A forecasting tournament run by RAND for questions relevant to policymakers.',
+        'https://www.infer-pub.com/',
+        'images/infer.svg',
+        '#1f4e79',
+        '#0f273c'
     );
\ No newline at end of file
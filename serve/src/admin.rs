@@ -0,0 +1,225 @@
+use super::*;
+
+/// How many jobs to return in the job list.
+const JOB_LIST_LIMIT: i64 = 100;
+
+/// Parameters for starting a grade run.
+#[derive(Debug, Deserialize)]
+pub struct GradeQueryParams {
+    methodology_version: Option<i32>,
+    /// Only rescore the markets that changed since the last grade.
+    #[serde(default)]
+    incremental: bool,
+    /// Compute every score and compare it against what's saved, without saving anything.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Response for a trigger that was accepted and is running in the background.
+#[derive(Debug, Serialize)]
+struct TriggerResponse {
+    status: String,
+    stage: String,
+    /// The job tracking this run, which can be checked in the job list.
+    job_id: i32,
+}
+
+/// Response for a refresh of the group links.
+#[derive(Debug, Serialize)]
+struct RefreshResponse {
+    /// Problems found in the group mapping file, same as the startup warnings.
+    problems: Vec<String>,
+}
+
+/// Make sure an admin request has the configured bearer token.
+pub fn check_admin_token(req: &HttpRequest, config: &ServerConfig) -> Result<(), ApiError> {
+    let Some(admin_token) = &config.admin_token else {
+        return Err(ApiError::new(404, "admin routes are disabled".to_string()));
+    };
+    let provided = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        Some(token) if token == admin_token => Ok(()),
+        _ => Err(ApiError::new(401, "invalid admin token".to_string())),
+    }
+}
+
+/// Check the group mapping file against the database again.
+/// Group scores are computed from the file on each request, so this confirms
+/// that any fixed links resolve without waiting for a restart.
+pub fn build_refresh_views(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let job_id = insert_job(
+        conn,
+        NewJob {
+            stage: "refresh_views".to_string(),
+            params: serde_json::json!({}),
+        },
+    )?;
+    let result = validate_groups(conn);
+    finish_job(
+        conn,
+        job_id,
+        result.as_ref().err().map(|e| e.to_string()),
+        None,
+    )?;
+    let problems = result?;
+    for problem in &problems {
+        eprintln!("Group validation: {problem}");
+    }
+    Ok(HttpResponse::Ok().json(RefreshResponse { problems }))
+}
+
+/// Run a grade for a job that's already been recorded, then mark the job finished.
+/// A dry run compares the scores against the saved ones instead of saving them.
+fn run_grade(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    job_id: i32,
+    methodology_version: i32,
+    incremental: bool,
+    dry_run: bool,
+) -> Result<GradeCost, ApiError> {
+    let mut cost = GradeCost::default();
+    if dry_run {
+        let result = GradeState::load(conn, excluded, methodology_version, incremental, &mut cost)
+            .and_then(|state| {
+                build_grade_diff(conn, excluded, methodology_version, &state, &mut cost)
+            });
+        let summary = result.as_ref().ok().and_then(|diff| {
+            let mut summary = serde_json::to_value(&cost).ok()?;
+            summary["diff"] = serde_json::to_value(diff).ok()?;
+            Some(summary)
+        });
+        finish_job(
+            conn,
+            job_id,
+            result.as_ref().err().map(|e| e.to_string()),
+            summary,
+        )?;
+        print!("{}", result?.report);
+        return Ok(cost);
+    }
+    let result = GradeState::load(conn, excluded, methodology_version, incremental, &mut cost)
+        .and_then(|state| {
+            record_all_cohort_scores(conn, excluded, methodology_version, &state, &mut cost)?;
+            record_period_scores(conn, methodology_version, &state, &mut cost)?;
+            record_platform_ratings(conn, methodology_version, &mut cost)?;
+            record_trader_scores(conn, excluded, methodology_version, &mut cost)?;
+            // saved last, so a failed grade rescores the same markets next time
+            state.save(conn, methodology_version, &mut cost)?;
+            Ok(cost)
+        });
+    let summary = result
+        .as_ref()
+        .ok()
+        .and_then(|cost| serde_json::to_value(cost).ok());
+    finish_job(
+        conn,
+        job_id,
+        result.as_ref().err().map(|e| e.to_string()),
+        summary,
+    )?;
+    result
+}
+
+/// Get the message a panic was raised with, to save as the job error.
+fn get_panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or(payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or("unknown panic".to_string())
+}
+
+/// Start recording scores for every cohort in the background.
+/// Scoring can take a while, so this returns as soon as the work is started.
+/// Only one grade job can run at a time, so this errors with a 409 if one already is.
+/// The job is marked failed even if the grade panics, so the stage isn't left locked.
+pub fn build_trigger_grade(
+    query: Query<GradeQueryParams>,
+    pool: Pool<ConnectionManager<PgConnection>>,
+    excluded: Arc<ExclusionList>,
+) -> Result<HttpResponse, ApiError> {
+    let methodology_version = get_methodology_version(query.methodology_version)?;
+    let mut conn = pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+    let job_id = insert_job(
+        &mut conn,
+        NewJob {
+            stage: "grade".to_string(),
+            params: serde_json::json!({
+                "methodology_version": methodology_version,
+                "incremental": query.incremental,
+                "dry_run": query.dry_run,
+            }),
+        },
+    )?;
+    let incremental = query.incremental;
+    let dry_run = query.dry_run;
+
+    // the grade reuses this connection, so there's no second checkout that could fail
+    // after the job was recorded
+    std::thread::spawn(move || {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_grade(
+                &mut conn,
+                &excluded,
+                job_id,
+                methodology_version,
+                incremental,
+                dry_run,
+            )
+        }))
+        .unwrap_or_else(|payload| {
+            let error = format!("grade panicked: {}", get_panic_message(payload.as_ref()));
+            // the panic may have left the connection in a broken transaction
+            let marked = match pool.get() {
+                Ok(mut fresh) => finish_job(&mut fresh, job_id, Some(error.clone()), None),
+                Err(_) => finish_job(&mut conn, job_id, Some(error.clone()), None),
+            };
+            if let Err(e) = marked {
+                eprintln!("Admin: Failed to mark grade job {job_id} as failed: {e}");
+            }
+            Err(ApiError::new(500, error))
+        });
+        match result {
+            Ok(cost) if dry_run => {
+                println!("Admin: Compared scores for {} cohorts.", cost.num_cohorts)
+            }
+            Ok(cost) => println!("Admin: Recorded scores for {} cohorts.", cost.num_cohorts),
+            Err(e) => eprintln!("Admin: Failed to record cohort scores: {e}"),
+        }
+    });
+    Ok(HttpResponse::Accepted().json(TriggerResponse {
+        status: "started".to_string(),
+        stage: "grade".to_string(),
+        job_id,
+    }))
+}
+
+/// Mark a running job as failed, to clear one left behind by a run that was killed before
+/// it could finish, which would otherwise block every later run of its stage.
+pub fn build_job_cancel(
+    job_id: i32,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let count = fail_running_job(conn, job_id, "cancelled by an admin".to_string())?;
+    if count == 0 {
+        return Err(ApiError::new(404, format!("job {job_id} is not running")));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "status": "cancelled", "job_id": job_id })))
+}
+
+/// List the most recent pipeline jobs, including fetch runs, newest first.
+pub fn build_job_list(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let jobs = get_recent_jobs(conn, JOB_LIST_LIMIT)?;
+    Ok(HttpResponse::Ok().json(jobs))
+}
@@ -0,0 +1,687 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// How many of the slowest markets to keep in a grade run's cost summary.
+const SLOWEST_MARKETS_LIMIT: usize = 20;
+/// How far the resolutions of a linked question's options can be from summing to 1 before
+/// the question is treated as incomplete and left out of the distribution scores.
+const MULTI_RESOLUTION_TOLERANCE: f32 = 0.01;
+
+/// Body sent when creating a cohort. The filters themselves are taken from the query string.
+#[derive(Debug, Deserialize)]
+pub struct CohortCreateBody {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Parameters for getting a cohort's scores, now or as recorded on a past date.
+#[derive(Debug, Deserialize)]
+pub struct CohortScoresQueryParams {
+    methodology_version: Option<i32>,
+    /// Show the last snapshot recorded on or before this date instead of scoring now.
+    as_of: Option<NaiveDate>,
+}
+
+/// Full response for a cohort's scores.
+#[derive(Debug, Serialize)]
+struct CohortScoresResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    cohort: Cohort,
+    current: Vec<CohortScore>,
+    history: Vec<CohortScore>,
+}
+
+/// Parameters to pick the two recorded snapshots to compare.
+/// Each picks the latest snapshot at or before that time, and defaults to the last two snapshots.
+#[derive(Debug, Deserialize)]
+pub struct CohortCompareQueryParams {
+    before: Option<DateTime<Utc>>,
+    after: Option<DateTime<Utc>>,
+}
+
+/// How one platform's scores changed between two snapshots, and why.
+#[derive(Debug, Serialize)]
+struct CohortScoreChange {
+    platform: String,
+    before: Option<CohortScore>,
+    after: Option<CohortScore>,
+    brier_at_midpoint_change: Option<f32>,
+    brier_at_close_change: Option<f32>,
+    brier_time_avg_change: Option<f32>,
+    /// Known reasons the scores moved, such as new markets or corrected resolutions.
+    causes: Vec<String>,
+}
+
+/// Full response for a comparison of two snapshots.
+#[derive(Debug, Serialize)]
+struct CohortCompareResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    cohort: Cohort,
+    before_dt: DateTime<Utc>,
+    after_dt: DateTime<Utc>,
+    changes: Vec<CohortScoreChange>,
+    /// The changes as plain text, for publishing alongside a site update.
+    report: String,
+}
+
+/// The time spent scoring one market, kept if it's among the slowest in a grade run.
+#[derive(Debug, Clone, Serialize)]
+struct MarketCost {
+    platform: String,
+    platform_id: String,
+    /// The number of daily forecasts scored.
+    points: usize,
+    micros: u64,
+}
+
+/// Where a grade run spent its time, saved with the job so optimization can target the
+/// markets that are actually slow. Binary markets are scored from probabilities computed
+/// during fetch, so they only count toward their stage. Numeric markets are scored over every
+/// daily forecast, so each one is timed.
+#[derive(Debug, Default, Serialize)]
+pub struct GradeCost {
+    pub num_cohorts: usize,
+    /// Whether only the markets that changed since the last grade were rescored.
+    pub incremental: bool,
+    /// The number of markets that were new or changed, or every market in a full grade.
+    pub num_markets_changed: usize,
+    /// The number of markets graded last time that are gone now.
+    pub num_markets_removed: usize,
+    num_markets_timed: usize,
+    /// Total time in each stage across every cohort, in microseconds.
+    stage_micros: BTreeMap<&'static str, u64>,
+    /// The markets that took longest to score, slowest first.
+    slowest_markets: Vec<MarketCost>,
+}
+impl GradeCost {
+    /// Add the time since `start` to a stage's total.
+    pub fn add_stage(&mut self, stage: &'static str, start: Instant) {
+        *self.stage_micros.entry(stage).or_default() += start.elapsed().as_micros() as u64;
+    }
+
+    /// Record how long a numeric market took to score since `start`, keeping only the slowest.
+    pub fn add_market(&mut self, market: &Market, start: Instant) {
+        let cost = MarketCost {
+            platform: market.platform.clone(),
+            platform_id: market.platform_id.clone(),
+            points: market
+                .quantiles_each_date
+                .as_object()
+                .map_or(0, |dates| dates.len()),
+            micros: start.elapsed().as_micros() as u64,
+        };
+        self.num_markets_timed += 1;
+        let index = self
+            .slowest_markets
+            .partition_point(|market| market.micros >= cost.micros);
+        if index < SLOWEST_MARKETS_LIMIT {
+            self.slowest_markets.insert(index, cost);
+            self.slowest_markets.truncate(SLOWEST_MARKETS_LIMIT);
+        }
+    }
+}
+
+/// Save a new named set of filters.
+pub fn build_cohort_create(
+    query: Query<CommonFilterParams>,
+    body: CohortCreateBody,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::new(400, "cohort name is required".to_string()));
+    }
+    let filters = serde_json::to_value(query.into_inner())
+        .map_err(|e| ApiError::new(500, format!("failed to serialize cohort filters: {e}")))?;
+
+    let cohort = insert_cohort(
+        conn,
+        NewCohort {
+            name,
+            description: body.description,
+            filters,
+        },
+    )?;
+
+    Ok(HttpResponse::Created().json(cohort))
+}
+
+/// Distribution scores for one platform's questions that were saved as one market per option.
+#[derive(Debug, Default)]
+struct MultiOutcomeScores {
+    multi_brier: Vec<f32>,
+    rps: Vec<f32>,
+}
+
+/// Score each question saved as one market per option on its whole distribution, using the
+/// time-averaged probability of each option. Questions are only scored when at least two of
+/// their options are in the cohort and the options' resolutions add up to 1, so a question
+/// missing its winner isn't scored as if every option lost. Questions whose options are all
+/// ordered also get a ranked probability score.
+fn get_multi_outcome_scores(markets: &[Market]) -> HashMap<String, MultiOutcomeScores> {
+    let mut questions: HashMap<(&String, &String), Vec<&Market>> = HashMap::new();
+    for market in markets {
+        if let Some(linked_group) = &market.linked_group {
+            questions
+                .entry((&market.platform, linked_group))
+                .or_default()
+                .push(market);
+        }
+    }
+
+    let mut scores: HashMap<String, MultiOutcomeScores> = HashMap::new();
+    for ((platform, _), mut options) in questions {
+        let total_resolution: f32 = options.iter().map(|m| m.resolution).sum();
+        if options.len() < 2 || (total_resolution - 1.0).abs() > MULTI_RESOLUTION_TOLERANCE {
+            continue;
+        }
+        let is_ordered = options.iter().all(|m| m.linked_order.is_some());
+        if is_ordered {
+            options.sort_unstable_by_key(|m| m.linked_order);
+        }
+        let probs: Vec<f32> = options.iter().map(|m| m.prob_time_avg).collect();
+        let outcomes: Vec<f32> = options.iter().map(|m| m.resolution).collect();
+
+        let entry = scores.entry(platform.clone()).or_default();
+        entry.multi_brier.push(multi_brier_score(&probs, &outcomes));
+        if is_ordered {
+            entry.rps.push(ranked_probability_score(&probs, &outcomes));
+        }
+    }
+    scores
+}
+
+/// Compute the current aggregate scores for each platform in a cohort.
+/// During a grade run, the numeric scores are taken from `state` instead of being computed
+/// again for every cohort. The time spent on each stage is added to `cost`.
+pub fn score_cohort(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    cohort: &Cohort,
+    methodology_version: i32,
+    state: Option<&GradeState>,
+    cost: &mut GradeCost,
+) -> Result<Vec<CohortScore>, ApiError> {
+    let filters: CommonFilterParams = serde_json::from_value(cohort.filters.clone())
+        .map_err(|e| ApiError::new(500, format!("failed to parse cohort filters: {e}")))?;
+    let start = Instant::now();
+    let markets = get_cohort_markets(conn, excluded, &filters, methodology_version)?;
+    let numeric_markets =
+        get_cohort_numeric_markets(conn, excluded, &filters, methodology_version)?;
+    let base_rates = get_base_rates(conn)?;
+    cost.add_stage("load", start);
+
+    Ok(score_markets(
+        cohort.id,
+        markets,
+        numeric_markets,
+        &base_rates,
+        methodology_version,
+        state,
+        cost,
+    ))
+}
+
+/// Compute the aggregate scores for each platform from a cohort's binary and numeric markets.
+/// The Brier skill score is measured against the `base_rates` of each platform and category.
+/// The numeric scores are taken from `state` if it's given. The time spent on each stage is
+/// added to `cost`.
+pub fn score_markets(
+    cohort_id: i32,
+    markets: Vec<Market>,
+    numeric_markets: Vec<Market>,
+    base_rates: &HashMap<(String, String), f32>,
+    methodology_version: i32,
+    state: Option<&GradeState>,
+    cost: &mut GradeCost,
+) -> Vec<CohortScore> {
+    let numeric_markets = categorize_markets_by_platform(numeric_markets);
+    let scored_dt = Utc::now();
+
+    let start = Instant::now();
+    let mut crps_scores: HashMap<&String, Vec<f32>> = HashMap::new();
+    for (platform, market_list) in &numeric_markets {
+        for market in market_list {
+            let score = match state {
+                Some(state) => state.get_crps(market),
+                None => market.crps_time_avg(),
+            };
+            crps_scores.entry(platform).or_default().extend(score);
+        }
+    }
+    cost.add_stage("numeric_scores", start);
+
+    let start = Instant::now();
+    let multi_scores = get_multi_outcome_scores(&markets);
+    cost.add_stage("multi_scores", start);
+
+    let start = Instant::now();
+    let mut scores: Vec<CohortScore> = categorize_markets_by_platform(markets)
+        .into_iter()
+        .map(|(platform, market_list)| {
+            let count = market_list.len() as f32;
+            let average_brier = |prob: fn(&Market) -> f32| {
+                market_list
+                    .iter()
+                    .map(|m| (m.resolution - prob(m)).powf(2.0))
+                    .sum::<f32>()
+                    / count
+            };
+            let numeric_list = numeric_markets.get(&platform);
+            let crps_time_avg = crps_scores
+                .get(&platform)
+                .filter(|scores| !scores.is_empty())
+                .map(|scores| scores.iter().sum::<f32>() / scores.len() as f32);
+            let multi = multi_scores.get(&platform);
+            let average = |scores: &[f32]| {
+                (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+            };
+            let calibration_error = get_calibration_error(
+                market_list
+                    .iter()
+                    .map(|m| (m.prob_time_avg, m.resolution, 1.0)),
+            );
+            CohortScore {
+                cohort_id,
+                scored_dt,
+                platform,
+                num_markets: market_list.len() as i32,
+                brier_at_midpoint: average_brier(|m| m.prob_at_midpoint),
+                brier_at_close: average_brier(|m| m.prob_at_close),
+                brier_time_avg: average_brier(|m| m.prob_time_avg),
+                methodology_version,
+                num_numeric_markets: numeric_list.map_or(0, |list| list.len() as i32),
+                crps_time_avg,
+                calibration_error: Some(calibration_error.expected),
+                brier_reliability: Some(calibration_error.reliability),
+                brier_skill_time_avg: get_brier_skill_score(
+                    &market_list,
+                    |m| m.prob_time_avg,
+                    base_rates,
+                ),
+                num_multi_questions: multi.map_or(0, |m| m.multi_brier.len() as i32),
+                multi_brier_time_avg: multi.and_then(|m| average(&m.multi_brier)),
+                rps_time_avg: multi.and_then(|m| average(&m.rps)),
+            }
+        })
+        .collect();
+    cost.add_stage("binary_scores", start);
+
+    // sort by platform name so it's consistent
+    scores.sort_unstable_by(|a, b| a.platform.cmp(&b.platform));
+
+    scores
+}
+
+/// Get the current scores for a cohort along with any recorded history.
+/// With `as_of`, the scores and history are as they were at the end of that day instead,
+/// so a grade cited on that date can be looked up later.
+pub fn build_cohort_scores(
+    cohort_id: i32,
+    query: Query<CohortScoresQueryParams>,
+    excluded: &ExclusionList,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let cohort = get_cohort_by_id(conn, cohort_id)?;
+    let mut history = get_cohort_score_history(conn, cohort_id)?;
+    let current = match query.as_of {
+        Some(as_of) => {
+            if query.methodology_version.is_some() {
+                return Err(ApiError::new(
+                    400,
+                    "`methodology_version` can't be set with `as_of`, recorded scores keep the version they were computed with".to_string(),
+                ));
+            }
+            let end_of_day = (as_of + chrono::Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .map(|dt| dt.and_utc() - chrono::Duration::microseconds(1));
+            let snapshots: Vec<DateTime<Utc>> =
+                history.iter().map(|score| score.scored_dt).collect();
+            let snapshot_dt = find_snapshot(&snapshots, end_of_day).ok_or(ApiError::new(
+                404,
+                format!("no scores were recorded for this cohort by {as_of}"),
+            ))?;
+            history.retain(|score| score.scored_dt <= snapshot_dt);
+            history
+                .iter()
+                .filter(|score| score.scored_dt == snapshot_dt)
+                .cloned()
+                .collect()
+        }
+        None => {
+            let methodology_version = get_methodology_version(query.methodology_version)?;
+            score_cohort(
+                conn,
+                excluded,
+                &cohort,
+                methodology_version,
+                None,
+                &mut GradeCost::default(),
+            )?
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(CohortScoresResponse {
+        schema_version: SCHEMA_VERSION,
+        cohort,
+        current,
+        history,
+    }))
+}
+
+/// Score a cohort and save the result to its history.
+pub fn build_cohort_score_record(
+    cohort_id: i32,
+    query: Query<MethodologyQueryParams>,
+    excluded: &ExclusionList,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let methodology_version = get_methodology_version(query.methodology_version)?;
+    let cohort = get_cohort_by_id(conn, cohort_id)?;
+    let current = score_cohort(
+        conn,
+        excluded,
+        &cohort,
+        methodology_version,
+        None,
+        &mut GradeCost::default(),
+    )?;
+    insert_cohort_scores(conn, &current)?;
+
+    Ok(HttpResponse::Created().json(current))
+}
+
+/// Score every cohort and save the results to their histories.
+/// The time taken and the number of cohorts that were scored are added to `cost`.
+pub fn record_all_cohort_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+    state: &GradeState,
+    cost: &mut GradeCost,
+) -> Result<(), ApiError> {
+    let cohorts = get_all_cohorts(conn)?;
+    for cohort in &cohorts {
+        let current = score_cohort(
+            conn,
+            excluded,
+            cohort,
+            methodology_version,
+            Some(state),
+            cost,
+        )?;
+        let start = Instant::now();
+        insert_cohort_scores(conn, &current)?;
+        cost.add_stage("save", start);
+    }
+    cost.num_cohorts = cohorts.len();
+    Ok(())
+}
+
+/// Pick the latest snapshot time at or before `time`, or the latest overall.
+fn find_snapshot(
+    snapshots: &[DateTime<Utc>],
+    time: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    snapshots
+        .iter()
+        .filter(|dt| time.iter().all(|time| *dt <= time))
+        .max()
+        .copied()
+}
+
+/// List the known reasons a platform's scores changed between two snapshots.
+/// Data corrections are counted for the whole platform, since the logged changes
+/// aren't tied to a cohort.
+fn get_change_causes(
+    before: Option<&CohortScore>,
+    after: Option<&CohortScore>,
+    changes: &[MarketChange],
+) -> Vec<String> {
+    let mut causes = Vec::new();
+    match (before, after) {
+        (None, Some(_)) => causes.push("platform added to the cohort".to_string()),
+        (Some(_), None) => causes.push("platform no longer has markets in the cohort".to_string()),
+        (Some(before), Some(after)) => {
+            if before.methodology_version != after.methodology_version {
+                causes.push(format!(
+                    "methodology changed from version {} to {}",
+                    before.methodology_version, after.methodology_version
+                ));
+            }
+            match after.num_markets - before.num_markets {
+                0 => (),
+                added if added > 0 => causes.push(format!("{added} new markets")),
+                removed => causes.push(format!("{} fewer markets", -removed)),
+            }
+        }
+        (None, None) => (),
+    }
+    for field in ["resolution", "volume_usd"] {
+        let count = changes.iter().filter(|c| c.field == field).count();
+        if count > 0 {
+            causes.push(format!("{count} {field} corrections on this platform"));
+        }
+    }
+    causes
+}
+
+/// Format a score change for the plain-text report.
+pub fn format_score_change(label: &str, before: Option<f32>, after: Option<f32>) -> String {
+    match (before, after) {
+        (Some(before), Some(after)) => {
+            format!("{label} {before:.4} -> {after:.4} ({:+.4})", after - before)
+        }
+        (None, Some(after)) => format!("{label} {after:.4} (new)"),
+        (Some(before), None) => format!("{label} {before:.4} (removed)"),
+        (None, None) => format!("{label} n/a"),
+    }
+}
+
+/// Compare two recorded snapshots of a cohort's scores and explain what changed.
+pub fn build_cohort_compare(
+    cohort_id: i32,
+    query: Query<CohortCompareQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let cohort = get_cohort_by_id(conn, cohort_id)?;
+    let history = get_cohort_score_history(conn, cohort_id)?;
+    let snapshots: Vec<DateTime<Utc>> = history
+        .iter()
+        .map(|score| score.scored_dt)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // default to the last two snapshots
+    let after_dt = find_snapshot(&snapshots, query.after).ok_or(ApiError::new(
+        404,
+        "no recorded scores to compare".to_string(),
+    ))?;
+    let before_dt = match query.before {
+        Some(before) => find_snapshot(&snapshots, Some(before)),
+        None => find_snapshot(
+            &snapshots,
+            Some(after_dt - chrono::Duration::microseconds(1)),
+        ),
+    }
+    .ok_or(ApiError::new(
+        404,
+        "no earlier snapshot to compare against".to_string(),
+    ))?;
+    if before_dt >= after_dt {
+        return Err(ApiError::new(
+            400,
+            "`before` must pick an earlier snapshot than `after`".to_string(),
+        ));
+    }
+
+    let snapshot_scores = |dt: DateTime<Utc>| -> HashMap<String, CohortScore> {
+        history
+            .iter()
+            .filter(|score| score.scored_dt == dt)
+            .map(|score| (score.platform.clone(), score.clone()))
+            .collect()
+    };
+    let before_scores = snapshot_scores(before_dt);
+    let after_scores = snapshot_scores(after_dt);
+    let market_changes = get_market_changes_between(conn, before_dt, after_dt)?;
+
+    let mut platforms: Vec<&String> = before_scores.keys().chain(after_scores.keys()).collect();
+    platforms.sort_unstable();
+    platforms.dedup();
+
+    let changes: Vec<CohortScoreChange> = platforms
+        .into_iter()
+        .map(|platform| {
+            let before = before_scores.get(platform);
+            let after = after_scores.get(platform);
+            let platform_changes: Vec<MarketChange> = market_changes
+                .iter()
+                .filter(|change| &change.platform == platform)
+                .cloned()
+                .collect();
+            let delta = |score: fn(&CohortScore) -> f32| Some(score(after?) - score(before?));
+            CohortScoreChange {
+                platform: platform.clone(),
+                causes: get_change_causes(before, after, &platform_changes),
+                brier_at_midpoint_change: delta(|s| s.brier_at_midpoint),
+                brier_at_close_change: delta(|s| s.brier_at_close),
+                brier_time_avg_change: delta(|s| s.brier_time_avg),
+                before: before.cloned(),
+                after: after.cloned(),
+            }
+        })
+        .collect();
+
+    let mut report = format!(
+        "Cohort {}: scores from {} compared to {}\n",
+        cohort.name,
+        before_dt.format("%Y-%m-%d %H:%M UTC"),
+        after_dt.format("%Y-%m-%d %H:%M UTC")
+    );
+    for change in &changes {
+        let before = change.before.as_ref();
+        let after = change.after.as_ref();
+        let scores = [
+            (
+                "midpoint",
+                before.map(|s| s.brier_at_midpoint),
+                after.map(|s| s.brier_at_midpoint),
+            ),
+            (
+                "close",
+                before.map(|s| s.brier_at_close),
+                after.map(|s| s.brier_at_close),
+            ),
+            (
+                "time-averaged",
+                before.map(|s| s.brier_time_avg),
+                after.map(|s| s.brier_time_avg),
+            ),
+        ]
+        .map(|(label, before, after)| format_score_change(label, before, after));
+        let causes = match change.causes.is_empty() {
+            true => "no logged cause".to_string(),
+            false => change.causes.join(", "),
+        };
+        report += &format!(
+            "- {}: Brier {}; {}\n",
+            change.platform,
+            scores.join(", "),
+            causes
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(CohortCompareResponse {
+        schema_version: SCHEMA_VERSION,
+        cohort,
+        before_dt,
+        after_dt,
+        changes,
+        report,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cohort() -> Cohort {
+        Cohort {
+            id: 2,
+            name: "Elections".to_string(),
+            description: "Resolved election markets".to_string(),
+            filters: serde_json::json!({"category_select": "Politics"}),
+            created_dt: "2024-01-01T00:00:00Z".parse().unwrap(),
+        }
+    }
+
+    fn sample_cohort_json() -> serde_json::Value {
+        serde_json::json!({
+            "id": 2,
+            "name": "Elections",
+            "description": "Resolved election markets",
+            "filters": {"category_select": "Politics"},
+            "created_dt": "2024-01-01T00:00:00Z",
+        })
+    }
+
+    #[test]
+    fn cohort_scores_response_matches_snapshot() {
+        let response = CohortScoresResponse {
+            schema_version: SCHEMA_VERSION,
+            cohort: sample_cohort(),
+            current: Vec::new(),
+            history: Vec::new(),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "cohort": sample_cohort_json(),
+            "current": [],
+            "history": [],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+
+    #[test]
+    fn cohort_compare_response_matches_snapshot() {
+        let response = CohortCompareResponse {
+            schema_version: SCHEMA_VERSION,
+            cohort: sample_cohort(),
+            before_dt: "2024-01-05T00:00:00Z".parse().unwrap(),
+            after_dt: "2024-01-06T00:00:00Z".parse().unwrap(),
+            changes: Vec::from([CohortScoreChange {
+                platform: "manifold".to_string(),
+                before: None,
+                after: None,
+                brier_at_midpoint_change: Some(0.25),
+                brier_at_close_change: None,
+                brier_time_avg_change: Some(-0.125),
+                causes: Vec::from(["1 new market".to_string()]),
+            }]),
+            report: "report".to_string(),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "cohort": sample_cohort_json(),
+            "before_dt": "2024-01-05T00:00:00Z",
+            "after_dt": "2024-01-06T00:00:00Z",
+            "changes": [{
+                "platform": "manifold",
+                "before": null,
+                "after": null,
+                "brier_at_midpoint_change": 0.25,
+                "brier_at_close_change": null,
+                "brier_time_avg_change": -0.125,
+                "causes": ["1 new market"],
+            }],
+            "report": "report",
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
@@ -0,0 +1,149 @@
+//! Probability criterion points defined in `criteria.yaml` instead of being hard-coded.
+//! The file is read once on startup, so points can be added without recompiling.
+
+use super::*;
+
+/// Criterion points used when the config file is missing, such as on a read-only mirror.
+const DEFAULT_AT_PCT: [usize; 2] = [25, 75];
+const DEFAULT_AFTER_OPEN_DAYS: [usize; 2] = [7, 30];
+
+/// The criterion points to offer, by kind.
+#[derive(Debug, Deserialize)]
+pub struct CriteriaConfig {
+    /// Percents of the market duration.
+    #[serde(default)]
+    at_pct: Vec<usize>,
+    /// Days after the market opened.
+    #[serde(default)]
+    after_open_days: Vec<usize>,
+    /// Days before the market closed.
+    #[serde(default)]
+    before_close_days: Vec<usize>,
+}
+impl Default for CriteriaConfig {
+    fn default() -> CriteriaConfig {
+        CriteriaConfig {
+            at_pct: DEFAULT_AT_PCT.to_vec(),
+            after_open_days: DEFAULT_AFTER_OPEN_DAYS.to_vec(),
+            before_close_days: Vec::new(),
+        }
+    }
+}
+impl CriteriaConfig {
+    /// Every configured point, in the order they should be shown to users.
+    pub fn points(&self) -> Vec<CriterionPoint> {
+        let at_pct = self.at_pct.iter().map(|pct| CriterionPoint::AtPct(*pct));
+        let after_open = self
+            .after_open_days
+            .iter()
+            .map(|days| CriterionPoint::AfterOpenDays(*days));
+        let before_close = self
+            .before_close_days
+            .iter()
+            .map(|days| CriterionPoint::BeforeCloseDays(*days));
+        at_pct.chain(after_open).chain(before_close).collect()
+    }
+
+    /// Make sure a requested point is one of the configured ones.
+    pub fn check(&self, point: &CriterionPoint) -> Result<(), ApiError> {
+        if self.points().contains(point) {
+            Ok(())
+        } else {
+            Err(ApiError::new(
+                400,
+                format!("criterion {} is not configured", point.id()),
+            ))
+        }
+    }
+}
+
+/// A probability taken from a single point in each market's history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CriterionPoint {
+    AtPct(usize),
+    AfterOpenDays(usize),
+    BeforeCloseDays(usize),
+}
+impl CriterionPoint {
+    /// Get the identifier sent by clients, such as `prob_at_pct_25`.
+    pub fn id(&self) -> String {
+        match self {
+            CriterionPoint::AtPct(pct) => format!("prob_at_pct_{pct}"),
+            CriterionPoint::AfterOpenDays(days) => format!("prob_after_open_days_{days}"),
+            CriterionPoint::BeforeCloseDays(days) => format!("prob_before_close_days_{days}"),
+        }
+    }
+
+    /// Parse an identifier from `id` back into a point.
+    pub fn parse(id: &str) -> Option<CriterionPoint> {
+        if let Some(pct) = id.strip_prefix("prob_at_pct_") {
+            pct.parse()
+                .ok()
+                .filter(|pct| *pct <= 100)
+                .map(CriterionPoint::AtPct)
+        } else if let Some(days) = id.strip_prefix("prob_after_open_days_") {
+            days.parse().ok().map(CriterionPoint::AfterOpenDays)
+        } else if let Some(days) = id.strip_prefix("prob_before_close_days_") {
+            days.parse().ok().map(CriterionPoint::BeforeCloseDays)
+        } else {
+            None
+        }
+    }
+
+    /// Get the market probability at this point.
+    /// Markets without the percent series are scored from the midpoint instead.
+    pub fn get_prob(&self, market: &Market) -> f32 {
+        match self {
+            CriterionPoint::AtPct(pct) => *market
+                .prob_each_pct
+                .get(*pct)
+                .unwrap_or(&market.prob_at_midpoint),
+            CriterionPoint::AfterOpenDays(days) => market.prob_after_open_days(*days as i64),
+            CriterionPoint::BeforeCloseDays(days) => market.prob_before_close_days(*days as i64),
+        }
+    }
+
+    /// Get the display name for this point.
+    pub fn get_title(&self) -> String {
+        match self {
+            CriterionPoint::AtPct(pct) => format!("Probability at {pct}% of Market Duration"),
+            CriterionPoint::AfterOpenDays(days) => {
+                format!("Probability {days} Days After Market Open")
+            }
+            CriterionPoint::BeforeCloseDays(days) => {
+                format!("Probability {days} Days Before Market Close")
+            }
+        }
+    }
+
+    /// Get a plain-language definition of how the probability is taken.
+    pub fn get_description(&self) -> String {
+        match self {
+            CriterionPoint::AtPct(pct) => {
+                format!("The market probability {pct}% of the way through its duration.")
+            }
+            CriterionPoint::AfterOpenDays(days) => format!(
+                "The market probability {days} days after it opened, or at close if it closed sooner."
+            ),
+            CriterionPoint::BeforeCloseDays(days) => format!(
+                "The market probability {days} days before it closed, or at open if it opened later."
+            ),
+        }
+    }
+}
+
+/// Load the criterion points from the config file, or the defaults if there isn't one.
+pub fn load_criteria_config() -> Result<CriteriaConfig, ApiError> {
+    let config_file = match File::open("criteria.yaml") {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CriteriaConfig::default()),
+        Err(e) => {
+            return Err(ApiError::new(
+                500,
+                format!("failed to load criteria file: {e}"),
+            ))
+        }
+    };
+    serde_yaml::from_reader(config_file)
+        .map_err(|e| ApiError::new(500, format!("failed to parse criteria file: {e}")))
+}
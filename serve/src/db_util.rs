@@ -1,4 +1,5 @@
 use super::*;
+use chrono::{Duration, SecondsFormat};
 
 // Diesel macro to get database schema.
 table! {
@@ -10,21 +11,43 @@ table! {
         url -> Varchar,
         open_dt -> Timestamptz,
         close_dt -> Timestamptz,
+        trading_end_dt -> Timestamptz,
+        resolved_dt -> Nullable<Timestamptz>,
         open_days -> Float,
         volume_usd -> Float,
         num_traders -> Integer,
         category -> Varchar,
+        region -> Varchar,
+        description -> Varchar,
+        description_excerpt -> Varchar,
+        image_url -> Nullable<Varchar>,
         prob_at_midpoint -> Float,
         prob_at_close -> Float,
         prob_each_pct -> Array<Float>,
         prob_each_date -> Jsonb,
+        prob_each_hour -> Jsonb,
+        prob_daily_start_dt -> Nullable<Timestamptz>,
+        prob_daily -> Array<Float>,
+        active_each_date -> Jsonb,
+        volume_each_date -> Jsonb,
+        prob_band_each_date -> Jsonb,
         prob_time_avg -> Float,
+        prob_min -> Float,
+        prob_max -> Float,
+        prob_daily_stddev -> Float,
+        prob_midpoint_crossings -> Integer,
+        days_to_confidence -> Nullable<Float>,
         resolution -> Float,
+        question_type -> Varchar,
+        quantiles_each_date -> Jsonb,
+        linked_group -> Nullable<Varchar>,
+        linked_order -> Nullable<Integer>,
     }
 }
 
 /// Data returned from the database, same as what we inserted.
-#[derive(Debug, Queryable, Serialize, Selectable, Clone)]
+/// Also read from the markets printed by fetch for an offline grade.
+#[derive(Debug, Queryable, Serialize, Deserialize, Selectable, Clone)]
 #[diesel(table_name = market)]
 pub struct Market {
     pub title: String,
@@ -33,16 +56,189 @@ pub struct Market {
     pub url: String,
     pub open_dt: DateTime<Utc>,
     pub close_dt: DateTime<Utc>,
+    pub trading_end_dt: DateTime<Utc>,
+    pub resolved_dt: Option<DateTime<Utc>>,
     pub open_days: f32,
     pub volume_usd: f32,
     pub num_traders: i32,
     pub category: String,
+    /// Either `US`, `UK`, `EU`, or `Global`, inferred from the title.
+    pub region: String,
+    pub description: String,
+    pub description_excerpt: String,
+    pub image_url: Option<String>,
     pub prob_at_midpoint: f32,
     pub prob_at_close: f32,
     pub prob_each_pct: Vec<f32>,
     pub prob_each_date: serde_json::Value,
+    /// The probability on each hour, only saved for short markets.
+    pub prob_each_hour: serde_json::Value,
+    /// The first day of `prob_daily`, if the daily probabilities were stored packed.
+    #[serde(skip_serializing, default)]
+    pub prob_daily_start_dt: Option<DateTime<Utc>>,
+    /// The probability on each consecutive day, if stored packed instead of in `prob_each_date`.
+    /// Use `unpack_prob_daily` after loading so everything else can read `prob_each_date`.
+    #[serde(skip_serializing, default)]
+    pub prob_daily: Vec<f32>,
+    pub active_each_date: serde_json::Value,
+    /// The volume traded on each day in USD, if the platform reports per-trade sizes.
+    pub volume_each_date: serde_json::Value,
+    /// How far a $1 bet would have moved the price on each day, for market makers that report it.
+    pub prob_band_each_date: serde_json::Value,
     pub prob_time_avg: f32,
+    /// The lowest and highest probabilities reached while the market was open.
+    pub prob_min: f32,
+    pub prob_max: f32,
+    /// The standard deviation of the daily probabilities.
+    pub prob_daily_stddev: f32,
+    /// How many times the daily probability crossed from one side of 50% to the other.
+    pub prob_midpoint_crossings: i32,
+    /// Days from open until the market reached 90% or 10% and stayed there, if it did.
+    pub days_to_confidence: Option<f32>,
     pub resolution: f32,
+    /// Either `binary`, `numeric`, or `date`. Numeric and date questions store the median
+    /// forecast as a position in the question range instead of a probability.
+    pub question_type: String,
+    /// The forecast quartiles at the end of each day, as positions in the question range.
+    pub quantiles_each_date: serde_json::Value,
+    /// Shared by every market that is one option of the same mutually-exclusive question.
+    pub linked_group: Option<String>,
+    /// The position of this option among the others in its linked group, if they're ordered.
+    pub linked_order: Option<i32>,
+}
+
+impl Market {
+    /// Expand packed daily probabilities into the `prob_each_date` map.
+    /// Markets stored as a JSON map are returned unchanged.
+    pub fn unpack_prob_daily(mut self) -> Market {
+        let Some(start) = self.prob_daily_start_dt else {
+            return self;
+        };
+        let prob_each_date: serde_json::Map<String, serde_json::Value> = self
+            .prob_daily
+            .iter()
+            .enumerate()
+            .map(|(i, prob)| {
+                let date = start + Duration::days(i as i64);
+                (
+                    date.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    serde_json::json!(prob),
+                )
+            })
+            .collect();
+        self.prob_each_date = serde_json::Value::Object(prob_each_date);
+        self.prob_daily_start_dt = None;
+        self.prob_daily = Vec::new();
+        self
+    }
+
+    /// Get the CRPS of the daily forecast quartiles against the outcome, averaged over every
+    /// day with a forecast. Binary questions have no quartiles, so this is `None` for them.
+    pub fn crps_time_avg(&self) -> Option<f32> {
+        let scores: Vec<f32> = self
+            .quantiles_each_date
+            .as_object()?
+            .values()
+            .filter_map(|value| serde_json::from_value::<[f32; 3]>(value.clone()).ok())
+            .map(|quartiles| crps_from_quartiles(quartiles, self.resolution))
+            .collect();
+        if scores.is_empty() {
+            return None;
+        }
+        Some(scores.iter().sum::<f32>() / scores.len() as f32)
+    }
+
+    /// Get the probability on the day a number of days after the market opened.
+    /// Markets that closed sooner use their closing probability, since it stopped changing then.
+    pub fn prob_after_open_days(&self, days: i64) -> f32 {
+        let Some(open_morning) = self.open_dt.date_naive().and_hms_opt(0, 0, 0) else {
+            return self.prob_at_close;
+        };
+        let date = (open_morning.and_utc() + Duration::days(days))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        match self
+            .prob_each_date
+            .get(&date)
+            .and_then(|prob| prob.as_f64())
+        {
+            Some(prob) => prob as f32,
+            None => self.prob_at_close,
+        }
+    }
+
+    /// Get the probability on the day a number of days before the market closed.
+    /// Markets that opened later use their opening probability, since they had no price yet.
+    pub fn prob_before_close_days(&self, days: i64) -> f32 {
+        let open_prob = self
+            .prob_each_pct
+            .first()
+            .copied()
+            .unwrap_or(self.prob_at_midpoint);
+        let Some(close_morning) = self.close_dt.date_naive().and_hms_opt(0, 0, 0) else {
+            return open_prob;
+        };
+        let date = (close_morning.and_utc() - Duration::days(days))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        match self
+            .prob_each_date
+            .get(&date)
+            .and_then(|prob| prob.as_f64())
+        {
+            Some(prob) => prob as f32,
+            None => open_prob,
+        }
+    }
+
+    /// Get the Brier score of each daily probability, averaged over the market's whole life.
+    /// Markets without a daily series fall back to the Brier score of the time-averaged probability.
+    pub fn brier_time_integrated(&self, weighting: DayWeighting) -> f32 {
+        let mut daily: Vec<(&String, f32)> = self
+            .prob_each_date
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(date, prob)| Some((date, prob.as_f64()? as f32)))
+            .collect();
+        if daily.is_empty() {
+            return (self.resolution - self.prob_time_avg).powi(2);
+        }
+        // dates are RFC 3339 in UTC, so they sort correctly as strings
+        daily.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let len = daily.len();
+        let (weighted_sum, total_weight) =
+            daily
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(sum, total), (index, (_, prob))| {
+                    let weight = weighting.get_weight(index, len);
+                    (
+                        sum + weight * (self.resolution - prob).powi(2),
+                        total + weight,
+                    )
+                });
+        weighted_sum / total_weight
+    }
+}
+
+/// How to weight each day when averaging daily scores over a market's life.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DayWeighting {
+    /// Every day counts the same.
+    Uniform,
+    /// The first day counts the most, tapering linearly to the last.
+    Early,
+    /// The last day counts the most, tapering linearly to the first.
+    Late,
+}
+impl DayWeighting {
+    /// Get the weight of the day at `index` out of `len` days, in order.
+    fn get_weight(&self, index: usize, len: usize) -> f32 {
+        match self {
+            DayWeighting::Uniform => 1.0,
+            DayWeighting::Early => (len - index) as f32,
+            DayWeighting::Late => (index + 1) as f32,
+        }
+    }
 }
 
 /// Get information about a market from the database.
@@ -57,6 +253,7 @@ pub fn get_market_by_platform_id(
         .filter(platform_id.eq(platform_id_sel))
         .select(Market::as_select())
         .first(conn)
+        .map(Market::unpack_prob_daily)
         .map_err(|e| {
             ApiError::new(
                 500,
@@ -72,9 +269,33 @@ pub fn _get_all_markets(
     market::table
         .select(Market::as_select())
         .load::<Market>(conn)
+        .map(|markets| markets.into_iter().map(Market::unpack_prob_daily).collect())
         .map_err(|e| ApiError::new(500, format!("failed to query db for markets: {e}")))
 }
 
+/// Get the historical resolution frequency of binary markets for each platform and category.
+pub fn get_base_rates(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HashMap<(String, String), f32>, ApiError> {
+    market::table
+        .filter(market::question_type.eq("binary"))
+        .group_by((market::platform, market::category))
+        .select((
+            market::platform,
+            market::category,
+            diesel::dsl::avg(market::resolution),
+        ))
+        .load::<(String, String, Option<f64>)>(conn)
+        .map(|rows| {
+            rows.into_iter()
+                .filter_map(|(platform, category, rate)| {
+                    rate.map(|rate| ((platform, category), rate as f32))
+                })
+                .collect()
+        })
+        .map_err(|e| ApiError::new(500, format!("failed to query db for base rates: {e}")))
+}
+
 // Diesel macro to get database schema.
 table! {
     platform (name) {
@@ -85,6 +306,7 @@ table! {
         site_url -> Varchar,
         color -> Varchar,
         color_accent -> Varchar,
+        capabilities -> Jsonb,
     }
 }
 
@@ -99,6 +321,8 @@ pub struct Platform {
     pub site_url: String,
     pub color: String,
     pub color_accent: String,
+    /// The data the platform provides, recorded by the fetch tool on each run.
+    pub capabilities: serde_json::Value,
 }
 
 /// Get information about a platform from the database.
@@ -122,3 +346,872 @@ pub fn get_all_platforms(
         .load::<Platform>(conn)
         .map_err(|e| ApiError::new(500, format!("failed to query db for platforms: {e}")))
 }
+
+// Diesel macro to get database schema.
+table! {
+    cohort (id) {
+        id -> Int4,
+        name -> Varchar,
+        description -> Varchar,
+        filters -> Jsonb,
+        created_dt -> Timestamptz,
+    }
+}
+
+/// A saved set of filters that can be scored over time.
+#[derive(Debug, Queryable, Serialize, Selectable)]
+#[diesel(table_name = cohort)]
+pub struct Cohort {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub filters: serde_json::Value,
+    pub created_dt: DateTime<Utc>,
+}
+
+/// Data needed to save a new cohort.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = cohort)]
+pub struct NewCohort {
+    pub name: String,
+    pub description: String,
+    pub filters: serde_json::Value,
+}
+
+/// Get all saved cohorts.
+pub fn get_all_cohorts(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<Cohort>, ApiError> {
+    cohort::table
+        .order(cohort::id.asc())
+        .select(Cohort::as_select())
+        .load::<Cohort>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for cohorts: {e}")))
+}
+
+/// Get a single saved cohort.
+pub fn get_cohort_by_id(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    cohort_id: i32,
+) -> Result<Cohort, ApiError> {
+    Ok(cohort::table
+        .find(cohort_id)
+        .select(Cohort::as_select())
+        .first(conn)?)
+}
+
+/// Save a new cohort, returning the stored row.
+pub fn insert_cohort(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    new_cohort: NewCohort,
+) -> Result<Cohort, ApiError> {
+    Ok(diesel::insert_into(cohort::table)
+        .values(new_cohort)
+        .returning(Cohort::as_returning())
+        .get_result(conn)?)
+}
+
+// Diesel macro to get database schema.
+table! {
+    cohort_score (id) {
+        id -> Int4,
+        cohort_id -> Int4,
+        scored_dt -> Timestamptz,
+        platform -> Varchar,
+        num_markets -> Integer,
+        brier_at_midpoint -> Float,
+        brier_at_close -> Float,
+        brier_time_avg -> Float,
+        methodology_version -> Integer,
+        num_numeric_markets -> Integer,
+        crps_time_avg -> Nullable<Float>,
+        calibration_error -> Nullable<Float>,
+        brier_reliability -> Nullable<Float>,
+        brier_skill_time_avg -> Nullable<Float>,
+        num_multi_questions -> Integer,
+        multi_brier_time_avg -> Nullable<Float>,
+        rps_time_avg -> Nullable<Float>,
+    }
+}
+
+/// Aggregate scores for one platform in a cohort at a point in time.
+#[derive(Debug, Default, Queryable, Serialize, Selectable, Insertable, Clone)]
+#[diesel(table_name = cohort_score)]
+pub struct CohortScore {
+    pub cohort_id: i32,
+    pub scored_dt: DateTime<Utc>,
+    pub platform: String,
+    pub num_markets: i32,
+    pub brier_at_midpoint: f32,
+    pub brier_at_close: f32,
+    pub brier_time_avg: f32,
+    /// The scoring methodology these scores were computed with.
+    pub methodology_version: i32,
+    /// The number of numeric and date questions, which are scored separately with the CRPS.
+    pub num_numeric_markets: i32,
+    /// The average CRPS of the numeric and date questions, if there are any.
+    pub crps_time_avg: Option<f32>,
+    /// The expected calibration error of the time-averaged probabilities.
+    /// Scores recorded before this was added don't have it.
+    pub calibration_error: Option<f32>,
+    /// The reliability component of the time-averaged Brier score.
+    pub brier_reliability: Option<f32>,
+    /// The Brier skill score of the time-averaged probabilities against the base rate
+    /// of each market's platform and category. Higher is better.
+    pub brier_skill_time_avg: Option<f32>,
+    /// The number of questions saved as one market per option, which are also scored on their
+    /// whole distribution.
+    pub num_multi_questions: i32,
+    /// The average multi-class Brier score of those questions, if there are any.
+    pub multi_brier_time_avg: Option<f32>,
+    /// The average ranked probability score of the questions with ordered options, if any.
+    pub rps_time_avg: Option<f32>,
+}
+
+/// Get all recorded scores for a cohort, oldest first.
+pub fn get_cohort_score_history(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    cohort_id_req: i32,
+) -> Result<Vec<CohortScore>, ApiError> {
+    cohort_score::table
+        .filter(cohort_score::cohort_id.eq(cohort_id_req))
+        .order((cohort_score::scored_dt.asc(), cohort_score::platform.asc()))
+        .select(CohortScore::as_select())
+        .load::<CohortScore>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for cohort scores: {e}")))
+}
+
+/// Get the last time scores were recorded for each platform, across all cohorts.
+pub fn get_last_scored_dt_by_platform(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HashMap<String, DateTime<Utc>>, ApiError> {
+    cohort_score::table
+        .group_by(cohort_score::platform)
+        .select((
+            cohort_score::platform,
+            diesel::dsl::max(cohort_score::scored_dt),
+        ))
+        .load::<(String, Option<DateTime<Utc>>)>(conn)
+        .map(|rows| {
+            rows.into_iter()
+                .filter_map(|(platform, scored_dt)| scored_dt.map(|dt| (platform, dt)))
+                .collect()
+        })
+        .map_err(|e| ApiError::new(500, format!("failed to query db for cohort scores: {e}")))
+}
+
+/// Save a set of cohort scores.
+pub fn insert_cohort_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    scores: &[CohortScore],
+) -> Result<usize, ApiError> {
+    Ok(diesel::insert_into(cohort_score::table)
+        .values(scores)
+        .execute(conn)?)
+}
+
+// Diesel macro to get database schema.
+table! {
+    platform_rating (id) {
+        id -> Int4,
+        scored_dt -> Timestamptz,
+        platform -> Varchar,
+        category -> Varchar,
+        group_key -> Varchar,
+        rated_dt -> Timestamptz,
+        rating -> Float,
+        num_groups -> Integer,
+        methodology_version -> Integer,
+    }
+}
+
+/// A platform's rating just after one linked question was counted.
+#[derive(Debug, Default, Queryable, Serialize, Selectable, Insertable, Clone)]
+#[diesel(table_name = platform_rating)]
+pub struct PlatformRating {
+    pub scored_dt: DateTime<Utc>,
+    pub platform: String,
+    /// The market category these ratings are for, or `All` for every category together.
+    pub category: String,
+    /// The linked question this rating is from, as its first market in `platform/platform_id`
+    /// form, which stays the same when the mapping file is reordered.
+    pub group_key: String,
+    /// When the last market in the group closed.
+    pub rated_dt: DateTime<Utc>,
+    pub rating: f32,
+    /// The number of groups this platform has been rated on so far.
+    pub num_groups: i32,
+    /// The scoring methodology these ratings were computed with.
+    pub methodology_version: i32,
+}
+
+/// Number of ratings to insert at once, to stay under the bind parameter limit.
+const RATING_INSERT_CHUNK_SIZE: usize = 1000;
+
+/// Replace the saved rating history for a methodology version with a new one.
+pub fn replace_platform_ratings(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    ratings: &[PlatformRating],
+) -> Result<usize, ApiError> {
+    conn.transaction(|conn| {
+        diesel::delete(
+            platform_rating::table.filter(platform_rating::methodology_version.eq(version)),
+        )
+        .execute(conn)?;
+        let mut count = 0;
+        for chunk in ratings.chunks(RATING_INSERT_CHUNK_SIZE) {
+            count += diesel::insert_into(platform_rating::table)
+                .values(chunk)
+                .execute(conn)?;
+        }
+        Ok(count)
+    })
+    .map_err(|e: diesel::result::Error| {
+        ApiError::new(500, format!("failed to save platform ratings: {e}"))
+    })
+}
+
+/// Get the saved rating history under a methodology version, optionally for one platform or
+/// category, oldest first.
+pub fn get_platform_ratings(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    platform: Option<&str>,
+    category: Option<&str>,
+) -> Result<Vec<PlatformRating>, ApiError> {
+    let mut query = platform_rating::table
+        .filter(platform_rating::methodology_version.eq(version))
+        .into_boxed();
+    if let Some(platform) = platform {
+        query = query.filter(platform_rating::platform.eq(platform));
+    }
+    if let Some(category) = category {
+        query = query.filter(platform_rating::category.eq(category));
+    }
+    query
+        .order((
+            platform_rating::rated_dt.asc(),
+            platform_rating::group_key.asc(),
+            platform_rating::platform.asc(),
+        ))
+        .select(PlatformRating::as_select())
+        .load::<PlatformRating>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for platform ratings: {e}")))
+}
+
+// Diesel macro to get database schema.
+table! {
+    period_score (id) {
+        id -> Int4,
+        scored_dt -> Timestamptz,
+        platform -> Varchar,
+        category -> Varchar,
+        year -> Integer,
+        num_markets -> Integer,
+        brier_at_midpoint -> Float,
+        brier_at_close -> Float,
+        brier_time_avg -> Float,
+        methodology_version -> Integer,
+    }
+}
+
+/// Aggregate scores for one platform and category over the markets that closed in one year.
+#[derive(Debug, Default, Queryable, Serialize, Selectable, Insertable, Clone)]
+#[diesel(table_name = period_score)]
+pub struct PeriodScore {
+    pub scored_dt: DateTime<Utc>,
+    pub platform: String,
+    /// The market category, or `All` for every category together.
+    pub category: String,
+    /// The calendar year the markets closed in.
+    pub year: i32,
+    pub num_markets: i32,
+    pub brier_at_midpoint: f32,
+    pub brier_at_close: f32,
+    pub brier_time_avg: f32,
+    /// The scoring methodology these scores were computed with.
+    pub methodology_version: i32,
+}
+
+/// Replace the saved yearly scores for a methodology version with a new set.
+pub fn replace_period_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    scores: &[PeriodScore],
+) -> Result<usize, ApiError> {
+    conn.transaction(|conn| {
+        diesel::delete(period_score::table.filter(period_score::methodology_version.eq(version)))
+            .execute(conn)?;
+        diesel::insert_into(period_score::table)
+            .values(scores)
+            .execute(conn)
+    })
+    .map_err(|e| ApiError::new(500, format!("failed to save period scores: {e}")))
+}
+
+/// Remove the saved yearly scores for each `stale` platform, category, and year, then save
+/// the new scores for the ones that still have markets.
+pub fn merge_period_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    scores: &[PeriodScore],
+    stale: &[(String, String, i32)],
+) -> Result<usize, ApiError> {
+    conn.transaction(|conn| {
+        for (platform_sel, category_sel, year_sel) in stale {
+            diesel::delete(
+                period_score::table
+                    .filter(period_score::methodology_version.eq(version))
+                    .filter(period_score::platform.eq(platform_sel))
+                    .filter(period_score::category.eq(category_sel))
+                    .filter(period_score::year.eq(year_sel)),
+            )
+            .execute(conn)?;
+        }
+        diesel::insert_into(period_score::table)
+            .values(scores)
+            .execute(conn)
+    })
+    .map_err(|e| ApiError::new(500, format!("failed to save period scores: {e}")))
+}
+
+/// Get the saved yearly scores for a methodology version, optionally for one platform
+/// or category, ordered by platform, category, then year.
+pub fn get_period_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    platform: Option<&str>,
+    category: Option<&str>,
+) -> Result<Vec<PeriodScore>, ApiError> {
+    let mut query = period_score::table
+        .filter(period_score::methodology_version.eq(version))
+        .into_boxed();
+    if let Some(platform) = platform {
+        query = query.filter(period_score::platform.eq(platform));
+    }
+    if let Some(category) = category {
+        query = query.filter(period_score::category.eq(category));
+    }
+    query
+        .order((
+            period_score::platform.asc(),
+            period_score::category.asc(),
+            period_score::year.asc(),
+        ))
+        .select(PeriodScore::as_select())
+        .load::<PeriodScore>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for period scores: {e}")))
+}
+
+// Diesel macro to get database schema.
+table! {
+    market_grade (id) {
+        id -> Int4,
+        platform -> Varchar,
+        platform_id -> Varchar,
+        methodology_version -> Integer,
+        content_hash -> Int8,
+        category -> Varchar,
+        close_year -> Integer,
+        crps_time_avg -> Nullable<Float>,
+        graded_dt -> Timestamptz,
+    }
+}
+
+/// What a market looked like when it was last graded, so the next incremental grade can tell
+/// whether it changed and reuse its slow scores if not.
+#[derive(Debug, Queryable, Selectable, Insertable, Clone)]
+#[diesel(table_name = market_grade)]
+pub struct MarketGrade {
+    pub platform: String,
+    pub platform_id: String,
+    pub methodology_version: i32,
+    /// A hash of every field the grade reads, see `get_content_hash`.
+    pub content_hash: i64,
+    /// The category and year the market was counted in for the yearly scores.
+    pub category: String,
+    pub close_year: i32,
+    /// The CRPS of a numeric or date question, so it only has to be integrated once.
+    pub crps_time_avg: Option<f32>,
+    pub graded_dt: DateTime<Utc>,
+}
+
+/// Number of market grades to insert at once, to stay under the bind parameter limit.
+const MARKET_GRADE_INSERT_CHUNK_SIZE: usize = 1000;
+
+/// Get every market fingerprint saved by the last grade under a methodology version.
+pub fn get_market_grades(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+) -> Result<Vec<MarketGrade>, ApiError> {
+    market_grade::table
+        .filter(market_grade::methodology_version.eq(version))
+        .select(MarketGrade::as_select())
+        .load::<MarketGrade>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for market grades: {e}")))
+}
+
+/// Save market fingerprints for a methodology version. With `replace`, every saved one is
+/// removed first. Otherwise the given ones are upserted and the `removed` ones deleted.
+pub fn save_market_grades(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    grades: &[MarketGrade],
+    removed: &[(String, String)],
+    replace: bool,
+) -> Result<usize, ApiError> {
+    use diesel::upsert::excluded;
+    conn.transaction(|conn| {
+        if replace {
+            diesel::delete(
+                market_grade::table.filter(market_grade::methodology_version.eq(version)),
+            )
+            .execute(conn)?;
+        }
+        for (platform_sel, platform_id_sel) in removed {
+            diesel::delete(
+                market_grade::table
+                    .filter(market_grade::methodology_version.eq(version))
+                    .filter(market_grade::platform.eq(platform_sel))
+                    .filter(market_grade::platform_id.eq(platform_id_sel)),
+            )
+            .execute(conn)?;
+        }
+        let mut count = 0;
+        for chunk in grades.chunks(MARKET_GRADE_INSERT_CHUNK_SIZE) {
+            count += diesel::insert_into(market_grade::table)
+                .values(chunk)
+                .on_conflict((
+                    market_grade::platform,
+                    market_grade::platform_id,
+                    market_grade::methodology_version,
+                ))
+                .do_update()
+                .set((
+                    market_grade::content_hash.eq(excluded(market_grade::content_hash)),
+                    market_grade::category.eq(excluded(market_grade::category)),
+                    market_grade::close_year.eq(excluded(market_grade::close_year)),
+                    market_grade::crps_time_avg.eq(excluded(market_grade::crps_time_avg)),
+                    market_grade::graded_dt.eq(excluded(market_grade::graded_dt)),
+                ))
+                .execute(conn)?;
+        }
+        Ok(count)
+    })
+    .map_err(|e: diesel::result::Error| {
+        ApiError::new(500, format!("failed to save market grades: {e}"))
+    })
+}
+
+// Diesel macro to get database schema.
+table! {
+    trader_bet (id) {
+        id -> Int4,
+        platform -> Varchar,
+        platform_id -> Varchar,
+        trader_id -> Varchar,
+        bet_probs -> Array<Float>,
+        last_bet_dt -> Timestamptz,
+    }
+}
+
+/// The prices one anonymized trader bought at on one market, saved by fetch.
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = trader_bet)]
+pub struct TraderBets {
+    pub platform: String,
+    pub platform_id: String,
+    pub trader_id: String,
+    /// The market probability just after each of the trader's buys, oldest first.
+    pub bet_probs: Vec<f32>,
+}
+
+/// Get every trader's bets on every market.
+pub fn get_all_trader_bets(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<TraderBets>, ApiError> {
+    trader_bet::table
+        .select(TraderBets::as_select())
+        .load::<TraderBets>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for trader bets: {e}")))
+}
+
+// Diesel macro to get database schema.
+table! {
+    trader_score (id) {
+        id -> Int4,
+        scored_dt -> Timestamptz,
+        platform -> Varchar,
+        trader_id -> Varchar,
+        num_markets -> Integer,
+        num_bets -> Integer,
+        brier -> Float,
+        relative_brier -> Float,
+        methodology_version -> Integer,
+    }
+}
+
+/// Aggregate scores for one anonymized trader over every resolved market they bought on.
+#[derive(Debug, Default, Queryable, Serialize, Selectable, Insertable, Clone)]
+#[diesel(table_name = trader_score)]
+pub struct TraderScore {
+    pub scored_dt: DateTime<Utc>,
+    pub platform: String,
+    pub trader_id: String,
+    pub num_markets: i32,
+    pub num_bets: i32,
+    /// The average Brier score of the trader's bet prices.
+    pub brier: f32,
+    /// The trader's Brier score minus the market's time-averaged Brier score, averaged
+    /// over markets. Negative means the trader's prices beat the market.
+    pub relative_brier: f32,
+    /// The scoring methodology these scores were computed with.
+    pub methodology_version: i32,
+}
+
+/// Number of trader scores to insert at once, to stay under the bind parameter limit.
+const TRADER_SCORE_INSERT_CHUNK_SIZE: usize = 1000;
+
+/// Replace the saved trader scores for a methodology version with a new set.
+pub fn replace_trader_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    scores: &[TraderScore],
+) -> Result<usize, ApiError> {
+    conn.transaction(|conn| {
+        diesel::delete(trader_score::table.filter(trader_score::methodology_version.eq(version)))
+            .execute(conn)?;
+        let mut count = 0;
+        for chunk in scores.chunks(TRADER_SCORE_INSERT_CHUNK_SIZE) {
+            count += diesel::insert_into(trader_score::table)
+                .values(chunk)
+                .execute(conn)?;
+        }
+        Ok(count)
+    })
+    .map_err(|e: diesel::result::Error| {
+        ApiError::new(500, format!("failed to save trader scores: {e}"))
+    })
+}
+
+/// Get the saved trader scores for a methodology version with at least `min_markets`
+/// markets, best relative score first.
+pub fn get_trader_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    version: i32,
+    min_markets: i32,
+    limit: i64,
+) -> Result<Vec<TraderScore>, ApiError> {
+    trader_score::table
+        .filter(trader_score::methodology_version.eq(version))
+        .filter(trader_score::num_markets.ge(min_markets))
+        .order((
+            trader_score::relative_brier.asc(),
+            trader_score::trader_id.asc(),
+        ))
+        .limit(limit)
+        .select(TraderScore::as_select())
+        .load::<TraderScore>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for trader scores: {e}")))
+}
+
+// Diesel macro to get database schema.
+table! {
+    suggestion (id) {
+        id -> Int4,
+        kind -> Varchar,
+        markets -> Jsonb,
+        message -> Varchar,
+        status -> Varchar,
+        created_dt -> Timestamptz,
+    }
+}
+
+/// Data needed to save a new user suggestion for moderation.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = suggestion)]
+pub struct NewSuggestion {
+    pub kind: String,
+    pub markets: serde_json::Value,
+    pub message: String,
+}
+
+/// Save a new suggestion, returning its ID.
+pub fn insert_suggestion(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    new_suggestion: NewSuggestion,
+) -> Result<i32, ApiError> {
+    Ok(diesel::insert_into(suggestion::table)
+        .values(new_suggestion)
+        .returning(suggestion::id)
+        .get_result(conn)?)
+}
+
+// Diesel macro to get database schema.
+table! {
+    job (id) {
+        id -> Int4,
+        stage -> Varchar,
+        params -> Jsonb,
+        status -> Varchar,
+        started_dt -> Timestamptz,
+        finished_dt -> Nullable<Timestamptz>,
+        error -> Nullable<Varchar>,
+        summary -> Nullable<Jsonb>,
+    }
+}
+
+/// A run of a pipeline stage, such as a fetch or a regrade.
+#[derive(Debug, Queryable, Serialize, Selectable)]
+#[diesel(table_name = job)]
+pub struct Job {
+    pub id: i32,
+    pub stage: String,
+    pub params: serde_json::Value,
+    /// Either `running`, `complete`, `degraded`, or `failed`.
+    pub status: String,
+    pub started_dt: DateTime<Utc>,
+    pub finished_dt: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    /// Details recorded when the job finished, such as where a grade run spent its time.
+    pub summary: Option<serde_json::Value>,
+}
+
+/// Data needed to record the start of a pipeline stage.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = job)]
+pub struct NewJob {
+    pub stage: String,
+    pub params: serde_json::Value,
+}
+
+/// Record the start of a pipeline stage, returning its ID.
+/// Only one job per stage can be running, so this errors with a 409 if one already is.
+pub fn insert_job(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    new_job: NewJob,
+) -> Result<i32, ApiError> {
+    let stage = new_job.stage.clone();
+    diesel::insert_into(job::table)
+        .values(new_job)
+        .returning(job::id)
+        .get_result(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => ApiError::new(409, format!("another {stage} job is already running")),
+            e => e.into(),
+        })
+}
+
+/// Mark a pipeline stage as finished, with the error if it failed and a summary of the run.
+pub fn finish_job(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    job_id: i32,
+    error: Option<String>,
+    summary: Option<serde_json::Value>,
+) -> Result<usize, ApiError> {
+    let status = match error {
+        None => "complete",
+        Some(_) => "failed",
+    };
+    Ok(diesel::update(job::table.find(job_id))
+        .set((
+            job::status.eq(status),
+            job::finished_dt.eq(Some(Utc::now())),
+            job::error.eq(error),
+            job::summary.eq(summary),
+        ))
+        .execute(conn)?)
+}
+
+/// Mark a job as failed if it's still running, returning the number of jobs that were updated.
+pub fn fail_running_job(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    job_id: i32,
+    error: String,
+) -> Result<usize, ApiError> {
+    Ok(
+        diesel::update(job::table.find(job_id).filter(job::status.eq("running")))
+            .set((
+                job::status.eq("failed"),
+                job::finished_dt.eq(Some(Utc::now())),
+                job::error.eq(Some(error)),
+            ))
+            .execute(conn)?,
+    )
+}
+
+/// Get the most recent jobs, newest first.
+pub fn get_recent_jobs(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    limit: i64,
+) -> Result<Vec<Job>, ApiError> {
+    job::table
+        .order(job::id.desc())
+        .limit(limit)
+        .select(Job::as_select())
+        .load::<Job>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for jobs: {e}")))
+}
+
+/// Get every finished job for a stage that didn't fail, most recently finished first.
+pub fn get_finished_jobs(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    stage_sel: &str,
+) -> Result<Vec<Job>, ApiError> {
+    job::table
+        .filter(job::stage.eq(stage_sel))
+        .filter(job::status.ne("failed"))
+        .filter(job::finished_dt.is_not_null())
+        .order(job::finished_dt.desc())
+        .select(Job::as_select())
+        .load::<Job>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for jobs: {e}")))
+}
+
+// Diesel macro to get database schema.
+table! {
+    market_change (id) {
+        id -> Int4,
+        run_id -> Varchar,
+        platform -> Varchar,
+        platform_id -> Varchar,
+        field -> Varchar,
+        old_value -> Float,
+        new_value -> Float,
+        changed_dt -> Timestamptz,
+    }
+}
+
+/// A material change to a stored market, logged by the fetch tool before it was overwritten.
+#[derive(Debug, Queryable, Serialize, Selectable, Clone)]
+#[diesel(table_name = market_change)]
+pub struct MarketChange {
+    pub run_id: String,
+    pub platform: String,
+    pub platform_id: String,
+    /// Either `resolution` or `volume_usd`.
+    pub field: String,
+    pub old_value: f32,
+    pub new_value: f32,
+    pub changed_dt: DateTime<Utc>,
+}
+
+/// Get every market change logged after `start` and up to `end`.
+pub fn get_market_changes_between(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<MarketChange>, ApiError> {
+    market_change::table
+        .filter(market_change::changed_dt.gt(start))
+        .filter(market_change::changed_dt.le(end))
+        .select(MarketChange::as_select())
+        .load::<MarketChange>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query db for market changes: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A market as printed by fetch, with every field set.
+    fn fetch_market_json() -> serde_json::Value {
+        serde_json::json!({
+            "title": "Will it rain?",
+            "platform": "manifold",
+            "platform_id": "abc123",
+            "url": "https://manifold.markets/user/will-it-rain",
+            "open_dt": "2024-01-01T00:00:00Z",
+            "close_dt": "2024-01-03T00:00:00Z",
+            "trading_end_dt": "2024-01-03T00:00:00Z",
+            "resolved_dt": "2024-01-04T00:00:00Z",
+            "open_days": 2.0,
+            "volume_usd": 12.5,
+            "num_traders": 3,
+            "category": "Climate",
+            "region": "US",
+            "description": "Resolves YES if it rains.",
+            "description_excerpt": "Resolves YES if it rains.",
+            "image_url": null,
+            "prob_at_midpoint": 0.5,
+            "prob_at_close": 0.75,
+            "prob_each_pct": [0.5, 0.75],
+            "prob_each_date": {"2024-01-01T00:00:00Z": 0.5},
+            "prob_each_hour": {},
+            "prob_daily_start_dt": null,
+            "prob_daily": [],
+            "active_each_date": {"2024-01-01T00:00:00Z": true},
+            "volume_each_date": {"2024-01-01T00:00:00Z": 12.5},
+            "prob_band_each_date": {},
+            "prob_time_avg": 0.625,
+            "prob_min": 0.5,
+            "prob_max": 0.75,
+            "prob_daily_stddev": 0.125,
+            "prob_midpoint_crossings": 1,
+            "days_to_confidence": 1.5,
+            "resolution": 1.0,
+            "question_type": "binary",
+            "quantiles_each_date": {},
+            "linked_group": "group",
+            "linked_order": 0,
+        })
+    }
+
+    /// Markets are returned by several routes and read back from fetch output for an offline
+    /// grade, so their format should only change along with `SCHEMA_VERSION`.
+    /// The packed probability fields are read but never returned.
+    #[test]
+    fn market_matches_snapshot() {
+        let market: Market = serde_json::from_value(fetch_market_json()).unwrap();
+        let mut expected = fetch_market_json();
+        let fields = expected.as_object_mut().unwrap();
+        fields.remove("prob_daily_start_dt");
+        fields.remove("prob_daily");
+        assert_eq!(serde_json::to_value(&market).unwrap(), expected);
+    }
+
+    #[test]
+    fn cohort_score_matches_snapshot() {
+        let score = CohortScore {
+            cohort_id: 2,
+            scored_dt: "2024-01-05T00:00:00Z".parse().unwrap(),
+            platform: "manifold".to_string(),
+            num_markets: 10,
+            brier_at_midpoint: 0.25,
+            brier_at_close: 0.125,
+            brier_time_avg: 0.1875,
+            methodology_version: 1,
+            num_numeric_markets: 2,
+            crps_time_avg: Some(0.5),
+            calibration_error: Some(0.0625),
+            brier_reliability: None,
+            brier_skill_time_avg: Some(-0.5),
+            num_multi_questions: 0,
+            multi_brier_time_avg: None,
+            rps_time_avg: None,
+        };
+        let expected = serde_json::json!({
+            "cohort_id": 2,
+            "scored_dt": "2024-01-05T00:00:00Z",
+            "platform": "manifold",
+            "num_markets": 10,
+            "brier_at_midpoint": 0.25,
+            "brier_at_close": 0.125,
+            "brier_time_avg": 0.1875,
+            "methodology_version": 1,
+            "num_numeric_markets": 2,
+            "crps_time_avg": 0.5,
+            "calibration_error": 0.0625,
+            "brier_reliability": null,
+            "brier_skill_time_avg": -0.5,
+            "num_multi_questions": 0,
+            "multi_brier_time_avg": null,
+            "rps_time_avg": null,
+        });
+        assert_eq!(serde_json::to_value(&score).unwrap(), expected);
+    }
+}
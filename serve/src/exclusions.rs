@@ -0,0 +1,127 @@
+//! Markets left out of every plot and score, listed in `exclusions.yaml` with the reason for each.
+//! The file is read once on startup, so the server must be restarted to pick up changes.
+
+use super::*;
+use std::collections::BTreeMap;
+
+/// Why a market is excluded.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// The platform reported data we know is wrong, like a broken price history.
+    BadData,
+    /// The market is a copy of another market on the same platform.
+    Duplicate,
+    /// The creator resolved the market against its own criteria.
+    AbusiveResolution,
+}
+
+/// A single excluded market, with a note explaining the problem.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Exclusion {
+    #[serde(flatten)]
+    market: MarketRef,
+    reason: ExclusionReason,
+    #[serde(default)]
+    note: String,
+}
+
+/// Full response for the exclusion list.
+#[derive(Debug, Serialize)]
+struct ExclusionListResponse<'a> {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    /// The number of excluded markets for each reason.
+    counts: BTreeMap<ExclusionReason, usize>,
+    exclusions: &'a [Exclusion],
+}
+
+/// Every excluded market, loaded once and shared by every request.
+#[derive(Debug, Default)]
+pub struct ExclusionList {
+    exclusions: Vec<Exclusion>,
+    markets: HashSet<MarketRef>,
+}
+impl ExclusionList {
+    /// Load the exclusion list from the config file, or an empty list if there isn't one.
+    pub fn load() -> Result<ExclusionList, ApiError> {
+        let config_file = match File::open("exclusions.yaml") {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(ExclusionList::default())
+            }
+            Err(e) => {
+                return Err(ApiError::new(
+                    500,
+                    format!("failed to load exclusion file: {e}"),
+                ))
+            }
+        };
+        let exclusions: Vec<Exclusion> = serde_yaml::from_reader(config_file)
+            .map_err(|e| ApiError::new(500, format!("failed to parse exclusion file: {e}")))?;
+        let markets = exclusions
+            .iter()
+            .map(|exclusion| exclusion.market.clone())
+            .collect();
+        Ok(ExclusionList {
+            exclusions,
+            markets,
+        })
+    }
+
+    /// Check whether a market is on the list.
+    pub fn contains(&self, market: &Market) -> bool {
+        !self.markets.is_empty()
+            && self.markets.contains(&MarketRef {
+                platform: market.platform.clone(),
+                platform_id: market.platform_id.clone(),
+            })
+    }
+}
+
+/// List every excluded market along with the number excluded for each reason.
+pub fn build_exclusion_list(exclusions: &ExclusionList) -> Result<HttpResponse, ApiError> {
+    let mut counts = BTreeMap::new();
+    for exclusion in &exclusions.exclusions {
+        *counts.entry(exclusion.reason).or_default() += 1;
+    }
+
+    Ok(HttpResponse::Ok().json(ExclusionListResponse {
+        schema_version: SCHEMA_VERSION,
+        counts,
+        exclusions: &exclusions.exclusions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclusion_list_response_matches_snapshot() {
+        let exclusions = [Exclusion {
+            market: MarketRef {
+                platform: "manifold".to_string(),
+                platform_id: "abc123".to_string(),
+            },
+            reason: ExclusionReason::AbusiveResolution,
+            note: "Resolved early".to_string(),
+        }];
+        let response = ExclusionListResponse {
+            schema_version: SCHEMA_VERSION,
+            counts: BTreeMap::from([(ExclusionReason::AbusiveResolution, 1)]),
+            exclusions: &exclusions,
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "counts": {"abusive_resolution": 1},
+            "exclusions": [{
+                "platform": "manifold",
+                "platform_id": "abc123",
+                "reason": "abusive_resolution",
+                "note": "Resolved early",
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
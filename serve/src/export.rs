@@ -0,0 +1,238 @@
+//! Export the full graded dataset to CSV files with a manifest, so researchers can download
+//! every score at once instead of paging through the API.
+
+use super::*;
+use std::io::Write;
+use std::path::Path;
+
+/// The version of the export layout, increment it when a file or column is renamed or removed.
+const EXPORT_VERSION: u32 = 1;
+
+/// The scores for a single market under the current methodology.
+#[derive(Debug, Default, Serialize)]
+struct MarketScoreRow {
+    platform: String,
+    platform_id: String,
+    title: String,
+    url: String,
+    category: String,
+    question_type: String,
+    close_dt: DateTime<Utc>,
+    resolution: f32,
+    prob_at_midpoint: f32,
+    prob_at_close: f32,
+    prob_time_avg: f32,
+    /// Only set for binary questions.
+    brier_at_midpoint: Option<f32>,
+    brier_at_close: Option<f32>,
+    brier_time_avg: Option<f32>,
+    /// Only set for numeric and date questions.
+    crps_time_avg: Option<f32>,
+}
+
+/// One file written by the export.
+#[derive(Debug, Serialize)]
+struct ExportFile {
+    name: String,
+    rows: usize,
+    columns: Vec<String>,
+}
+
+/// Describes every file in the export, saved alongside them as `manifest.json`.
+#[derive(Debug, Serialize)]
+pub struct ExportManifest {
+    export_version: u32,
+    /// The version of the API response format the rows match, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    methodology_version: i32,
+    exported_dt: DateTime<Utc>,
+    files: Vec<ExportFile>,
+}
+
+/// Format one value as a CSV cell. Nested values are written as JSON, and cells with a comma,
+/// quote, or line break are quoted.
+fn format_csv_cell(value: &serde_json::Value) -> String {
+    let cell = match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    match cell.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell,
+    }
+}
+
+/// Get the columns of a row type, in field order. These come from a default row rather than
+/// the first one, so an empty table still gets a header.
+fn get_columns<T: Serialize + Default>() -> Result<Vec<String>, ApiError> {
+    match serde_json::to_value(T::default()) {
+        Ok(serde_json::Value::Object(fields)) => Ok(fields.keys().cloned().collect()),
+        _ => Err(ApiError::new(
+            500,
+            "failed to get the columns of a row type".to_string(),
+        )),
+    }
+}
+
+/// Write a list of rows to a CSV file, with one column for each field.
+fn write_csv<T: Serialize + Default>(
+    output_dir: &Path,
+    name: &str,
+    rows: &[T],
+) -> Result<ExportFile, ApiError> {
+    let path = output_dir.join(name);
+    let write_error =
+        |e: std::io::Error| ApiError::new(500, format!("failed to write {}: {e}", path.display()));
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+        .iter()
+        .map(|row| match serde_json::to_value(row) {
+            Ok(serde_json::Value::Object(fields)) => Ok(fields),
+            _ => Err(ApiError::new(
+                500,
+                format!("failed to convert a row of {name} to columns"),
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+    let columns = get_columns::<T>()?;
+
+    let mut file = std::io::BufWriter::new(File::create(&path).map_err(write_error)?);
+    writeln!(file, "{}", columns.join(",")).map_err(write_error)?;
+    for fields in &rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| format_csv_cell(fields.get(column).unwrap_or(&serde_json::Value::Null)))
+            .collect();
+        writeln!(file, "{}", cells.join(",")).map_err(write_error)?;
+    }
+    file.flush().map_err(write_error)?;
+
+    Ok(ExportFile {
+        name: name.to_string(),
+        rows: rows.len(),
+        columns,
+    })
+}
+
+/// Score every market that would be graded under this methodology, using the saved CRPS
+/// for numeric markets the last grade saved one for, as long as they haven't changed since.
+fn get_market_score_rows(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+) -> Result<Vec<MarketScoreRow>, ApiError> {
+    let filters = CommonFilterParams::default();
+    let markets = get_cohort_markets(conn, excluded, &filters, methodology_version)?;
+    let numeric_markets =
+        get_cohort_numeric_markets(conn, excluded, &filters, methodology_version)?;
+    let saved_crps: HashMap<(String, String), (i64, f32)> =
+        get_market_grades(conn, methodology_version)?
+            .into_iter()
+            .filter_map(|grade| {
+                let crps = grade.crps_time_avg?;
+                Some((
+                    (grade.platform, grade.platform_id),
+                    (grade.content_hash, crps),
+                ))
+            })
+            .collect();
+
+    let brier = |market: &Market, prob: f32| (market.resolution - prob).powf(2.0);
+    let rows = markets
+        .iter()
+        .map(|market| (market, false))
+        .chain(numeric_markets.iter().map(|market| (market, true)))
+        .map(|(market, is_numeric)| {
+            let binary_score = |prob: f32| (!is_numeric).then(|| brier(market, prob));
+            let crps_time_avg = match is_numeric {
+                true => saved_crps
+                    .get(&(market.platform.clone(), market.platform_id.clone()))
+                    .filter(|(content_hash, _)| *content_hash == get_content_hash(market))
+                    .map(|(_, crps)| *crps)
+                    .or_else(|| market.crps_time_avg()),
+                false => None,
+            };
+            MarketScoreRow {
+                platform: market.platform.clone(),
+                platform_id: market.platform_id.clone(),
+                title: market.title.clone(),
+                url: market.url.clone(),
+                category: market.category.clone(),
+                question_type: market.question_type.clone(),
+                close_dt: market.close_dt,
+                resolution: market.resolution,
+                prob_at_midpoint: market.prob_at_midpoint,
+                prob_at_close: market.prob_at_close,
+                prob_time_avg: market.prob_time_avg,
+                brier_at_midpoint: binary_score(market.prob_at_midpoint),
+                brier_at_close: binary_score(market.prob_at_close),
+                brier_time_avg: binary_score(market.prob_time_avg),
+                crps_time_avg,
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Write every market score, cohort score snapshot, yearly score, rating, and trader score
+/// under a methodology version to CSV files in the output directory, with a `manifest.json`
+/// listing the files, their row counts, and their columns.
+pub fn export_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+    output_dir: &Path,
+) -> Result<ExportManifest, ApiError> {
+    std::fs::create_dir_all(output_dir).map_err(|e| {
+        ApiError::new(
+            500,
+            format!("failed to create {}: {e}", output_dir.display()),
+        )
+    })?;
+
+    let mut snapshots = Vec::new();
+    for cohort in get_all_cohorts(conn)? {
+        snapshots.extend(
+            get_cohort_score_history(conn, cohort.id)?
+                .into_iter()
+                .filter(|score| score.methodology_version == methodology_version),
+        );
+    }
+    let files = vec![
+        write_csv(
+            output_dir,
+            "market_scores.csv",
+            &get_market_score_rows(conn, excluded, methodology_version)?,
+        )?,
+        write_csv(output_dir, "cohort_scores.csv", &snapshots)?,
+        write_csv(
+            output_dir,
+            "period_scores.csv",
+            &get_period_scores(conn, methodology_version, None, None)?,
+        )?,
+        write_csv(
+            output_dir,
+            "ratings.csv",
+            &get_platform_ratings(conn, methodology_version, None, None)?,
+        )?,
+        write_csv(
+            output_dir,
+            "trader_scores.csv",
+            &get_trader_scores(conn, methodology_version, 0, i64::MAX)?,
+        )?,
+    ];
+
+    let manifest = ExportManifest {
+        export_version: EXPORT_VERSION,
+        schema_version: SCHEMA_VERSION,
+        methodology_version,
+        exported_dt: Utc::now(),
+        files,
+    };
+    let path = output_dir.join("manifest.json");
+    let file = File::create(&path)
+        .map_err(|e| ApiError::new(500, format!("failed to create {}: {e}", path.display())))?;
+    serde_json::to_writer_pretty(file, &manifest)
+        .map_err(|e| ApiError::new(500, format!("failed to write {}: {e}", path.display())))?;
+    Ok(manifest)
+}
@@ -0,0 +1,177 @@
+//! How recently each pipeline stage has run for each platform, so stale data can be flagged.
+//! A fetch run downloads and extracts markets in one stage, so both share a timestamp.
+
+use super::*;
+use chrono::Duration;
+
+/// How old a stage can get before it is considered stale, if not set in the query.
+const DEFAULT_MAX_AGE_HOURS: i64 = 48;
+
+/// Parameters passed to the freshness check.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FreshnessQueryParams {
+    /// How many hours old the last fetch can be, or how far grading can trail it.
+    max_age_hours: Option<i64>,
+}
+
+/// How fresh a platform's data is, from best to worst.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum FreshnessStatus {
+    /// Every stage has run recently.
+    Ok,
+    /// The last fetch finished but some responses failed to deserialize.
+    Degraded,
+    /// The last fetch is recent but the scores haven't been recorded since.
+    Lagging,
+    /// The last fetch is too old.
+    Stale,
+    /// The platform has never been fetched.
+    Missing,
+}
+
+/// The stage timestamps and status for a single platform.
+#[derive(Debug, Serialize)]
+struct PlatformFreshness {
+    platform: String,
+    /// When the last fetch including this platform finished.
+    last_fetch_dt: Option<DateTime<Utc>>,
+    /// The status of that fetch, either `complete` or `degraded`.
+    last_fetch_status: Option<String>,
+    /// When scores were last recorded for this platform in any cohort.
+    last_grade_dt: Option<DateTime<Utc>>,
+    status: FreshnessStatus,
+}
+
+/// Full response for the freshness check.
+#[derive(Debug, Serialize)]
+struct FreshnessResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    checked_dt: DateTime<Utc>,
+    max_age_hours: i64,
+    /// The worst status of any platform.
+    status: FreshnessStatus,
+    platforms: Vec<PlatformFreshness>,
+}
+
+/// Check whether a fetch job covered a platform.
+/// Fetch records the platform as its enum name, or null if it ran every platform.
+fn is_job_for_platform(job: &Job, platform: &str) -> bool {
+    match job.params.get("platform").and_then(|p| p.as_str()) {
+        None => true,
+        Some(name) => name.eq_ignore_ascii_case(platform),
+    }
+}
+
+/// Decide a platform's status from its stage timestamps.
+fn get_freshness_status(
+    last_fetch: Option<&Job>,
+    last_grade_dt: Option<DateTime<Utc>>,
+    checked_dt: DateTime<Utc>,
+    max_age: Duration,
+) -> FreshnessStatus {
+    let Some((fetch_dt, fetch_status)) =
+        last_fetch.and_then(|job| job.finished_dt.map(|dt| (dt, job.status.as_str())))
+    else {
+        return FreshnessStatus::Missing;
+    };
+    if checked_dt - fetch_dt > max_age {
+        return FreshnessStatus::Stale;
+    }
+    // platforms that aren't in any cohort are never graded, so they can't lag
+    if last_grade_dt.is_some_and(|grade_dt| fetch_dt - grade_dt > max_age) {
+        return FreshnessStatus::Lagging;
+    }
+    match fetch_status {
+        "degraded" => FreshnessStatus::Degraded,
+        _ => FreshnessStatus::Ok,
+    }
+}
+
+/// Report the last fetch and grade for each platform along with a status.
+pub fn build_freshness(
+    query: Query<FreshnessQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let max_age_hours = query.max_age_hours.unwrap_or(DEFAULT_MAX_AGE_HOURS);
+    if max_age_hours <= 0 {
+        return Err(ApiError::new(
+            400,
+            format!("max_age_hours must be positive, got {max_age_hours}"),
+        ));
+    }
+    let max_age = Duration::hours(max_age_hours);
+    let checked_dt = Utc::now();
+
+    let fetch_jobs = get_finished_jobs(conn, "fetch")?;
+    let last_grades = get_last_scored_dt_by_platform(conn)?;
+    let mut platforms: Vec<PlatformFreshness> = get_all_platforms(conn)?
+        .into_iter()
+        .map(|platform| {
+            let last_fetch = fetch_jobs
+                .iter()
+                .find(|job| is_job_for_platform(job, &platform.name));
+            let last_grade_dt = last_grades.get(&platform.name).copied();
+            PlatformFreshness {
+                status: get_freshness_status(last_fetch, last_grade_dt, checked_dt, max_age),
+                last_fetch_dt: last_fetch.and_then(|job| job.finished_dt),
+                last_fetch_status: last_fetch.map(|job| job.status.clone()),
+                last_grade_dt,
+                platform: platform.name,
+            }
+        })
+        .collect();
+
+    // sort by platform name so it's consistent
+    platforms.sort_unstable_by(|a, b| a.platform.cmp(&b.platform));
+
+    let response = FreshnessResponse {
+        schema_version: SCHEMA_VERSION,
+        checked_dt,
+        max_age_hours,
+        status: platforms
+            .iter()
+            .map(|platform| platform.status)
+            .max()
+            .unwrap_or(FreshnessStatus::Ok),
+        platforms,
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freshness_response_matches_snapshot() {
+        let response = FreshnessResponse {
+            schema_version: SCHEMA_VERSION,
+            checked_dt: "2024-01-05T00:00:00Z".parse().unwrap(),
+            max_age_hours: 48,
+            status: FreshnessStatus::Lagging,
+            platforms: Vec::from([PlatformFreshness {
+                platform: "manifold".to_string(),
+                last_fetch_dt: Some("2024-01-04T00:00:00Z".parse().unwrap()),
+                last_fetch_status: Some("complete".to_string()),
+                last_grade_dt: None,
+                status: FreshnessStatus::Lagging,
+            }]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "checked_dt": "2024-01-05T00:00:00Z",
+            "max_age_hours": 48,
+            "status": "lagging",
+            "platforms": [{
+                "platform": "manifold",
+                "last_fetch_dt": "2024-01-04T00:00:00Z",
+                "last_fetch_status": "complete",
+                "last_grade_dt": null,
+                "status": "lagging",
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
@@ -0,0 +1,269 @@
+//! A dry run of the grade, which computes every score and compares it against what's saved
+//! without writing anything, so a change to the scoring can be reviewed before it's deployed.
+
+use super::*;
+use std::time::Instant;
+
+/// How far a score can move before it's listed as changed, to skip float noise.
+const SCORE_CHANGE_TOLERANCE: f32 = 1e-6;
+
+/// How one platform's scores in a cohort or category would change if the grade were saved.
+#[derive(Debug, Serialize)]
+pub struct GradeChange {
+    /// Either the cohort name or the year, depending on which scores changed.
+    scope: String,
+    platform: String,
+    /// The market category for yearly scores, or `All` for cohorts.
+    category: String,
+    num_markets_before: Option<i32>,
+    num_markets_after: Option<i32>,
+    /// Every other value that's saved for the platform, so a change to any of them is listed.
+    scores: Vec<ScoreChange>,
+}
+
+/// The saved and new value of one score, with the other market counts compared the same way.
+#[derive(Debug, Serialize)]
+pub struct ScoreChange {
+    name: &'static str,
+    before: Option<f32>,
+    after: Option<f32>,
+}
+
+/// Every score that would change, with a plain-text report for reviewing them.
+#[derive(Debug, Serialize)]
+pub struct GradeDiff {
+    pub cohort_changes: Vec<GradeChange>,
+    pub period_changes: Vec<GradeChange>,
+    pub report: String,
+}
+
+impl ScoreChange {
+    /// Check whether the score moved by more than float noise, or was added or removed.
+    fn is_changed(&self) -> bool {
+        match (self.before, self.after) {
+            (Some(before), Some(after)) => (after - before).abs() > SCORE_CHANGE_TOLERANCE,
+            (None, None) => false,
+            _ => true,
+        }
+    }
+}
+
+impl GradeChange {
+    /// Check whether anything differs between the saved and new scores.
+    fn is_changed(&self) -> bool {
+        self.num_markets_before != self.num_markets_after
+            || self.scores.iter().any(ScoreChange::is_changed)
+    }
+
+    /// Format the change as one line of the report.
+    fn format_line(&self) -> String {
+        let markets = match (self.num_markets_before, self.num_markets_after) {
+            (Some(before), Some(after)) if before != after => {
+                format!("{before} -> {after} markets")
+            }
+            (Some(before), Some(_)) => format!("{before} markets"),
+            (None, Some(after)) => format!("{after} markets (new)"),
+            (Some(before), None) => format!("{before} markets (removed)"),
+            (None, None) => "no markets".to_string(),
+        };
+        let scores: Vec<String> = self
+            .scores
+            .iter()
+            .filter(|score| score.is_changed())
+            .map(|score| format_score_change(score.name, score.before, score.after))
+            .collect();
+        let scores = match scores.is_empty() {
+            true => "scores unchanged".to_string(),
+            false => scores.join(", "),
+        };
+        format!(
+            "- {} / {} / {}: {}; {}\n",
+            self.scope, self.platform, self.category, scores, markets
+        )
+    }
+}
+
+/// The named values saved for one platform, in a fixed order.
+type ScoreValues = Vec<(&'static str, Option<f32>)>;
+
+/// Every value saved for a platform in a cohort snapshot, besides its market count.
+fn get_cohort_score_values(score: &CohortScore) -> ScoreValues {
+    Vec::from([
+        ("brier_at_midpoint", Some(score.brier_at_midpoint)),
+        ("brier_at_close", Some(score.brier_at_close)),
+        ("brier_time_avg", Some(score.brier_time_avg)),
+        (
+            "num_numeric_markets",
+            Some(score.num_numeric_markets as f32),
+        ),
+        ("crps_time_avg", score.crps_time_avg),
+        ("calibration_error", score.calibration_error),
+        ("brier_reliability", score.brier_reliability),
+        ("brier_skill_time_avg", score.brier_skill_time_avg),
+        (
+            "num_multi_questions",
+            Some(score.num_multi_questions as f32),
+        ),
+        ("multi_brier_time_avg", score.multi_brier_time_avg),
+        ("rps_time_avg", score.rps_time_avg),
+    ])
+}
+
+/// Every value saved for a platform in a yearly score, besides its market count.
+fn get_period_score_values(score: &PeriodScore) -> ScoreValues {
+    Vec::from([
+        ("brier_at_midpoint", Some(score.brier_at_midpoint)),
+        ("brier_at_close", Some(score.brier_at_close)),
+        ("brier_time_avg", Some(score.brier_time_avg)),
+    ])
+}
+
+/// Pair up the saved and new values of each score by name.
+fn pair_scores<T>(
+    before: Option<&T>,
+    after: Option<&T>,
+    get_values: fn(&T) -> ScoreValues,
+) -> Vec<ScoreChange> {
+    let before = before.map(get_values).unwrap_or_default();
+    let after = after.map(get_values).unwrap_or_default();
+    let names = match before.is_empty() {
+        true => &after,
+        false => &before,
+    };
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| ScoreChange {
+            name,
+            before: before.get(i).and_then(|(_, value)| *value),
+            after: after.get(i).and_then(|(_, value)| *value),
+        })
+        .collect()
+}
+
+/// Compare the current scores of every cohort against its latest saved snapshot under the
+/// same methodology. The time spent is added to `cost`.
+fn diff_cohort_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+    state: &GradeState,
+    cost: &mut GradeCost,
+) -> Result<Vec<GradeChange>, ApiError> {
+    let cohorts = get_all_cohorts(conn)?;
+    let mut changes = Vec::new();
+    for cohort in &cohorts {
+        let current = score_cohort(
+            conn,
+            excluded,
+            cohort,
+            methodology_version,
+            Some(state),
+            cost,
+        )?;
+        let history: Vec<CohortScore> = get_cohort_score_history(conn, cohort.id)?
+            .into_iter()
+            .filter(|score| score.methodology_version == methodology_version)
+            .collect();
+        let last_dt = history.iter().map(|score| score.scored_dt).max();
+        let saved: HashMap<String, CohortScore> = history
+            .into_iter()
+            .filter(|score| Some(score.scored_dt) == last_dt)
+            .map(|score| (score.platform.clone(), score))
+            .collect();
+        let current: HashMap<String, CohortScore> = current
+            .into_iter()
+            .map(|score| (score.platform.clone(), score))
+            .collect();
+
+        let mut platforms: Vec<&String> = saved.keys().chain(current.keys()).collect();
+        platforms.sort_unstable();
+        platforms.dedup();
+        for platform in platforms {
+            let before = saved.get(platform);
+            let after = current.get(platform);
+            changes.push(GradeChange {
+                scope: cohort.name.clone(),
+                platform: platform.clone(),
+                category: "All".to_string(),
+                num_markets_before: before.map(|s| s.num_markets),
+                num_markets_after: after.map(|s| s.num_markets),
+                scores: pair_scores(before, after, get_cohort_score_values),
+            });
+        }
+    }
+    cost.num_cohorts = cohorts.len();
+    Ok(changes)
+}
+
+/// Compare the yearly scores for every platform and category against the saved ones.
+fn diff_period_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    methodology_version: i32,
+    state: &GradeState,
+) -> Result<Vec<GradeChange>, ApiError> {
+    let current = score_periods(&state.markets, None, Utc::now(), methodology_version);
+    let saved = get_period_scores(conn, methodology_version, None, None)?;
+    let key = |score: &PeriodScore| (score.year, score.platform.clone(), score.category.clone());
+    let saved: HashMap<(i32, String, String), PeriodScore> = saved
+        .into_iter()
+        .map(|score| (key(&score), score))
+        .collect();
+    let current: HashMap<(i32, String, String), PeriodScore> = current
+        .into_iter()
+        .map(|score| (key(&score), score))
+        .collect();
+
+    let mut periods: Vec<&(i32, String, String)> = saved.keys().chain(current.keys()).collect();
+    periods.sort_unstable();
+    periods.dedup();
+    Ok(periods
+        .into_iter()
+        .map(|period| {
+            let before = saved.get(period);
+            let after = current.get(period);
+            let (year, platform, category) = period;
+            GradeChange {
+                scope: year.to_string(),
+                platform: platform.clone(),
+                category: category.clone(),
+                num_markets_before: before.map(|s| s.num_markets),
+                num_markets_after: after.map(|s| s.num_markets),
+                scores: pair_scores(before, after, get_period_score_values),
+            }
+        })
+        .collect())
+}
+
+/// Compute the cohort and yearly scores a grade would save and list the ones that differ
+/// from what's saved now. Nothing is written. The time spent is added to `cost`.
+pub fn build_grade_diff(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+    state: &GradeState,
+    cost: &mut GradeCost,
+) -> Result<GradeDiff, ApiError> {
+    let mut cohort_changes = diff_cohort_scores(conn, excluded, methodology_version, state, cost)?;
+    let start = Instant::now();
+    let mut period_changes = diff_period_scores(conn, methodology_version, state)?;
+    cost.add_stage("period_scores", start);
+    cohort_changes.retain(GradeChange::is_changed);
+    period_changes.retain(GradeChange::is_changed);
+
+    let mut report = format!(
+        "Dry run under methodology version {methodology_version}: \
+        {} cohort scores and {} yearly scores would change\n",
+        cohort_changes.len(),
+        period_changes.len()
+    );
+    for change in cohort_changes.iter().chain(&period_changes) {
+        report += &change.format_line();
+    }
+
+    Ok(GradeDiff {
+        cohort_changes,
+        period_changes,
+        report,
+    })
+}
@@ -1,11 +1,165 @@
 use super::*;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
 
 type PlatformKey = String;
 type DateKey = String;
 type CategoryKey = String;
+/// Markets in a group keyed by platform, along with whether each one is inverted.
+type GroupMarkets = (HashMap<PlatformKey, Market>, HashMap<PlatformKey, bool>);
+
+/// Markets opened this many days apart should get a scoring window override.
+const WINDOW_SUGGESTION_MIN_DAYS: i64 = 30;
+
+/// Platforms with fewer markets than this in a category are flagged as too small a sample.
+const MIN_CATEGORY_SAMPLE_SIZE: usize = 10;
+
+/// How many markets' worth of weight the platform's overall mean gets when shrinking category scores.
+const SHRINKAGE_PRIOR_WEIGHT: f32 = 10.0;
+
+/// Number of passes used to fit the platform and question effects for the adjusted score.
+const ADJUSTMENT_ITERATIONS: usize = 20;
+
+/// The platform name given to uploaded forecasts when they are scored alongside the markets.
+const FORECAST_PLATFORM: &str = "forecast";
+
+/// The platform name for the baseline that always predicts 50%.
+const BASELINE_UNIFORM_PLATFORM: &str = "baseline_uniform";
+
+/// The platform name for the baseline that carries forward the question's earliest probability.
+const BASELINE_OPEN_PLATFORM: &str = "baseline_open";
+
+/// Options for scoring groups.
+#[derive(Debug, Deserialize)]
+pub struct GroupQueryParams {
+    /// Also compute relative scores for the first, middle, and last third of each group's dates.
+    pub time_slices: Option<bool>,
+    /// Weight given to days where a market had no activity when averaging relative scores.
+    /// Set to 0 to exclude inactive days entirely (default 1).
+    pub inactive_day_weight: Option<f32>,
+    /// Also score baseline forecasters that always predict 50% or the question's opening probability.
+    pub baselines: Option<bool>,
+    /// Only include groups about this region (`US`, `UK`, `EU`, or `Global`).
+    pub region_select: Option<String>,
+    /// Leave out groups about this region, e.g. `US` for everything else.
+    pub region_exclude: Option<String>,
+    /// Only include groups at least this difficult, e.g. to leave easy questions out.
+    pub difficulty_min: Option<f32>,
+    /// Only include groups at most this difficult.
+    pub difficulty_max: Option<f32>,
+    /// Weight each market's scores by duration, volume, or traders when averaging them
+    /// into its platform's scores (default `none`).
+    pub weight_attribute: Option<WeightAttribute>,
+    /// How to combine each platform's absolute Brier scores (default `mean`).
+    pub absolute_brier_aggregation: Option<Aggregation>,
+    /// How to combine each platform's relative Brier scores, including the time slices
+    /// (default `mean`).
+    pub relative_brier_aggregation: Option<Aggregation>,
+    /// What each market is compared against for its relative score: `median` (default),
+    /// `mean`, `best`, or a platform name to use that platform as the benchmark.
+    pub relative_baseline: Option<String>,
+    /// What to do with a market that resolved differently from most of its group:
+    /// `own` (default) or `exclude`.
+    pub resolution_policy: Option<ResolutionPolicy>,
+}
+
+/// How to score a market that resolved differently from most of the markets in its group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionPolicy {
+    /// Score each market against its own resolution.
+    #[default]
+    Own,
+    /// Leave the market out of the group's scores.
+    Exclude,
+}
+
+/// The score each market is compared against on each day to get its relative score.
+#[derive(Debug, Clone, Default, PartialEq)]
+enum RelativeBaseline {
+    /// The median score of every platform.
+    #[default]
+    Median,
+    /// The mean score of every platform.
+    Mean,
+    /// The best score of any platform, so every relative score is zero or worse.
+    Best,
+    /// The score of one benchmark platform, which must be linked to every group scored.
+    Platform(String),
+}
+impl RelativeBaseline {
+    /// Parse a baseline from the query, where anything other than the named options is
+    /// taken as a platform name.
+    fn parse(value: &str) -> RelativeBaseline {
+        match value {
+            "median" => RelativeBaseline::Median,
+            "mean" => RelativeBaseline::Mean,
+            "best" => RelativeBaseline::Best,
+            platform => RelativeBaseline::Platform(platform.to_string()),
+        }
+    }
+
+    /// Get the name of the relative score measured against this baseline.
+    /// The median is the default, so it keeps the plain name.
+    fn get_score_type(&self) -> String {
+        match self {
+            RelativeBaseline::Median => "relative_brier".to_string(),
+            RelativeBaseline::Mean => "relative_brier_vs_mean".to_string(),
+            RelativeBaseline::Best => "relative_brier_vs_best".to_string(),
+            RelativeBaseline::Platform(platform) => format!("relative_brier_vs_{platform}"),
+        }
+    }
+
+    /// Get the baseline score on a date from every platform's scores.
+    /// The baseline forecasters are left out so they don't move it.
+    fn get_score(
+        &self,
+        score_data: &HashMap<PlatformKey, HashMap<DateKey, f32>>,
+        date: &DateKey,
+    ) -> Result<f32, ApiError> {
+        let mut scores: Vec<f32> = score_data
+            .iter()
+            .filter(|(platform, _)| !is_baseline(platform))
+            .flat_map(|(_, date_map)| date_map.get(date))
+            .copied()
+            .collect();
+        match self {
+            RelativeBaseline::Median => float_median(&mut scores),
+            RelativeBaseline::Mean => Ok(scores.iter().sum::<f32>() / scores.len() as f32),
+            RelativeBaseline::Best => Ok(scores.iter().copied().fold(f32::INFINITY, f32::min)),
+            RelativeBaseline::Platform(platform) => {
+                get_score_from_nested_map(score_data, platform, date)
+            }
+        }
+    }
+}
+
+/// Options that change how each group is scored.
+#[derive(Debug, Clone)]
+struct ScoringOptions {
+    /// Also compute relative scores for each third of the dates.
+    time_slices: bool,
+    /// Weight given to days where a market had no activity when averaging relative scores.
+    inactive_day_weight: f32,
+    /// Also score the baseline forecasters.
+    baselines: bool,
+    relative_baseline: RelativeBaseline,
+    resolution_policy: ResolutionPolicy,
+}
+impl Default for ScoringOptions {
+    fn default() -> ScoringOptions {
+        ScoringOptions {
+            time_slices: false,
+            inactive_day_weight: 1.0,
+            baselines: false,
+            relative_baseline: RelativeBaseline::Median,
+            resolution_policy: ResolutionPolicy::Own,
+        }
+    }
+}
 
 /// Structure for deserialization from config file.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct InputMarketData {
     platform: String,
     platform_id: String,
@@ -13,30 +167,80 @@ struct InputMarketData {
 }
 
 /// Structure for deserialization from config file.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct InputGroupData {
     title: String,
     category: String,
+    /// Override the region inferred from the markets.
+    region: Option<String>,
+    /// Only score dates on or after this day (YYYY-MM-DD).
+    start_date: Option<DateKey>,
+    /// Only score dates on or before this day (YYYY-MM-DD).
+    end_date: Option<DateKey>,
     markets: Vec<InputMarketData>,
 }
 
+/// A range of dates used to score a group, inclusive on both ends.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct GroupWindow {
+    start_date: DateKey,
+    end_date: DateKey,
+}
+
 /// Structure for serialization for response.
 #[derive(Serialize, Debug, Clone)]
 struct ResponseMarketData {
     market_data: Market,
     platform: String,
+    /// Whether this market asks the inverse of the group question.
+    invert: bool,
     absolute_brier: f32,
     relative_brier: f32,
+    /// The relative_brier over the first third of the dates, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relative_brier_early: Option<f32>,
+    /// The relative_brier over the middle third of the dates, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relative_brier_middle: Option<f32>,
+    /// The relative_brier over the last third of the dates, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relative_brier_late: Option<f32>,
 }
 
 /// Structure for serialization for response.
 #[derive(Serialize, Debug, Clone)]
 struct ResponseGroupData {
+    /// The group's key, see `get_group_key`.
+    group_key: String,
     group_title: String,
     category: String,
+    /// The region from the config file, or the most common region among the markets.
+    region: String,
+    /// The scoring window overrides from the config file, if set.
+    window_override: Option<GroupWindow>,
+    /// A proposed scoring window for review, if no override is set and the markets differ.
+    window_suggestion: Option<GroupWindow>,
+    /// How hard the question was to forecast: the median Brier score across platforms,
+    /// averaged over each scored day. Higher is harder.
+    difficulty: f32,
+    /// Markets that resolved differently from most of the group, and whether they were scored.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    resolution_disagreements: Vec<ResolutionDisagreement>,
     markets: Vec<ResponseMarketData>,
 }
 
+/// A market that resolved on the other side of the group question from most of the others.
+#[derive(Serialize, Debug, Clone)]
+struct ResolutionDisagreement {
+    platform: String,
+    /// The market's resolution, flipped if the market is inverted.
+    resolution: f32,
+    /// The resolution most markets in the group agreed on.
+    consensus: f32,
+    /// Whether the market was left out of the scores.
+    excluded: bool,
+}
+
 /// Structure for serialization for response.
 #[derive(Serialize, Debug)]
 struct ResponsePlatformStats {
@@ -46,18 +250,115 @@ struct ResponsePlatformStats {
     platform_absolute_brier: Option<f32>,
     /// The mean relative_brier of all markets in sample.
     platform_relative_brier: Option<f32>,
+    /// The expected absolute_brier on a question of average difficulty, after controlling
+    /// for how hard the questions this platform took on were.
+    platform_adjusted_brier: Option<f32>,
+    /// The mean relative_brier_early of all markets in sample, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform_relative_brier_early: Option<f32>,
+    /// The mean relative_brier_middle of all markets in sample, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform_relative_brier_middle: Option<f32>,
+    /// The mean relative_brier_late of all markets in sample, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform_relative_brier_late: Option<f32>,
     /// The percent of groups in the sample where this platform is represented.
     platform_sample_presence: f32,
+    /// The number of markets from this platform in the sample.
+    platform_sample_size: usize,
+    /// Whether the sample is large enough to compare against other platforms.
+    platform_sample_sufficient: bool,
+    /// The platform_absolute_brier pulled toward the platform's overall mean, more so for small samples.
+    platform_absolute_brier_shrunk: Option<f32>,
+    /// The platform_relative_brier pulled toward the platform's overall mean, more so for small samples.
+    platform_relative_brier_shrunk: Option<f32>,
+}
+
+/// Structure for serialization for response (one pair of platforms).
+#[derive(Serialize, Debug)]
+struct ResponseHeadToHead {
+    platform: String,
+    opponent: String,
+    category: String,
+    /// The number of groups where both platforms had a market.
+    shared_groups: usize,
+    /// Groups where this platform had the lower relative_brier.
+    wins: usize,
+    losses: usize,
+    ties: usize,
+    /// The share of shared groups this platform won, counting ties as half.
+    win_rate: f32,
 }
 
 /// Structure for serialization for response (top-level).
 #[derive(Serialize, Debug)]
 struct FullResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    /// The name of the relative score, which shows what it was measured against.
+    relative_score_type: String,
     platform_metadata: Vec<Platform>,
     platform_stats: Vec<ResponsePlatformStats>,
+    /// How often each platform beat each other platform on the same question.
+    head_to_head: Vec<ResponseHeadToHead>,
     groups: Vec<ResponseGroupData>,
 }
 
+/// Structure for serialization for response (single group).
+#[derive(Serialize, Debug)]
+struct GroupMarketsResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    /// The name of the relative score, which shows what it was measured against.
+    relative_score_type: String,
+    platform_metadata: Vec<Platform>,
+    group: ResponseGroupData,
+}
+
+/// A single probability from an uploaded forecast file.
+/// Each row is scored against a group by `group_key`, or a single market by `platform` and `platform_id`.
+#[derive(Debug, Deserialize)]
+pub struct ForecastRow {
+    group_key: Option<String>,
+    platform: Option<String>,
+    platform_id: Option<String>,
+    /// The day the forecast was made (YYYY-MM-DD).
+    date: String,
+    probability: f32,
+}
+
+/// The group or market that a set of uploaded forecasts is scored against.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ForecastTarget {
+    group_key: Option<String>,
+    platform: Option<String>,
+    platform_id: Option<String>,
+}
+
+/// Structure for serialization for response (single forecast target).
+#[derive(Serialize, Debug)]
+struct ForecastTargetResponse {
+    #[serde(flatten)]
+    target: ForecastTarget,
+    group_title: String,
+    category: String,
+    /// The linked markets along with the uploaded forecasts, scored together.
+    markets: Vec<ResponseMarketData>,
+}
+
+/// Structure for serialization for response (uploaded forecasts).
+#[derive(Serialize, Debug)]
+struct ForecastScoreResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    /// The name of the relative score, which shows what it was measured against.
+    relative_score_type: String,
+    /// Every platform and the uploaded forecasts, from lowest to highest mean relative_brier.
+    ranking: Vec<PlatformKey>,
+    platform_stats: Vec<ResponsePlatformStats>,
+    targets: Vec<ForecastTargetResponse>,
+}
+
 /// Gets a list of all dates where 2 or more markets were open.
 /// Used to calculate the absolute Brier score.
 /// Panics of the database is not well-formed.
@@ -104,8 +405,112 @@ fn get_dates_for_relative_scoring(markets: &HashMap<String, Market>) -> Vec<Date
     date_vec
 }
 
+/// Gets the day (YYYY-MM-DD) from a date key, which is a timestamp at the start of the day.
+fn get_day(date: &DateKey) -> String {
+    date.get(..10).unwrap_or(date).to_string()
+}
+
+/// Gets the first date where a market's probability moved from its opening value.
+/// Days before this are treated as a dead period with no real trading.
+fn get_first_active_date(market: &Market) -> Option<DateKey> {
+    let mut dates: Vec<(&String, f64)> = market
+        .prob_each_date
+        .as_object()?
+        .iter()
+        .filter_map(|(date, prob)| prob.as_f64().map(|p| (date, p)))
+        .collect();
+    dates.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    let (first_date, first_prob) = dates.first()?;
+    let first_active = dates
+        .iter()
+        .find(|(_, prob)| prob != first_prob)
+        .map(|(date, _)| *date)
+        .unwrap_or(first_date);
+    Some(first_active.to_owned())
+}
+
+/// Gets the last date a market has a probability for.
+fn get_last_date(market: &Market) -> Option<DateKey> {
+    market.prob_each_date.as_object()?.keys().max().cloned()
+}
+
+/// Propose a scoring window for a group where every market is open and actively traded.
+/// Returns None if the markets already cover the same dates or do not overlap at all.
+fn suggest_group_window(markets: &HashMap<PlatformKey, Market>) -> Option<GroupWindow> {
+    let first_dates: Vec<DateKey> = markets.values().filter_map(get_first_active_date).collect();
+    let last_dates: Vec<DateKey> = markets.values().filter_map(get_last_date).collect();
+    let start_date = get_day(first_dates.iter().max()?);
+    let end_date = get_day(last_dates.iter().min()?);
+    if start_date > end_date {
+        return None;
+    }
+
+    // only suggest a window if the markets opened far enough apart to matter
+    let earliest_open = markets.values().map(|m| m.open_dt).min()?;
+    let latest_open = markets.values().map(|m| m.open_dt).max()?;
+    if (latest_open - earliest_open).num_days() < WINDOW_SUGGESTION_MIN_DAYS {
+        return None;
+    }
+    Some(GroupWindow {
+        start_date,
+        end_date,
+    })
+}
+
+/// Get the scoring window set in the config file, filling any missing end with the widest bound.
+fn get_window_override(group: &InputGroupData) -> Option<GroupWindow> {
+    match (&group.start_date, &group.end_date) {
+        (None, None) => None,
+        (start_date, end_date) => Some(GroupWindow {
+            start_date: start_date.clone().unwrap_or("0000-01-01".to_string()),
+            end_date: end_date.clone().unwrap_or("9999-12-31".to_string()),
+        }),
+    }
+}
+
+/// Get the region of a group from the config file, otherwise the most common
+/// specific region among its markets, otherwise `Global`.
+fn get_group_region(group: &InputGroupData, markets: &HashMap<PlatformKey, Market>) -> String {
+    if let Some(region) = &group.region {
+        return region.clone();
+    }
+    let mut counts: BTreeMap<&String, usize> = BTreeMap::new();
+    for market in markets.values().filter(|m| m.region != "Global") {
+        *counts.entry(&market.region).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(region, _)| region.clone())
+        .unwrap_or("Global".to_string())
+}
+
+/// Test if a group passes the region filters in the query.
+fn is_group_in_region(query: &GroupQueryParams, group: &ResponseGroupData) -> bool {
+    query
+        .region_select
+        .iter()
+        .all(|region| &group.region == region)
+        && query
+            .region_exclude
+            .iter()
+            .all(|region| &group.region != region)
+}
+
+/// Test if a group passes the difficulty filters in the query.
+fn is_group_in_difficulty_range(query: &GroupQueryParams, group: &ResponseGroupData) -> bool {
+    query
+        .difficulty_min
+        .iter()
+        .all(|min| group.difficulty >= *min)
+        && query
+            .difficulty_max
+            .iter()
+            .all(|max| group.difficulty <= *max)
+}
+
 /// Extract the unique platform names from a list of groups.
-fn get_unique_platforms_from_groups(groups: &Vec<ResponseGroupData>) -> Vec<PlatformKey> {
+fn get_unique_platforms_from_groups(groups: &[ResponseGroupData]) -> Vec<PlatformKey> {
     let mut set: HashSet<String> = HashSet::new();
     for group in groups {
         for market in &group.markets {
@@ -216,8 +621,58 @@ fn get_average_score_from_map(
     }
 }
 
+/// Get the weight of a day for scoring, based on whether the market had any activity.
+/// Markets fetched before activity was tracked count every day as active.
+fn get_day_weight(market: &Market, date: &DateKey, inactive_day_weight: f32) -> f32 {
+    match market.active_each_date.get(date).and_then(|a| a.as_bool()) {
+        Some(false) => inactive_day_weight,
+        _ => 1.0,
+    }
+}
+
+/// Get the weighted average score from a map given the platform name, only counting the given dates.
+/// Returns None if the platform has no weighted scores on any of those dates.
+fn get_average_score_over_dates(
+    score_data: &HashMap<PlatformKey, HashMap<DateKey, f32>>,
+    platform: &PlatformKey,
+    dates: &[DateKey],
+    weight: impl Fn(&DateKey) -> f32,
+) -> Option<f32> {
+    let date_map = score_data.get(platform)?;
+    let (weighted_sum, total_weight) = dates
+        .iter()
+        .filter_map(|date| date_map.get(date).map(|score| (score, weight(date))))
+        .fold((0.0, 0.0), |(sum, total), (score, w)| {
+            (sum + score * w, total + w)
+        });
+    match total_weight {
+        0.0 => None,
+        _ => Some(weighted_sum / total_weight),
+    }
+}
+
+/// Split a list of dates into the first, middle, and last third in chronological order.
+/// Dates are formatted as YYYY-MM-DD so they sort correctly as strings.
+fn split_dates_into_thirds(dates: &[DateKey]) -> [Vec<DateKey>; 3] {
+    let mut sorted = dates.to_vec();
+    sorted.sort_unstable();
+    let len = sorted.len();
+    let late = sorted.split_off(len * 2 / 3);
+    let middle = sorted.split_off(len / 3);
+    [sorted, middle, late]
+}
+
+/// Combine a list of optional scores, each paired with its weight, ignoring any that are missing.
+fn aggregate_present(scores: &[(Option<f32>, f32)], aggregation: Aggregation) -> Option<f32> {
+    let present: Vec<(f32, f32)> = scores
+        .iter()
+        .filter_map(|(score, weight)| score.map(|score| (score, *weight)))
+        .collect();
+    aggregate_scores(&present, aggregation)
+}
+
 /// Get the median from a list of floats.
-fn float_median(numbers: &mut Vec<f32>) -> Result<f32, ApiError> {
+fn float_median(numbers: &mut [f32]) -> Result<f32, ApiError> {
     numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
     let len = numbers.len();
     match len {
@@ -226,7 +681,7 @@ fn float_median(numbers: &mut Vec<f32>) -> Result<f32, ApiError> {
             message: "Generated Brier scores list was empty".to_string(),
         }),
         _ => {
-            if len % 2 == 0 {
+            if len.is_multiple_of(2) {
                 let mid = len / 2;
                 Ok((numbers[mid - 1] + numbers[mid]) / 2.0)
             } else {
@@ -236,27 +691,100 @@ fn float_median(numbers: &mut Vec<f32>) -> Result<f32, ApiError> {
     }
 }
 
+/// Fit a two-way additive model where each market's absolute_brier is the overall mean
+/// plus a platform effect plus a question effect, by alternating means.
+/// Returns the overall mean plus each platform's effect, which is its expected score on a
+/// question of average difficulty. Unlike a raw average this doesn't penalize platforms
+/// for taking on harder questions.
+fn get_difficulty_adjusted_scores(groups: &[ResponseGroupData]) -> HashMap<PlatformKey, f32> {
+    let scores: Vec<(usize, &PlatformKey, f32)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(index, group)| {
+            group
+                .markets
+                .iter()
+                .map(move |market| (index, &market.platform, market.absolute_brier))
+        })
+        .collect();
+    if scores.is_empty() {
+        return HashMap::new();
+    }
+    let overall = scores.iter().map(|(_, _, score)| score).sum::<f32>() / scores.len() as f32;
+
+    let mut platform_effects: HashMap<&PlatformKey, f32> = HashMap::new();
+    let mut group_effects: Vec<f32> = vec![0.0; groups.len()];
+    for _ in 0..ADJUSTMENT_ITERATIONS {
+        // mean residual of each question after removing the platform effects
+        let mut group_sums: Vec<(f32, usize)> = vec![(0.0, 0); groups.len()];
+        for (index, platform, score) in &scores {
+            let residual = score - overall - platform_effects.get(platform).unwrap_or(&0.0);
+            group_sums[*index].0 += residual;
+            group_sums[*index].1 += 1;
+        }
+        for (effect, (sum, count)) in group_effects.iter_mut().zip(group_sums) {
+            if count > 0 {
+                *effect = sum / count as f32;
+            }
+        }
+        // mean residual of each platform after removing the question effects
+        let mut platform_sums: HashMap<&PlatformKey, (f32, usize)> = HashMap::new();
+        for (index, platform, score) in &scores {
+            let entry = platform_sums.entry(platform).or_default();
+            entry.0 += score - overall - group_effects[*index];
+            entry.1 += 1;
+        }
+        platform_effects = platform_sums
+            .into_iter()
+            .map(|(platform, (sum, count))| (platform, sum / count as f32))
+            .collect();
+    }
+
+    platform_effects
+        .into_iter()
+        .map(|(platform, effect)| (platform.clone(), overall + effect))
+        .collect()
+}
+
 /// Aggregate data from a list of groups.
 /// The result is a list where each item represents all markets in a platform.
+/// Each market's scores are weighted by `weight_attribute`, except on platforms where every
+/// market has no weight (such as volume on a platform without trading), which stay unweighted.
+/// The absolute and relative scores are then combined with their own aggregation.
 fn get_platform_aggregate_stats(
-    groups: &Vec<ResponseGroupData>,
+    groups: &[ResponseGroupData],
     category: String,
+    weight_attribute: &WeightAttribute,
+    absolute_aggregation: Aggregation,
+    relative_aggregation: Aggregation,
 ) -> Vec<ResponsePlatformStats> {
     // filter out the groups we want
     let category_groups: Vec<ResponseGroupData> = match category.as_str() {
-        "All" => groups.clone(),
+        "All" => groups.to_vec(),
         _ => groups
-            .clone()
-            .into_iter()
+            .iter()
             .filter(|g| g.category == category)
+            .cloned()
             .collect(),
     };
     let total_count = category_groups.len();
+    let adjusted_scores = get_difficulty_adjusted_scores(&category_groups);
+
+    // platforms where at least one market can be weighted
+    let weighted_platforms: HashSet<String> = category_groups
+        .iter()
+        .flat_map(|group| &group.markets)
+        .filter(|market| weight_attribute.get_weight(&market.market_data) > 0.0)
+        .map(|market| market.platform.clone())
+        .collect();
 
     // set up the counters
     struct PlatformStatsIntermediate {
-        cumulative_absolute_brier: f32,
-        cumulative_relative_brier: f32,
+        absolute_brier: Vec<(f32, f32)>,
+        relative_brier: Vec<(f32, f32)>,
+        relative_brier_early: Vec<(Option<f32>, f32)>,
+        relative_brier_middle: Vec<(Option<f32>, f32)>,
+        relative_brier_late: Vec<(Option<f32>, f32)>,
         count: usize,
     }
     let mut platform_stat_intermediates: HashMap<String, PlatformStatsIntermediate> =
@@ -264,149 +792,1501 @@ fn get_platform_aggregate_stats(
     for group in category_groups {
         for market in group.markets {
             let platform_name = market.platform.clone();
+            let weight = match weighted_platforms.contains(&platform_name) {
+                true => weight_attribute.get_weight(&market.market_data).max(0.0),
+                false => 1.0,
+            };
             // add new counter or update existing
             match platform_stat_intermediates.get_mut(&platform_name) {
                 None => {
                     platform_stat_intermediates.insert(
                         platform_name,
                         PlatformStatsIntermediate {
-                            cumulative_absolute_brier: market.absolute_brier,
-                            cumulative_relative_brier: market.relative_brier,
+                            absolute_brier: Vec::from([(market.absolute_brier, weight)]),
+                            relative_brier: Vec::from([(market.relative_brier, weight)]),
+                            relative_brier_early: Vec::from([(
+                                market.relative_brier_early,
+                                weight,
+                            )]),
+                            relative_brier_middle: Vec::from([(
+                                market.relative_brier_middle,
+                                weight,
+                            )]),
+                            relative_brier_late: Vec::from([(market.relative_brier_late, weight)]),
                             count: 1,
                         },
                     );
                 }
                 Some(psi) => {
-                    psi.cumulative_absolute_brier += market.absolute_brier;
-                    psi.cumulative_relative_brier += market.relative_brier;
+                    psi.absolute_brier.push((market.absolute_brier, weight));
+                    psi.relative_brier.push((market.relative_brier, weight));
+                    psi.relative_brier_early
+                        .push((market.relative_brier_early, weight));
+                    psi.relative_brier_middle
+                        .push((market.relative_brier_middle, weight));
+                    psi.relative_brier_late
+                        .push((market.relative_brier_late, weight));
                     psi.count += 1;
                 }
             }
         }
     }
 
-    // divide out into averages
+    // combine into a single score for each platform
     let mut platform_stats = Vec::new();
     for (platform_name, psi) in platform_stat_intermediates {
+        let platform_absolute_brier = aggregate_scores(&psi.absolute_brier, absolute_aggregation);
+        let platform_relative_brier = aggregate_scores(&psi.relative_brier, relative_aggregation);
+        let platform_adjusted_brier = adjusted_scores.get(&platform_name).copied();
         platform_stats.push(ResponsePlatformStats {
             platform: platform_name,
             category: category.clone(),
             // TODO: set scores to none if presence < 10%
-            platform_absolute_brier: Some(psi.cumulative_absolute_brier / psi.count as f32),
-            platform_relative_brier: Some(psi.cumulative_relative_brier / psi.count as f32),
+            platform_absolute_brier,
+            platform_relative_brier,
+            platform_adjusted_brier,
+            platform_relative_brier_early: aggregate_present(
+                &psi.relative_brier_early,
+                relative_aggregation,
+            ),
+            platform_relative_brier_middle: aggregate_present(
+                &psi.relative_brier_middle,
+                relative_aggregation,
+            ),
+            platform_relative_brier_late: aggregate_present(
+                &psi.relative_brier_late,
+                relative_aggregation,
+            ),
             platform_sample_presence: psi.count as f32 / total_count as f32,
+            platform_sample_size: psi.count,
+            platform_sample_sufficient: psi.count >= MIN_CATEGORY_SAMPLE_SIZE,
+            // these are shrunk later if this is one category out of many
+            platform_absolute_brier_shrunk: platform_absolute_brier,
+            platform_relative_brier_shrunk: platform_relative_brier,
         })
     }
     platform_stats
 }
 
+/// Get each platform's score in a group, sorted by platform.
+/// A platform with several markets in a group is scored by their mean relative_brier.
+fn get_group_platform_scores(group: &ResponseGroupData) -> Vec<(&String, f32)> {
+    let mut scores: BTreeMap<&String, (f32, usize)> = BTreeMap::new();
+    for market in &group.markets {
+        let entry = scores.entry(&market.platform).or_default();
+        entry.0 += market.relative_brier;
+        entry.1 += 1;
+    }
+    scores
+        .into_iter()
+        .map(|(platform, (sum, count))| (platform, sum / count as f32))
+        .collect()
+}
+
+/// Compare every pair of platforms on each group they both had a market in.
+/// Both directions of each pair are returned, sorted by platform then opponent.
+fn get_head_to_head(groups: &[ResponseGroupData], category: &str) -> Vec<ResponseHeadToHead> {
+    // wins, losses, and ties for each platform against each opponent
+    let mut records: BTreeMap<(&String, &String), (usize, usize, usize)> = BTreeMap::new();
+    for group in groups {
+        if category != "All" && group.category != category {
+            continue;
+        }
+        let scores = get_group_platform_scores(group);
+        for (platform, score) in &scores {
+            for (opponent, opponent_score) in &scores {
+                if platform == opponent {
+                    continue;
+                }
+                let record = records.entry((platform, opponent)).or_default();
+                match score.partial_cmp(opponent_score) {
+                    Some(std::cmp::Ordering::Less) => record.0 += 1,
+                    Some(std::cmp::Ordering::Greater) => record.1 += 1,
+                    _ => record.2 += 1,
+                }
+            }
+        }
+    }
+
+    records
+        .into_iter()
+        .map(|((platform, opponent), (wins, losses, ties))| {
+            let shared_groups = wins + losses + ties;
+            ResponseHeadToHead {
+                platform: platform.clone(),
+                opponent: opponent.clone(),
+                category: category.to_string(),
+                shared_groups,
+                wins,
+                losses,
+                ties,
+                win_rate: (wins as f32 + ties as f32 / 2.0) / shared_groups as f32,
+            }
+        })
+        .collect()
+}
+
+/// Get a key for a group that stays the same when groups are added, removed, or reordered in
+/// the mapping file, unlike its index. This is its first market by platform and ID, written as
+/// `platform/platform_id`, since a market should only be in one group.
+fn get_group_key(group: &InputGroupData) -> String {
+    group
+        .markets
+        .iter()
+        .map(|market| format!("{}/{}", market.platform, market.platform_id))
+        .min()
+        .unwrap_or_default()
+}
+
+/// Find a group in the mapping file by its key, see `get_group_key`.
+fn find_group_by_key(
+    config_file_groups: &[InputGroupData],
+    group_key: &str,
+) -> Result<InputGroupData, ApiError> {
+    config_file_groups
+        .iter()
+        .find(|group| get_group_key(group) == group_key)
+        .cloned()
+        .ok_or(ApiError::new(
+            404,
+            format!("no group found with key {group_key}"),
+        ))
+}
+
+/// The outcome of one linked question, used to rate platforms against each other.
+#[derive(Debug)]
+pub struct GroupResult {
+    /// The group's key, see `get_group_key`.
+    pub group_key: String,
+    pub category: String,
+    /// When the last market in the group closed.
+    pub close_dt: DateTime<Utc>,
+    /// Each platform's mean relative_brier in the group, sorted by platform.
+    pub platform_scores: Vec<(String, f32)>,
+}
+
+/// Score every group in the mapping file with the default options, oldest first.
+/// Groups without any markets are left out.
+pub fn get_group_results(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<GroupResult>, ApiError> {
+    let mut results = Vec::new();
+    for group in load_groups_from_file()? {
+        let group = score_group(conn, group, &ScoringOptions::default())?;
+        let Some(close_dt) = group.markets.iter().map(|m| m.market_data.close_dt).max() else {
+            continue;
+        };
+        results.push(GroupResult {
+            group_key: group.group_key.clone(),
+            category: group.category.clone(),
+            close_dt,
+            platform_scores: get_group_platform_scores(&group)
+                .into_iter()
+                .map(|(platform, score)| (platform.clone(), score))
+                .collect(),
+        });
+    }
+    results.sort_by_key(|result| result.close_dt);
+    Ok(results)
+}
+
+/// Pull a score toward a prior mean, weighting the prior as if it were a fixed number of samples.
+fn shrink_toward(score: Option<f32>, sample_size: usize, prior: Option<f32>) -> Option<f32> {
+    match (score, prior) {
+        (Some(score), Some(prior)) => Some(
+            (score * sample_size as f32 + prior * SHRINKAGE_PRIOR_WEIGHT)
+                / (sample_size as f32 + SHRINKAGE_PRIOR_WEIGHT),
+        ),
+        (score, _) => score,
+    }
+}
+
+/// Shrink each platform's category scores toward that platform's scores across all categories,
+/// so a category with only a few markets doesn't look more certain than it is.
+fn shrink_category_stats(
+    category_stats: &mut [ResponsePlatformStats],
+    overall_stats: &[ResponsePlatformStats],
+) {
+    for stats in category_stats {
+        let Some(overall) = overall_stats.iter().find(|o| o.platform == stats.platform) else {
+            continue;
+        };
+        stats.platform_absolute_brier_shrunk = shrink_toward(
+            stats.platform_absolute_brier,
+            stats.platform_sample_size,
+            overall.platform_absolute_brier,
+        );
+        stats.platform_relative_brier_shrunk = shrink_toward(
+            stats.platform_relative_brier,
+            stats.platform_sample_size,
+            overall.platform_relative_brier,
+        );
+    }
+}
+
+/// Load all group definitions from the group mapping file.
+fn load_groups_from_file() -> Result<Vec<InputGroupData>, ApiError> {
+    let config_file = File::open("groups.yaml")
+        .map_err(|e| ApiError::new(500, format!("failed to load config file: {e}")))?;
+    serde_yaml::from_reader(config_file)
+        .map_err(|e| ApiError::new(500, format!("failed to parse config file: {e}")))
+}
+
+/// Get the market data for each market in a group from the database, keyed by platform.
+/// Also returns whether each market asks the inverse of the group question.
+fn load_group_markets(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    group: &InputGroupData,
+) -> Result<GroupMarkets, ApiError> {
+    let mut markets_by_platform: HashMap<PlatformKey, Market> =
+        HashMap::with_capacity(group.markets.len());
+    let mut invert_by_platform: HashMap<PlatformKey, bool> =
+        HashMap::with_capacity(group.markets.len());
+    for market in &group.markets {
+        let market_data = get_market_by_platform_id(conn, &market.platform, &market.platform_id)?;
+        invert_by_platform.insert(market.platform.clone(), market.invert.unwrap_or(false));
+        markets_by_platform.insert(market.platform.clone(), market_data);
+    }
+    Ok((markets_by_platform, invert_by_platform))
+}
+
+/// Grab the relevant markets for a group and get their brier scores over time.
+/// Also compare their scores to see which platforms were more accurate over time.
+/// If `time_slices` is set, relative scores are also computed for each third of the dates.
+/// If `baselines` is set, the baseline forecasters are scored alongside the markets.
+fn score_group(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    group: InputGroupData,
+    options: &ScoringOptions,
+) -> Result<ResponseGroupData, ApiError> {
+    let (markets_by_platform, invert_by_platform) = load_group_markets(conn, &group)?;
+    score_group_markets(group, markets_by_platform, invert_by_platform, options)
+}
+
+/// Get the brier scores over time for markets that have already been loaded.
+fn score_group_markets(
+    group: InputGroupData,
+    mut markets_by_platform: HashMap<PlatformKey, Market>,
+    invert_by_platform: HashMap<PlatformKey, bool>,
+    options: &ScoringOptions,
+) -> Result<ResponseGroupData, ApiError> {
+    let group_key = get_group_key(&group);
+    if let RelativeBaseline::Platform(platform) = &options.relative_baseline {
+        if !has_relative_baseline(&group, &options.relative_baseline) {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "group {group_key} has no {platform} market to use as the relative baseline"
+                ),
+            ));
+        }
+    }
+    let window_override = get_window_override(&group);
+    let region = get_group_region(&group, &markets_by_platform);
+
+    // restrict scoring to the override window, otherwise suggest one for review
+    let window_suggestion = match window_override {
+        Some(_) => None,
+        None => suggest_group_window(&markets_by_platform),
+    };
+    let in_window = |date: &DateKey| match &window_override {
+        Some(window) => get_day(date) >= window.start_date && get_day(date) <= window.end_date,
+        None => true,
+    };
+
+    // markets that resolved against the rest of the group can be left out instead
+    let resolution_disagreements = find_resolution_disagreements(
+        &markets_by_platform,
+        &invert_by_platform,
+        options.resolution_policy,
+    );
+    for disagreement in &resolution_disagreements {
+        if disagreement.excluded {
+            markets_by_platform.remove(&disagreement.platform);
+        }
+    }
+    if let RelativeBaseline::Platform(platform) = &options.relative_baseline {
+        if !markets_by_platform.contains_key(platform) {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "group {group_key}'s {platform} market resolved against the group and was excluded, so it can't be the relative baseline"
+                ),
+            ));
+        }
+    }
+
+    // get the dates to score from the real markets only
+    let mut dates_for_absolute_scoring = get_dates_for_absolute_scoring(&markets_by_platform);
+    dates_for_absolute_scoring.retain(in_window);
+    let mut dates_for_relative_scoring = get_dates_for_relative_scoring(&markets_by_platform);
+    dates_for_relative_scoring.retain(in_window);
+    if options.baselines {
+        markets_by_platform.extend(build_baseline_markets(
+            &markets_by_platform,
+            &invert_by_platform,
+            &dates_for_absolute_scoring,
+        )?);
+    }
+
+    // get absolute brier per day on each market
+    let mut absolute_score_data: HashMap<PlatformKey, HashMap<DateKey, f32>> = HashMap::new();
+    for (platform, market) in &markets_by_platform {
+        for date in &dates_for_absolute_scoring {
+            // calculate brier for the day
+            let resolution = market.resolution;
+            let prediction = get_prob_on_date_from_market(market, date)?;
+            let absolute_brier = (resolution - prediction).powi(2);
+            // save it to map
+            save_score_to_nested_map(&mut absolute_score_data, platform, date, absolute_brier)?;
+        }
+    }
+
+    // get the baseline brier per day for the relative scores
+    let mut relative_baselines: HashMap<&DateKey, f32> = HashMap::new();
+    for date in &dates_for_relative_scoring {
+        relative_baselines.insert(
+            date,
+            options
+                .relative_baseline
+                .get_score(&absolute_score_data, date)?,
+        );
+    }
+
+    // get median brier per day for the difficulty, leaving out the baselines so they don't move it
+    for date in &dates_for_absolute_scoring {
+        let mut brier_scores: Vec<f32> = absolute_score_data
+            .iter()
+            .filter(|(platform, _)| !is_baseline(platform))
+            .flat_map(|(_, date_map)| date_map.get(date))
+            .copied()
+            .collect();
+        let median_brier = float_median(&mut brier_scores)?;
+        save_score_to_nested_map(
+            &mut absolute_score_data,
+            &"median".to_owned(),
+            date,
+            median_brier,
+        )?;
+    }
+
+    // get relative brier per day on each market
+    let mut relative_score_data: HashMap<PlatformKey, HashMap<DateKey, f32>> = HashMap::new();
+    for platform in markets_by_platform.keys() {
+        for date in &dates_for_relative_scoring {
+            // calculate relative brier for the day
+            let absolute = get_score_from_nested_map(&absolute_score_data, platform, date)?;
+            let relative_brier = absolute - relative_baselines[date];
+            // save it to map
+            save_score_to_nested_map(&mut relative_score_data, platform, date, relative_brier)?;
+        }
+    }
+
+    // split the relative dates so we can see who was more accurate early or late
+    let date_slices = match options.time_slices {
+        true => Some(split_dates_into_thirds(&dates_for_relative_scoring)),
+        false => None,
+    };
+    // stale probabilities on inactive days can be down-weighted in the relative averages
+    let get_relative_score = |platform: &PlatformKey, market: &Market, dates: &[DateKey]| {
+        get_average_score_over_dates(&relative_score_data, platform, dates, |date| {
+            get_day_weight(market, date, options.inactive_day_weight)
+        })
+    };
+
+    let mut markets_for_response = Vec::new();
+    for (platform, market) in markets_by_platform {
+        let get_slice_score = |slice: usize| {
+            date_slices
+                .as_ref()
+                .and_then(|slices| get_relative_score(&platform, &market, &slices[slice]))
+        };
+        // fall back to the unweighted average if every day was excluded
+        let relative_brier =
+            match get_relative_score(&platform, &market, &dates_for_relative_scoring) {
+                Some(score) => score,
+                None => get_average_score_from_map(&relative_score_data, &platform)?,
+            };
+        let relative_brier_early = get_slice_score(0);
+        let relative_brier_middle = get_slice_score(1);
+        let relative_brier_late = get_slice_score(2);
+        markets_for_response.push(ResponseMarketData {
+            market_data: market,
+            platform: platform.clone(),
+            invert: invert_by_platform.get(&platform).copied().unwrap_or(false),
+            absolute_brier: get_average_score_from_map(&absolute_score_data, &platform)?,
+            relative_brier,
+            relative_brier_early,
+            relative_brier_middle,
+            relative_brier_late,
+        })
+    }
+
+    Ok(ResponseGroupData {
+        group_key,
+        group_title: group.title,
+        category: group.category,
+        region,
+        window_override,
+        window_suggestion,
+        difficulty: get_average_score_from_map(&absolute_score_data, &"median".to_owned())?,
+        resolution_disagreements,
+        markets: markets_for_response,
+    })
+}
+
+/// Get the platform metadata for every platform represented in the groups.
+fn get_platform_metadata_for_groups(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    groups: &[ResponseGroupData],
+) -> Result<Vec<Platform>, ApiError> {
+    let platform_list = get_unique_platforms_from_groups(groups);
+    let mut platform_metadata = Vec::with_capacity(platform_list.len());
+    for platform in platform_list
+        .iter()
+        .filter(|platform| !is_baseline(platform))
+    {
+        platform_metadata.push(get_platform_by_name(conn, platform)?)
+    }
+    Ok(platform_metadata)
+}
+
+/// Get the inactive day weight from the query, making sure it is within bounds.
+fn get_inactive_day_weight(query: &GroupQueryParams) -> Result<f32, ApiError> {
+    match query.inactive_day_weight {
+        None => Ok(1.0),
+        Some(weight) if (0.0..=1.0).contains(&weight) => Ok(weight),
+        Some(weight) => Err(ApiError::new(
+            400,
+            format!("inactive_day_weight must be between 0 and 1, got {weight}"),
+        )),
+    }
+}
+
+/// Get the group scoring options from the query, checking that they're valid.
+fn get_scoring_options(query: &GroupQueryParams) -> Result<ScoringOptions, ApiError> {
+    Ok(ScoringOptions {
+        time_slices: query.time_slices.unwrap_or(false),
+        inactive_day_weight: get_inactive_day_weight(query)?,
+        baselines: query.baselines.unwrap_or(false),
+        relative_baseline: query
+            .relative_baseline
+            .as_deref()
+            .map(RelativeBaseline::parse)
+            .unwrap_or_default(),
+        resolution_policy: query.resolution_policy.unwrap_or_default(),
+    })
+}
+
+/// Check whether a group can be scored against the relative baseline.
+/// A benchmark platform can only be used on groups it's linked to.
+fn has_relative_baseline(group: &InputGroupData, baseline: &RelativeBaseline) -> bool {
+    match baseline {
+        RelativeBaseline::Platform(platform) => group
+            .markets
+            .iter()
+            .any(|market| &market.platform == platform),
+        _ => true,
+    }
+}
+
+/// Check whether the benchmark platform's market in a group would be left out by the
+/// resolution policy for resolving against the rest of the group.
+fn is_relative_baseline_excluded(
+    markets_by_platform: &HashMap<PlatformKey, Market>,
+    invert_by_platform: &HashMap<PlatformKey, bool>,
+    options: &ScoringOptions,
+) -> bool {
+    let RelativeBaseline::Platform(platform) = &options.relative_baseline else {
+        return false;
+    };
+    find_resolution_disagreements(
+        markets_by_platform,
+        invert_by_platform,
+        options.resolution_policy,
+    )
+    .iter()
+    .any(|disagreement| disagreement.excluded && &disagreement.platform == platform)
+}
+
 /// Take data from a group mapping file, grab the relevant markets, and get
 /// their brier scores over time. Also compare their scores to see which
 /// platforms were more accurate over time.
 pub fn build_group_comparison(
+    query: Query<GroupQueryParams>,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> Result<HttpResponse, ApiError> {
+    let options = get_scoring_options(&query)?;
+
     // load group data from the file
-    let config_file = File::open("groups.yaml")
-        .map_err(|e| ApiError::new(500, format!("failed to load config file: {e}")))?;
-    let config_file_groups: Vec<InputGroupData> = serde_yaml::from_reader(config_file)
-        .map_err(|e| ApiError::new(500, format!("failed to parse config file: {e}")))?;
+    let config_file_groups = load_groups_from_file()?;
 
-    // go through each group & constituent market
+    // go through each group & constituent market, skipping groups without the benchmark
     let mut groups = Vec::with_capacity(config_file_groups.len());
     for group in config_file_groups {
-        // get market data from db
-        let mut markets_by_platform: HashMap<String, Market> =
-            HashMap::with_capacity(group.markets.len());
-        for market in group.markets {
-            let market_data =
-                get_market_by_platform_id(conn, &market.platform, &market.platform_id)?;
-            markets_by_platform.insert(market.platform, market_data);
-        }
-
-        // get absolute brier per day on each market
-        let dates_for_absolute_scoring = get_dates_for_absolute_scoring(&markets_by_platform);
-        let mut absolute_score_data: HashMap<PlatformKey, HashMap<DateKey, f32>> = HashMap::new();
-        for (platform, market) in &markets_by_platform {
-            for date in &dates_for_absolute_scoring {
-                // calculate brier for the day
-                let resolution = market.resolution.clone();
-                let prediction = get_prob_on_date_from_market(&market, &date)?;
-                let absolute_brier = (resolution - prediction).powi(2);
-                // save it to map
-                save_score_to_nested_map(&mut absolute_score_data, platform, date, absolute_brier)?;
-            }
+        if !has_relative_baseline(&group, &options.relative_baseline) {
+            continue;
+        }
+        let (markets_by_platform, invert_by_platform) = load_group_markets(conn, &group)?;
+        if is_relative_baseline_excluded(&markets_by_platform, &invert_by_platform, &options) {
+            continue;
         }
+        groups.push(score_group_markets(
+            group,
+            markets_by_platform,
+            invert_by_platform,
+            &options,
+        )?);
+    }
+    if let RelativeBaseline::Platform(platform) = &options.relative_baseline {
+        if groups.is_empty() {
+            return Err(ApiError::new(
+                400,
+                format!("no groups have a {platform} market to use as the relative baseline"),
+            ));
+        }
+    }
+    groups.retain(|group| is_group_in_region(&query, group));
+    groups.retain(|group| is_group_in_difficulty_range(&query, group));
 
-        // get median brier per day
-        for date in &dates_for_absolute_scoring {
-            let mut brier_scores: Vec<f32> = absolute_score_data
-                .values()
-                .flat_map(|date_map| date_map.get(date))
-                .copied()
-                .collect();
-            let median_brier = float_median(&mut brier_scores)?;
-            save_score_to_nested_map(
-                &mut absolute_score_data,
-                &"median".to_owned(),
-                date,
-                median_brier,
-            )?;
-        }
-
-        // get relative brier per day on each market
-        let dates_for_relative_scoring = get_dates_for_relative_scoring(&markets_by_platform);
-        let mut relative_score_data: HashMap<PlatformKey, HashMap<DateKey, f32>> = HashMap::new();
-        for (platform, _) in &markets_by_platform {
-            for date in &dates_for_relative_scoring {
-                // calculate relative brier for the day
-                let absolute = get_score_from_nested_map(&absolute_score_data, platform, date)?;
-                let median =
-                    get_score_from_nested_map(&absolute_score_data, &"median".to_owned(), date)?;
-                let relative_brier = absolute - median;
-                // save it to map
-                save_score_to_nested_map(&mut relative_score_data, platform, date, relative_brier)?;
+    // get the platform metadata
+    let platform_metadata = get_platform_metadata_for_groups(conn, &groups)?;
+
+    // get the aggregate stats for all categories then each individual category
+    let category_list = get_unique_categories_from_groups(&groups);
+    let weight_attribute = query
+        .weight_attribute
+        .as_ref()
+        .unwrap_or(&WeightAttribute::None);
+    let absolute_aggregation = query.absolute_brier_aggregation.unwrap_or_default();
+    let relative_aggregation = query.relative_brier_aggregation.unwrap_or_default();
+    let mut platform_stats = get_platform_aggregate_stats(
+        &groups,
+        "All".to_string(),
+        weight_attribute,
+        absolute_aggregation,
+        relative_aggregation,
+    );
+    let mut category_stats: Vec<ResponsePlatformStats> = category_list
+        .iter()
+        .flat_map(|category| {
+            get_platform_aggregate_stats(
+                &groups,
+                category.clone(),
+                weight_attribute,
+                absolute_aggregation,
+                relative_aggregation,
+            )
+        })
+        .collect();
+    shrink_category_stats(&mut category_stats, &platform_stats);
+    platform_stats.extend(category_stats);
+
+    // compare each pair of platforms directly, overall and within each category
+    let mut head_to_head = get_head_to_head(&groups, "All");
+    for category in &category_list {
+        head_to_head.extend(get_head_to_head(&groups, category));
+    }
+
+    // save it all to the response struct & ship
+    let response = FullResponse {
+        schema_version: SCHEMA_VERSION,
+        relative_score_type: options.relative_baseline.get_score_type(),
+        platform_metadata,
+        platform_stats,
+        head_to_head,
+        groups,
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Take a single group from the group mapping file and return its linked markets
+/// along with their invert flags and per-market scores.
+pub fn build_group_markets(
+    group_key: &str,
+    query: Query<GroupQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    // load group data from the file and find the requested group
+    let group = find_group_by_key(&load_groups_from_file()?, group_key)?;
+
+    // score the group and get the platform metadata
+    let options = get_scoring_options(&query)?;
+    let group = score_group(conn, group, &options)?;
+    let platform_metadata = get_platform_metadata_for_groups(conn, std::slice::from_ref(&group))?;
+
+    let response = GroupMarketsResponse {
+        schema_version: SCHEMA_VERSION,
+        relative_score_type: options.relative_baseline.get_score_type(),
+        platform_metadata,
+        group,
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Parse an uploaded forecast file in CSV format.
+/// The first line must name the columns, which can be in any order.
+/// Quoted fields are not supported.
+fn parse_forecast_csv(body: &str) -> Result<Vec<ForecastRow>, ApiError> {
+    let mut lines = body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or(ApiError::new(400, "forecast file is empty".to_string()))?;
+    let header: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| header.iter().position(|h| *h == name);
+    let (Some(date_col), Some(probability_col)) = (column("date"), column("probability")) else {
+        return Err(ApiError::new(
+            400,
+            "forecast file must have date and probability columns".to_string(),
+        ));
+    };
+    let (group_key_col, platform_col, platform_id_col) = (
+        column("group_key"),
+        column("platform"),
+        column("platform_id"),
+    );
+
+    let mut rows = Vec::new();
+    for (line_index, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let get_field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c))
+                .filter(|field| !field.is_empty())
+                .map(|field| field.to_string())
+        };
+        let line_error =
+            |message: String| ApiError::new(400, format!("line {}: {message}", line_index + 1));
+        let date = get_field(Some(date_col)).ok_or(line_error("missing date".to_string()))?;
+        let probability = get_field(Some(probability_col))
+            .ok_or(line_error("missing probability".to_string()))?
+            .parse::<f32>()
+            .map_err(|e| line_error(format!("invalid probability: {e}")))?;
+        rows.push(ForecastRow {
+            group_key: get_field(group_key_col),
+            platform: get_field(platform_col),
+            platform_id: get_field(platform_id_col),
+            date,
+            probability,
+        });
+    }
+    Ok(rows)
+}
+
+/// Sort uploaded forecasts by the group or market they are scored against.
+/// Errors if a row is out of bounds, has no target, or repeats a day for the same target.
+fn group_forecasts_by_target(
+    rows: Vec<ForecastRow>,
+) -> Result<BTreeMap<ForecastTarget, BTreeMap<NaiveDate, f32>>, ApiError> {
+    let mut forecasts: BTreeMap<ForecastTarget, BTreeMap<NaiveDate, f32>> = BTreeMap::new();
+    for row in rows {
+        // a group already names its markets, so ignore any market given alongside it
+        let target = match (row.group_key, row.platform, row.platform_id) {
+            (Some(group_key), _, _) => ForecastTarget {
+                group_key: Some(group_key),
+                platform: None,
+                platform_id: None,
+            },
+            (None, Some(platform), Some(platform_id)) => ForecastTarget {
+                group_key: None,
+                platform: Some(platform),
+                platform_id: Some(platform_id),
+            },
+            _ => {
+                return Err(ApiError::new(
+                    400,
+                    format!(
+                        "forecast on {} needs a group_key or a platform and platform_id",
+                        row.date
+                    ),
+                ))
             }
+        };
+        let date = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d")
+            .map_err(|e| ApiError::new(400, format!("invalid forecast date {}: {e}", row.date)))?;
+        if !(0.0..=1.0).contains(&row.probability) {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "forecast probability must be between 0 and 1, got {}",
+                    row.probability
+                ),
+            ));
         }
+        if forecasts
+            .entry(target.clone())
+            .or_default()
+            .insert(date, row.probability)
+            .is_some()
+        {
+            return Err(ApiError::new(
+                400,
+                format!("more than one forecast on {date} for {target:?}"),
+            ));
+        }
+    }
+    Ok(forecasts)
+}
 
-        let mut markets_for_response = Vec::new();
-        for (platform, market) in markets_by_platform {
-            markets_for_response.push(ResponseMarketData {
-                market_data: market,
-                platform: platform.clone(),
-                absolute_brier: get_average_score_from_map(&absolute_score_data, &platform)?,
-                relative_brier: get_average_score_from_map(&relative_score_data, &platform)?,
+/// Get the group that a set of uploaded forecasts is scored against.
+/// A single market is treated as a group with only that market in it.
+fn get_forecast_target_group(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    target: &ForecastTarget,
+    config_file_groups: &[InputGroupData],
+) -> Result<InputGroupData, ApiError> {
+    match target {
+        ForecastTarget {
+            group_key: Some(group_key),
+            ..
+        } => find_group_by_key(config_file_groups, group_key),
+        ForecastTarget {
+            platform: Some(platform),
+            platform_id: Some(platform_id),
+            ..
+        } => {
+            let market = get_market_by_platform_id(conn, platform, platform_id)?;
+            Ok(InputGroupData {
+                title: market.title,
+                category: market.category,
+                region: Some(market.region),
+                start_date: None,
+                end_date: None,
+                markets: Vec::from([InputMarketData {
+                    platform: platform.clone(),
+                    platform_id: platform_id.clone(),
+                    invert: None,
+                }]),
             })
         }
+        _ => Err(ApiError::new(
+            400,
+            format!("forecast target {target:?} needs a group_key or a platform and platform_id"),
+        )),
+    }
+}
+
+/// Get a market to base synthetic markets on, along with the resolution of the group question.
+/// Synthetic markets forecast the group question, so a market that is not inverted is preferred.
+fn get_reference_market<'a>(
+    markets_by_platform: &'a HashMap<PlatformKey, Market>,
+    invert_by_platform: &HashMap<PlatformKey, bool>,
+) -> Result<(&'a Market, f32), ApiError> {
+    let (reference_platform, reference) = markets_by_platform
+        .iter()
+        .min_by_key(|(platform, _)| invert_by_platform.get(*platform).copied().unwrap_or(false))
+        .ok_or(ApiError::new(500, "group has no markets".to_string()))?;
+    let resolution = match invert_by_platform.get(reference_platform) {
+        Some(true) => 1.0 - reference.resolution,
+        _ => reference.resolution,
+    };
+    Ok((reference, resolution))
+}
+
+/// Test if a platform is one of the synthetic baselines.
+fn is_baseline(platform: &str) -> bool {
+    platform == BASELINE_UNIFORM_PLATFORM || platform == BASELINE_OPEN_PLATFORM
+}
+
+/// Find the markets that resolved on the other side of the group question from a strict
+/// majority of the group. If there is no majority, nobody is flagged.
+fn find_resolution_disagreements(
+    markets_by_platform: &HashMap<PlatformKey, Market>,
+    invert_by_platform: &HashMap<PlatformKey, bool>,
+    policy: ResolutionPolicy,
+) -> Vec<ResolutionDisagreement> {
+    // resolutions of the group question, not each market's own question
+    let resolutions: Vec<(&PlatformKey, f32)> = markets_by_platform
+        .iter()
+        .map(
+            |(platform, market)| match invert_by_platform.get(platform) {
+                Some(true) => (platform, 1.0 - market.resolution),
+                _ => (platform, market.resolution),
+            },
+        )
+        .collect();
+    let yes_count = resolutions.iter().filter(|(_, res)| *res >= 0.5).count();
+    let consensus = if yes_count * 2 > resolutions.len() {
+        1.0
+    } else if (resolutions.len() - yes_count) * 2 > resolutions.len() {
+        0.0
+    } else {
+        return Vec::new();
+    };
 
-        groups.push(ResponseGroupData {
-            group_title: group.title,
-            category: group.category,
-            markets: markets_for_response,
+    let mut disagreements: Vec<ResolutionDisagreement> = resolutions
+        .into_iter()
+        .filter(|(_, res)| (*res >= 0.5) != (consensus >= 0.5))
+        .map(|(platform, resolution)| ResolutionDisagreement {
+            platform: platform.clone(),
+            resolution,
+            consensus,
+            excluded: policy == ResolutionPolicy::Exclude,
         })
+        .collect();
+    disagreements.sort_by(|a, b| a.platform.cmp(&b.platform));
+    disagreements
+}
+
+/// Build a market that predicts the same probability on every date.
+fn build_constant_market(
+    reference: &Market,
+    platform: &str,
+    title: &str,
+    prob: f32,
+    resolution: f32,
+    dates: &[DateKey],
+) -> Market {
+    let prob_each_date: serde_json::Map<String, serde_json::Value> = dates
+        .iter()
+        .map(|date| (date.clone(), serde_json::json!(prob)))
+        .collect();
+    Market {
+        title: title.to_string(),
+        platform: platform.to_string(),
+        platform_id: String::new(),
+        url: String::new(),
+        volume_usd: 0.0,
+        num_traders: 0,
+        description: String::new(),
+        description_excerpt: String::new(),
+        image_url: None,
+        prob_at_midpoint: prob,
+        prob_at_close: prob,
+        prob_each_pct: Vec::from([prob; 101]),
+        prob_each_date: serde_json::Value::Object(prob_each_date),
+        prob_each_hour: serde_json::json!({}),
+        active_each_date: serde_json::json!({}),
+        volume_each_date: serde_json::json!({}),
+        prob_band_each_date: serde_json::json!({}),
+        prob_time_avg: prob,
+        prob_min: prob,
+        prob_max: prob,
+        prob_daily_stddev: 0.0,
+        prob_midpoint_crossings: 0,
+        days_to_confidence: None,
+        resolution,
+        ..reference.clone()
     }
+}
 
-    // get the platform metadata
-    let platform_list = get_unique_platforms_from_groups(&groups);
-    let mut platform_metadata = Vec::with_capacity(platform_list.len());
-    for platform in platform_list {
-        platform_metadata.push(get_platform_by_name(conn, &platform)?)
+/// Build the baseline markets for a group, which show how much the real markets add.
+/// One always predicts 50%, and the other carries forward the earliest probability of any
+/// market in the group. Both are given a probability on each of the given dates.
+fn build_baseline_markets(
+    markets_by_platform: &HashMap<PlatformKey, Market>,
+    invert_by_platform: &HashMap<PlatformKey, bool>,
+    dates: &[DateKey],
+) -> Result<Vec<(PlatformKey, Market)>, ApiError> {
+    let (reference, resolution) = get_reference_market(markets_by_platform, invert_by_platform)?;
+
+    // find the earliest probability and flip it to match the group question
+    let mut first_probs: Vec<(DateKey, f32)> = Vec::with_capacity(markets_by_platform.len());
+    for (platform, market) in markets_by_platform {
+        let Some(first_date) = market
+            .prob_each_date
+            .as_object()
+            .and_then(|dates| dates.keys().min())
+        else {
+            continue;
+        };
+        let prob = get_prob_on_date_from_market(market, first_date)?;
+        let prob = match invert_by_platform.get(platform) {
+            Some(true) => 1.0 - prob,
+            _ => prob,
+        };
+        first_probs.push((first_date.clone(), prob));
     }
+    let open_prob = first_probs
+        .into_iter()
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, prob)| prob)
+        .ok_or(ApiError::new(500, "group has no market dates".to_string()))?;
 
-    // get the aggregate stats for all categories then each individual category
-    let category_list = get_unique_categories_from_groups(&groups);
-    let mut platform_stats = get_platform_aggregate_stats(&groups, "All".to_string());
-    platform_stats.extend(
-        category_list
+    Ok(Vec::from([
+        (
+            BASELINE_UNIFORM_PLATFORM.to_string(),
+            build_constant_market(
+                reference,
+                BASELINE_UNIFORM_PLATFORM,
+                "Always 50%",
+                0.5,
+                resolution,
+                dates,
+            ),
+        ),
+        (
+            BASELINE_OPEN_PLATFORM.to_string(),
+            build_constant_market(
+                reference,
+                BASELINE_OPEN_PLATFORM,
+                "Opening probability",
+                open_prob,
+                resolution,
+                dates,
+            ),
+        ),
+    ]))
+}
+
+/// Build a market out of uploaded forecasts so they can be scored like any other platform.
+/// Each forecast is carried forward daily until the last date any linked market has a
+/// probability, and only days with a new forecast are marked as active.
+fn build_forecast_market(
+    markets_by_platform: &HashMap<PlatformKey, Market>,
+    invert_by_platform: &HashMap<PlatformKey, bool>,
+    forecast: &BTreeMap<NaiveDate, f32>,
+) -> Result<Market, ApiError> {
+    let (reference, resolution) = get_reference_market(markets_by_platform, invert_by_platform)?;
+
+    // fill in every day between the first forecast and the last market date
+    let last_day = markets_by_platform
+        .values()
+        .filter_map(get_last_date)
+        .max()
+        .and_then(|date| NaiveDate::parse_from_str(&get_day(&date), "%Y-%m-%d").ok())
+        .ok_or(ApiError::new(500, "group has no market dates".to_string()))?;
+    let mut prob_each_date = serde_json::Map::new();
+    let mut active_each_date = serde_json::Map::new();
+    let mut daily_probs = Vec::new();
+    let mut prob = None;
+    for day in forecast
+        .keys()
+        .next()
+        .copied()
+        .into_iter()
+        .flat_map(|first_day| {
+            first_day
+                .iter_days()
+                .take_while(move |day| *day <= last_day)
+        })
+    {
+        let forecast_today = forecast.get(&day).copied();
+        prob = forecast_today.or(prob);
+        let Some(prob) = prob else { continue };
+        let date = format!("{}T00:00:00Z", day.format("%Y-%m-%d"));
+        prob_each_date.insert(date.clone(), serde_json::json!(prob));
+        active_each_date.insert(date, serde_json::json!(forecast_today.is_some()));
+        daily_probs.push(prob);
+    }
+    let Some(last_prob) = daily_probs.last() else {
+        return Err(ApiError::new(
+            400,
+            "every forecast was made after the linked markets closed".to_string(),
+        ));
+    };
+    let prob_time_avg = daily_probs.iter().sum::<f32>() / daily_probs.len() as f32;
+
+    Ok(Market {
+        title: "Uploaded forecasts".to_string(),
+        platform: FORECAST_PLATFORM.to_string(),
+        platform_id: String::new(),
+        url: String::new(),
+        open_dt: forecast
+            .keys()
+            .next()
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .map(|dt| dt.and_utc())
+            .unwrap_or(reference.open_dt),
+        open_days: daily_probs.len() as f32,
+        volume_usd: 0.0,
+        num_traders: 1,
+        description: String::new(),
+        description_excerpt: String::new(),
+        image_url: None,
+        prob_at_midpoint: daily_probs[daily_probs.len() / 2],
+        prob_at_close: *last_prob,
+        prob_each_pct: Vec::new(),
+        prob_each_date: serde_json::Value::Object(prob_each_date),
+        prob_each_hour: serde_json::json!({}),
+        active_each_date: serde_json::Value::Object(active_each_date),
+        volume_each_date: serde_json::json!({}),
+        prob_band_each_date: serde_json::json!({}),
+        prob_time_avg,
+        prob_min: daily_probs.iter().copied().fold(f32::INFINITY, f32::min),
+        prob_max: daily_probs
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max),
+        prob_daily_stddev: (daily_probs
             .iter()
-            .flat_map(|category| get_platform_aggregate_stats(&groups, category.clone())),
+            .map(|prob| (prob - prob_time_avg).powi(2))
+            .sum::<f32>()
+            / daily_probs.len() as f32)
+            .sqrt(),
+        prob_midpoint_crossings: daily_probs
+            .iter()
+            .filter(|prob| **prob != 0.5)
+            .map(|prob| *prob > 0.5)
+            .collect::<Vec<bool>>()
+            .windows(2)
+            .filter(|sides| sides[0] != sides[1])
+            .count() as i32,
+        days_to_confidence: None,
+        resolution,
+        ..reference.clone()
+    })
+}
+
+/// Take an uploaded forecast file and score it against the linked markets with the same
+/// machinery as the group comparison, then rank it against every platform.
+/// The file can be JSON or, if `content_type` is `text/csv`, CSV with a header line.
+pub fn build_forecast_scores(
+    content_type: &str,
+    body: &str,
+    query: Query<GroupQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let options = get_scoring_options(&query)?;
+
+    // parse and sort the forecasts
+    let rows: Vec<ForecastRow> = match content_type {
+        "text/csv" => parse_forecast_csv(body)?,
+        _ => serde_json::from_str(body)
+            .map_err(|e| ApiError::new(400, format!("failed to parse forecast file: {e}")))?,
+    };
+    let forecasts = group_forecasts_by_target(rows)?;
+    if forecasts.is_empty() {
+        return Err(ApiError::new(400, "forecast file is empty".to_string()));
+    }
+
+    // score each target with the forecasts added as another platform
+    let config_file_groups = load_groups_from_file()?;
+    let mut groups = Vec::with_capacity(forecasts.len());
+    let mut targets = Vec::with_capacity(forecasts.len());
+    for (target, forecast) in forecasts {
+        let group = get_forecast_target_group(conn, &target, &config_file_groups)?;
+        let (mut markets_by_platform, mut invert_by_platform) = load_group_markets(conn, &group)?;
+        let forecast_market =
+            build_forecast_market(&markets_by_platform, &invert_by_platform, &forecast)?;
+        markets_by_platform.insert(FORECAST_PLATFORM.to_string(), forecast_market);
+        invert_by_platform.insert(FORECAST_PLATFORM.to_string(), false);
+        let group = score_group_markets(group, markets_by_platform, invert_by_platform, &options)
+            .map_err(|e| {
+            ApiError::new(
+                400,
+                format!("failed to score forecasts for {target:?}: {}", e.message),
+            )
+        })?;
+        targets.push(ForecastTargetResponse {
+            target,
+            group_title: group.group_title.clone(),
+            category: group.category.clone(),
+            markets: group.markets.clone(),
+        });
+        groups.push(group);
+    }
+
+    // rank everything by mean relative score
+    let mut platform_stats = get_platform_aggregate_stats(
+        &groups,
+        "All".to_string(),
+        &WeightAttribute::None,
+        Aggregation::Mean,
+        Aggregation::Mean,
     );
+    platform_stats.sort_by(|a, b| {
+        a.platform_relative_brier
+            .partial_cmp(&b.platform_relative_brier)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let ranking = platform_stats
+        .iter()
+        .map(|stats| stats.platform.clone())
+        .collect();
 
-    // save it all to the response struct & ship
-    let response = FullResponse {
-        platform_metadata,
+    let response = ForecastScoreResponse {
+        schema_version: SCHEMA_VERSION,
+        relative_score_type: options.relative_baseline.get_score_type(),
+        ranking,
         platform_stats,
-        groups,
+        targets,
     };
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// Check every group in the group mapping file against the database.
+/// Returns a list of problems, such as references to markets that do not exist,
+/// groups with more than one market from the same platform, or markets in more than one group.
+pub fn validate_groups(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<String>, ApiError> {
+    let groups = load_groups_from_file()?;
+    let mut problems = Vec::new();
+    let mut groups_by_market: HashMap<(&String, &String), usize> = HashMap::new();
+    for (group_id, group) in groups.iter().enumerate() {
+        let mut markets_by_platform: HashMap<PlatformKey, Market> = HashMap::new();
+        let mut platforms_seen: HashSet<&String> = HashSet::new();
+        for market in &group.markets {
+            // markets are keyed by platform when scoring, so duplicates would be dropped
+            if !platforms_seen.insert(&market.platform) {
+                problems.push(format!(
+                    "group {group_id} ({}) has more than one market from {}",
+                    group.title, market.platform
+                ));
+            }
+            // groups are keyed by their markets in the rating history, see `get_group_key`
+            if let Some(other_id) =
+                groups_by_market.insert((&market.platform, &market.platform_id), group_id)
+            {
+                problems.push(format!(
+                    "group {group_id} ({}) has {}/{}, which is also in group {other_id}",
+                    group.title, market.platform, market.platform_id
+                ));
+            }
+            // make sure the market has actually been fetched
+            match get_market_by_platform_id(conn, &market.platform, &market.platform_id) {
+                Ok(market_data) => {
+                    markets_by_platform.insert(market.platform.clone(), market_data);
+                }
+                Err(e) => {
+                    problems.push(format!("group {group_id} ({}): {}", group.title, e.message))
+                }
+            }
+        }
+        // unset overrides give an unfair relative score to markets that opened early
+        if get_window_override(group).is_none() {
+            if let Some(window) = suggest_group_window(&markets_by_platform) {
+                problems.push(format!(
+                    "group {group_id} ({}) has markets that opened far apart, consider setting start_date: {} and end_date: {}",
+                    group.title, window.start_date, window.end_date
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_platform_stats() -> ResponsePlatformStats {
+        ResponsePlatformStats {
+            platform: "manifold".to_string(),
+            category: "Politics".to_string(),
+            platform_absolute_brier: Some(0.25),
+            platform_relative_brier: Some(-0.125),
+            platform_adjusted_brier: None,
+            platform_relative_brier_early: None,
+            platform_relative_brier_middle: None,
+            platform_relative_brier_late: Some(0.5),
+            platform_sample_presence: 0.75,
+            platform_sample_size: 4,
+            platform_sample_sufficient: false,
+            platform_absolute_brier_shrunk: Some(0.25),
+            platform_relative_brier_shrunk: None,
+        }
+    }
+
+    /// Unset thirds are left out instead of returned as null.
+    fn sample_platform_stats_json() -> serde_json::Value {
+        serde_json::json!({
+            "platform": "manifold",
+            "category": "Politics",
+            "platform_absolute_brier": 0.25,
+            "platform_relative_brier": -0.125,
+            "platform_adjusted_brier": null,
+            "platform_relative_brier_late": 0.5,
+            "platform_sample_presence": 0.75,
+            "platform_sample_size": 4,
+            "platform_sample_sufficient": false,
+            "platform_absolute_brier_shrunk": 0.25,
+            "platform_relative_brier_shrunk": null,
+        })
+    }
+
+    fn sample_group() -> ResponseGroupData {
+        ResponseGroupData {
+            group_key: "manifold/abc123".to_string(),
+            group_title: "Who will win?".to_string(),
+            category: "Politics".to_string(),
+            region: "US".to_string(),
+            window_override: None,
+            window_suggestion: Some(GroupWindow {
+                start_date: "2024-01-01".to_string(),
+                end_date: "2024-02-01".to_string(),
+            }),
+            difficulty: 0.125,
+            resolution_disagreements: Vec::from([ResolutionDisagreement {
+                platform: "kalshi".to_string(),
+                resolution: 0.0,
+                consensus: 1.0,
+                excluded: true,
+            }]),
+            markets: Vec::new(),
+        }
+    }
+
+    fn sample_group_json() -> serde_json::Value {
+        serde_json::json!({
+            "group_key": "manifold/abc123",
+            "group_title": "Who will win?",
+            "category": "Politics",
+            "region": "US",
+            "window_override": null,
+            "window_suggestion": {"start_date": "2024-01-01", "end_date": "2024-02-01"},
+            "difficulty": 0.125,
+            "resolution_disagreements": [{
+                "platform": "kalshi",
+                "resolution": 0.0,
+                "consensus": 1.0,
+                "excluded": true,
+            }],
+            "markets": [],
+        })
+    }
+
+    fn sample_platform() -> Platform {
+        Platform {
+            name: "manifold".to_string(),
+            name_fmt: "Manifold".to_string(),
+            description: "A play-money market".to_string(),
+            avatar_url: "/images/manifold.svg".to_string(),
+            site_url: "https://manifold.markets".to_string(),
+            color: "#4337c9".to_string(),
+            color_accent: "#8c84f5".to_string(),
+            capabilities: serde_json::json!({"resolved_dt": true}),
+        }
+    }
+
+    fn sample_platform_json() -> serde_json::Value {
+        serde_json::json!({
+            "name": "manifold",
+            "name_fmt": "Manifold",
+            "description": "A play-money market",
+            "avatar_url": "/images/manifold.svg",
+            "site_url": "https://manifold.markets",
+            "color": "#4337c9",
+            "color_accent": "#8c84f5",
+            "capabilities": {"resolved_dt": true},
+        })
+    }
+
+    /// A resolved market with two days of probabilities, from the fetch output format.
+    fn sample_market(platform: &str, resolution: f32) -> Market {
+        serde_json::from_value(serde_json::json!({
+            "title": "Will it rain?",
+            "platform": platform,
+            "platform_id": "abc123",
+            "url": "https://example.com/will-it-rain",
+            "open_dt": "2024-01-01T00:00:00Z",
+            "close_dt": "2024-01-03T00:00:00Z",
+            "trading_end_dt": "2024-01-03T00:00:00Z",
+            "resolved_dt": "2024-01-04T00:00:00Z",
+            "open_days": 2.0,
+            "volume_usd": 12.5,
+            "num_traders": 3,
+            "category": "Climate",
+            "region": "US",
+            "description": "",
+            "description_excerpt": "",
+            "image_url": null,
+            "prob_at_midpoint": 0.5,
+            "prob_at_close": 0.75,
+            "prob_each_pct": [0.5, 0.75],
+            "prob_each_date": {"2024-01-01T00:00:00Z": 0.5, "2024-01-02T00:00:00Z": 0.75},
+            "prob_each_hour": {},
+            "active_each_date": {},
+            "volume_each_date": {},
+            "prob_band_each_date": {},
+            "prob_time_avg": 0.625,
+            "prob_min": 0.5,
+            "prob_max": 0.75,
+            "prob_daily_stddev": 0.125,
+            "prob_midpoint_crossings": 0,
+            "days_to_confidence": null,
+            "resolution": resolution,
+            "question_type": "binary",
+            "quantiles_each_date": {},
+            "linked_group": null,
+            "linked_order": null,
+        }))
+        .unwrap()
+    }
+
+    /// A group where kalshi resolved NO and the other two platforms resolved YES.
+    fn sample_disagreeing_group() -> (InputGroupData, GroupMarkets) {
+        let platforms = [("kalshi", 0.0), ("manifold", 1.0), ("polymarket", 1.0)];
+        let group = InputGroupData {
+            title: "Will it rain?".to_string(),
+            category: "Climate".to_string(),
+            region: None,
+            start_date: None,
+            end_date: None,
+            markets: platforms
+                .iter()
+                .map(|(platform, _)| InputMarketData {
+                    platform: platform.to_string(),
+                    platform_id: "abc123".to_string(),
+                    invert: None,
+                })
+                .collect(),
+        };
+        let markets_by_platform = platforms
+            .iter()
+            .map(|(platform, resolution)| {
+                (platform.to_string(), sample_market(platform, *resolution))
+            })
+            .collect();
+        let invert_by_platform = platforms
+            .iter()
+            .map(|(platform, _)| (platform.to_string(), false))
+            .collect();
+        (group, (markets_by_platform, invert_by_platform))
+    }
+
+    /// Links to a group and uploaded forecasts keep pointing at it when the file changes.
+    #[test]
+    fn group_key_survives_reordering() {
+        let (group, _) = sample_disagreeing_group();
+        let mut other = group.clone();
+        other.title = "Will it snow?".to_string();
+        for market in &mut other.markets {
+            market.platform_id = "def456".to_string();
+        }
+        assert_eq!(get_group_key(&group), "kalshi/abc123");
+        for groups in [[group.clone(), other.clone()], [other, group]] {
+            let found = find_group_by_key(&groups, "kalshi/abc123").unwrap();
+            assert_eq!(found.title, "Will it rain?");
+        }
+        assert_eq!(
+            find_group_by_key(&[], "kalshi/abc123")
+                .unwrap_err()
+                .status_code,
+            404
+        );
+    }
+
+    /// A benchmark market left out for resolving against its group can't be the baseline,
+    /// so the group is skipped by the list and refused by the single-group route.
+    #[test]
+    fn excluded_relative_baseline_is_refused() {
+        let (group, (markets_by_platform, invert_by_platform)) = sample_disagreeing_group();
+        let options = ScoringOptions {
+            relative_baseline: RelativeBaseline::Platform("kalshi".to_string()),
+            resolution_policy: ResolutionPolicy::Exclude,
+            ..ScoringOptions::default()
+        };
+        assert!(has_relative_baseline(&group, &options.relative_baseline));
+        assert!(is_relative_baseline_excluded(
+            &markets_by_platform,
+            &invert_by_platform,
+            &options
+        ));
+        let error = score_group_markets(group, markets_by_platform, invert_by_platform, &options)
+            .unwrap_err();
+        assert_eq!(error.status_code, 400);
+    }
+
+    #[test]
+    fn kept_relative_baseline_is_scored() {
+        let (group, (markets_by_platform, invert_by_platform)) = sample_disagreeing_group();
+        let options = ScoringOptions {
+            relative_baseline: RelativeBaseline::Platform("kalshi".to_string()),
+            resolution_policy: ResolutionPolicy::Own,
+            ..ScoringOptions::default()
+        };
+        assert!(!is_relative_baseline_excluded(
+            &markets_by_platform,
+            &invert_by_platform,
+            &options
+        ));
+        let group =
+            score_group_markets(group, markets_by_platform, invert_by_platform, &options).unwrap();
+        assert_eq!(group.markets.len(), 3);
+    }
+
+    #[test]
+    fn full_response_matches_snapshot() {
+        let response = FullResponse {
+            schema_version: SCHEMA_VERSION,
+            relative_score_type: "relative_brier".to_string(),
+            platform_metadata: Vec::from([sample_platform()]),
+            platform_stats: Vec::from([sample_platform_stats()]),
+            head_to_head: Vec::from([ResponseHeadToHead {
+                platform: "manifold".to_string(),
+                opponent: "kalshi".to_string(),
+                category: "Politics".to_string(),
+                shared_groups: 4,
+                wins: 2,
+                losses: 1,
+                ties: 1,
+                win_rate: 0.625,
+            }]),
+            groups: Vec::from([sample_group()]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "relative_score_type": "relative_brier",
+            "platform_metadata": [sample_platform_json()],
+            "platform_stats": [sample_platform_stats_json()],
+            "head_to_head": [{
+                "platform": "manifold",
+                "opponent": "kalshi",
+                "category": "Politics",
+                "shared_groups": 4,
+                "wins": 2,
+                "losses": 1,
+                "ties": 1,
+                "win_rate": 0.625,
+            }],
+            "groups": [sample_group_json()],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+
+    #[test]
+    fn group_markets_response_matches_snapshot() {
+        let response = GroupMarketsResponse {
+            schema_version: SCHEMA_VERSION,
+            relative_score_type: "relative_brier".to_string(),
+            platform_metadata: Vec::from([sample_platform()]),
+            group: sample_group(),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "relative_score_type": "relative_brier",
+            "platform_metadata": [sample_platform_json()],
+            "group": sample_group_json(),
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+
+    #[test]
+    fn forecast_score_response_matches_snapshot() {
+        let response = ForecastScoreResponse {
+            schema_version: SCHEMA_VERSION,
+            relative_score_type: "relative_brier".to_string(),
+            ranking: Vec::from(["manifold".to_string(), "forecast".to_string()]),
+            platform_stats: Vec::from([sample_platform_stats()]),
+            targets: Vec::from([ForecastTargetResponse {
+                target: ForecastTarget {
+                    group_key: Some("manifold/abc123".to_string()),
+                    platform: None,
+                    platform_id: None,
+                },
+                group_title: "Who will win?".to_string(),
+                category: "Politics".to_string(),
+                markets: Vec::new(),
+            }]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "relative_score_type": "relative_brier",
+            "ranking": ["manifold", "forecast"],
+            "platform_stats": [sample_platform_stats_json()],
+            "targets": [{
+                "group_key": "manifold/abc123",
+                "platform": null,
+                "platform_id": null,
+                "group_title": "Who will win?",
+                "category": "Politics",
+                "markets": [],
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
@@ -48,6 +48,230 @@ pub fn scale_data_point(value: f32, p: ScaleParams) -> f32 {
         + p.output_min
 }
 
+/// Number of equal-width probability bins used to measure calibration error.
+const CALIBRATION_BINS: usize = 10;
+
+/// Number of steps used to integrate the CRPS over the question range.
+const CRPS_STEPS: usize = 1000;
+
+/// Share of the total weight cut from each end by the trimmed and winsorized means.
+const TRIM_FRACTION: f32 = 0.1;
+
+/// Get the continuous ranked probability score of a forecast given as quartiles, with the
+/// quartiles and outcome as positions from 0 to 1 in the question range.
+/// The forecast CDF is taken to be linear between the range bounds and each quartile,
+/// and the score is its squared distance from the outcome's step CDF over the whole range.
+/// Lower is better: a perfect forecast scores 0 and a uniform forecast averages 1/6.
+pub fn crps_from_quartiles(quartiles: [f32; 3], outcome: f32) -> f32 {
+    let [q1, q2, q3] = quartiles.map(|q| q.clamp(0.0, 1.0));
+    let knots = [(0.0, 0.0), (q1, 0.25), (q2, 0.5), (q3, 0.75), (1.0, 1.0)];
+    let cdf = |x: f32| -> f32 {
+        for pair in knots.windows(2) {
+            let ((x0, p0), (x1, p1)) = (pair[0], pair[1]);
+            if x <= x1 {
+                if x1 <= x0 {
+                    return p1;
+                }
+                return p0 + (p1 - p0) * (x - x0) / (x1 - x0);
+            }
+        }
+        1.0
+    };
+    let step = 1.0 / CRPS_STEPS as f32;
+    (0..CRPS_STEPS)
+        .map(|i| {
+            let x = (i as f32 + 0.5) * step;
+            let observed = if x >= outcome { 1.0 } else { 0.0 };
+            (cdf(x) - observed).powi(2) * step
+        })
+        .sum()
+}
+
+/// Rescale the probabilities of every option of a question so they sum to 1.
+/// Options on most platforms are traded separately, so their prices rarely add up exactly.
+fn normalize_probs(probs: &[f32]) -> Vec<f32> {
+    let total: f32 = probs.iter().sum();
+    if total <= 0.0 {
+        return vec![1.0 / probs.len() as f32; probs.len()];
+    }
+    probs.iter().map(|prob| prob / total).collect()
+}
+
+/// Get the multi-class Brier score of a forecast over every option of a question, given the
+/// probability and resolution of each option. The probabilities are rescaled to sum to 1 first.
+/// Lower is better: a perfect forecast scores 0 and the worst possible forecast scores 2.
+pub fn multi_brier_score(probs: &[f32], outcomes: &[f32]) -> f32 {
+    normalize_probs(probs)
+        .iter()
+        .zip(outcomes)
+        .map(|(prob, outcome)| (prob - outcome).powi(2))
+        .sum()
+}
+
+/// Get the ranked probability score of a forecast over the ordered options of a question,
+/// given in order. This compares the cumulative distributions, so probability on an option
+/// next to the outcome is penalized less than probability on one far from it.
+/// Lower is better: a perfect forecast scores 0 and the worst possible forecast scores 1.
+pub fn ranked_probability_score(probs: &[f32], outcomes: &[f32]) -> f32 {
+    if probs.len() < 2 {
+        return 0.0;
+    }
+    let mut prob_cdf = 0.0;
+    let mut outcome_cdf = 0.0;
+    let mut total = 0.0;
+    for (prob, outcome) in normalize_probs(probs).iter().zip(outcomes) {
+        prob_cdf += prob;
+        outcome_cdf += outcome;
+        total += (prob_cdf - outcome_cdf).powi(2);
+    }
+    // the last option always has both distributions at 1, so it adds nothing
+    total / (probs.len() - 1) as f32
+}
+
+/// How far a set of forecasts is from perfect calibration, as a single number each.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CalibrationError {
+    /// Expected calibration error: the weighted average distance between the mean forecast
+    /// and the observed frequency in each bin. 0 is perfectly calibrated.
+    pub expected: f32,
+    /// The reliability component of the Brier score: the same as above with squared distances.
+    pub reliability: f32,
+}
+
+/// Measure the calibration error of forecasts given as probability, outcome, and weight.
+/// Forecasts are sorted into equal-width bins by probability before comparing.
+pub fn get_calibration_error(forecasts: impl Iterator<Item = (f32, f32, f32)>) -> CalibrationError {
+    // weight, weighted forecast sum, and weighted outcome sum for each bin
+    let mut bins = [(0.0, 0.0, 0.0); CALIBRATION_BINS];
+    for (prob, outcome, weight) in forecasts {
+        let index = ((prob * CALIBRATION_BINS as f32) as usize).min(CALIBRATION_BINS - 1);
+        bins[index].0 += weight;
+        bins[index].1 += weight * prob;
+        bins[index].2 += weight * outcome;
+    }
+    let total_weight: f32 = bins.iter().map(|bin| bin.0).sum();
+    let mut result = CalibrationError {
+        expected: 0.0,
+        reliability: 0.0,
+    };
+    for (weight, prob_sum, outcome_sum) in bins {
+        if weight <= 0.0 {
+            continue;
+        }
+        let distance = (prob_sum - outcome_sum) / weight;
+        result.expected += weight / total_weight * distance.abs();
+        result.reliability += weight / total_weight * distance.powi(2);
+    }
+    result
+}
+
+/// Get the Brier skill score of a set of markets against a reference forecast that always
+/// predicts the base rate of each market's platform and category.
+/// 1 is perfect, 0 is no better than the base rate, and below 0 is worse.
+/// Returns None if the reference is never wrong, such as when every market resolved the same way.
+pub fn get_brier_skill_score(
+    markets: &[Market],
+    prob: fn(&Market) -> f32,
+    base_rates: &HashMap<(String, String), f32>,
+) -> Option<f32> {
+    let mut brier_sum = 0.0;
+    let mut reference_sum = 0.0;
+    for market in markets {
+        let base_rate = base_rates
+            .get(&(market.platform.clone(), market.category.clone()))
+            .copied()
+            .unwrap_or(0.5);
+        brier_sum += (market.resolution - prob(market)).powf(2.0);
+        reference_sum += (market.resolution - base_rate).powf(2.0);
+    }
+    match reference_sum > 0.0 {
+        true => Some(1.0 - brier_sum / reference_sum),
+        false => None,
+    }
+}
+
+/// How a list of scores is combined into a single score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// The mean of every score, which a single extreme score can move a long way.
+    #[default]
+    Mean,
+    /// The middle score.
+    Median,
+    /// The mean after dropping the highest and lowest 10% of scores.
+    TrimmedMean,
+    /// The mean after pulling the highest and lowest 10% of scores in to the nearest kept score.
+    Winsorized,
+}
+
+/// Get the score at a position in the total weight, from the lowest score to the highest.
+/// With `inclusive`, a score whose weight ends exactly at the position is picked instead of the next.
+fn score_at_weight(sorted: &[(f32, f32)], position: f32, inclusive: bool) -> f32 {
+    let mut cumulative = 0.0;
+    for (score, weight) in sorted {
+        cumulative += weight;
+        if cumulative > position || (inclusive && cumulative >= position) {
+            return *score;
+        }
+    }
+    sorted.last().map_or(0.0, |(score, _)| *score)
+}
+
+/// Combine a list of scores, each paired with its weight, into one.
+/// Returns None if there are no scores with any weight.
+pub fn aggregate_scores(scores: &[(f32, f32)], aggregation: Aggregation) -> Option<f32> {
+    let mut sorted: Vec<(f32, f32)> = scores
+        .iter()
+        .copied()
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    let total_weight: f32 = sorted.iter().map(|(_, weight)| weight).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    let (low, high) = (
+        total_weight * TRIM_FRACTION,
+        total_weight * (1.0 - TRIM_FRACTION),
+    );
+    let score = match aggregation {
+        Aggregation::Mean => {
+            sorted
+                .iter()
+                .map(|(score, weight)| score * weight)
+                .sum::<f32>()
+                / total_weight
+        }
+        Aggregation::Median => {
+            // average the two middle scores if the middle falls between them
+            let half = total_weight / 2.0;
+            (score_at_weight(&sorted, half, true) + score_at_weight(&sorted, half, false)) / 2.0
+        }
+        Aggregation::TrimmedMean => {
+            // keep only the part of each score's weight between the cutoffs
+            let mut start = 0.0;
+            let mut sum = 0.0;
+            for (score, weight) in &sorted {
+                let end: f32 = start + weight;
+                sum += score * (end.min(high) - start.max(low)).max(0.0);
+                start = end;
+            }
+            sum / (high - low)
+        }
+        Aggregation::Winsorized => {
+            let floor = score_at_weight(&sorted, low, false);
+            let ceiling = score_at_weight(&sorted, high, true);
+            sorted
+                .iter()
+                .map(|(score, weight)| score.max(floor).min(ceiling) * weight)
+                .sum::<f32>()
+                / total_weight
+        }
+    };
+    Some(score)
+}
+
 /// Sort all markets into Vecs based on the platform name.
 pub fn categorize_markets_by_platform(markets: Vec<Market>) -> HashMap<String, Vec<Market>> {
     let mut markets_by_platform: HashMap<String, Vec<Market>> = HashMap::new();
@@ -1,53 +1,188 @@
-use actix_web::web::{Data, Query};
-use actix_web::{get, middleware, App, HttpResponse, HttpServer};
-use chrono::{DateTime, Utc};
+use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
+use actix_web::{get, middleware, post, App, HttpMessage, HttpRequest, HttpResponse, HttpServer};
+use chrono::{DateTime, NaiveDate, Utc};
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use diesel::{pg::PgConnection, prelude::*};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json;
-use serde_yaml;
 use std::collections::{HashMap, HashSet};
 use std::env::var;
 use std::fs::File;
+use std::sync::Arc;
 
+mod admin;
+mod cohort;
+mod criteria;
 mod db_util;
+mod exclusions;
+mod export;
+mod freshness;
+mod grade_diff;
 mod group_comparison;
 mod helper;
 mod market_accuracy;
 mod market_calibration;
 mod market_filter;
+mod market_grade;
 mod market_list;
+mod methodology;
+mod offline;
+mod period_score;
+mod rating;
+mod score_types;
+mod suggestion;
+mod trader_score;
 
+use admin::{
+    build_job_cancel, build_job_list, build_refresh_views, build_trigger_grade, check_admin_token,
+    GradeQueryParams,
+};
+use cohort::{
+    build_cohort_compare, build_cohort_create, build_cohort_score_record, build_cohort_scores,
+    format_score_change, record_all_cohort_scores, score_cohort, score_markets,
+    CohortCompareQueryParams, CohortCreateBody, CohortScoresQueryParams, GradeCost,
+};
+use criteria::{load_criteria_config, CriteriaConfig, CriterionPoint};
 use db_util::{
-    get_all_platforms, get_market_by_platform_id, get_platform_by_name, market, platform, Market,
-    Platform,
+    fail_running_job, finish_job, get_all_cohorts, get_all_platforms, get_all_trader_bets,
+    get_base_rates, get_cohort_by_id, get_cohort_score_history, get_finished_jobs,
+    get_last_scored_dt_by_platform, get_market_by_platform_id, get_market_changes_between,
+    get_market_grades, get_period_scores, get_platform_by_name, get_platform_ratings,
+    get_recent_jobs, get_trader_scores, insert_cohort, insert_cohort_scores, insert_job,
+    insert_suggestion, market, merge_period_scores, platform, replace_period_scores,
+    replace_platform_ratings, replace_trader_scores, save_market_grades, Cohort, CohortScore,
+    DayWeighting, Job, Market, MarketChange, MarketGrade, NewCohort, NewJob, NewSuggestion,
+    PeriodScore, Platform, PlatformRating, TraderScore,
+};
+use exclusions::{build_exclusion_list, ExclusionList};
+use export::export_scores;
+use freshness::{build_freshness, FreshnessQueryParams};
+use grade_diff::build_grade_diff;
+use group_comparison::{
+    build_forecast_scores, build_group_comparison, build_group_markets, get_group_results,
+    validate_groups, GroupQueryParams, GroupResult,
+};
+use helper::{
+    aggregate_scores, categorize_markets_by_platform, crps_from_quartiles, get_brier_skill_score,
+    get_calibration_error, get_scale_params, multi_brier_score, ranked_probability_score,
+    scale_data_point, Aggregation, ApiError, CalibrationError,
 };
-use group_comparison::build_group_comparison;
-use helper::{categorize_markets_by_platform, get_scale_params, scale_data_point, ApiError};
 use market_accuracy::{build_accuracy_plot, AccuracyQueryParams};
-use market_calibration::{build_calibration_plot, CalibrationQueryParams};
-use market_filter::{get_markets_filtered, CommonFilterParams, PageSortParams};
-use market_list::{build_market_list, MarketListQueryParams};
+use market_calibration::{
+    build_calibration_plot, build_criteria_list, CalibrationQueryParams, WeightAttribute,
+    YAxisMethods,
+};
+use market_filter::{
+    get_markets_filtered, get_markets_filtered_by_type, CommonFilterParams, MarketRef,
+    MarketSelection, PageSortParams, UNSETTLED_PLATFORMS,
+};
+use market_grade::{get_content_hash, GradeState};
+use market_list::{
+    build_market_list, build_recently_resolved, MarketListQueryParams, RecentlyResolvedQueryParams,
+};
+use methodology::{
+    get_cohort_markets, get_cohort_numeric_markets, get_methodology_version,
+    MethodologyQueryParams, METHODOLOGY_VERSION,
+};
+use offline::grade_offline;
+use period_score::{
+    build_period_scores, record_period_scores, score_periods, PeriodScoreQueryParams,
+};
+use rating::{build_ratings, record_platform_ratings, RatingQueryParams};
+use score_types::build_score_type_list;
+use suggestion::{build_suggestion, get_client_addr, RateLimiter, SuggestionBody};
+use trader_score::{build_trader_leaderboard, record_trader_scores, TraderScoreQueryParams};
+
+/// The version of the JSON response format, sent in every response.
+/// Bump this whenever a field is renamed, removed, or changes meaning,
+/// so clients and saved exports can tell which format they have.
+const SCHEMA_VERSION: u32 = 1;
+
+/// Deployment options read from the environment on startup.
+#[derive(Debug, Clone)]
+struct ServerConfig {
+    /// Disable routes that write to the database or read local files,
+    /// so a mirror can be served from a database dump alone.
+    read_only: bool,
+    /// Bearer token required for the admin routes, which are disabled if this is unset.
+    admin_token: Option<String>,
+    /// Reverse proxies whose forwarded client address headers are trusted.
+    trusted_proxies: Vec<std::net::IpAddr>,
+}
+impl ServerConfig {
+    fn from_env() -> ServerConfig {
+        ServerConfig {
+            read_only: var("READ_ONLY")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            admin_token: var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
+            trusted_proxies: var("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty())
+                .map(|addr| {
+                    addr.parse()
+                        .unwrap_or_else(|_| panic!("Invalid address in TRUSTED_PROXIES: {addr}"))
+                })
+                .collect(),
+        }
+    }
+}
 
 #[derive(Debug, Serialize)]
 struct IndexResponse {
     status: String,
+    read_only: bool,
+    /// The cohort scoring methodology used unless an older one is pinned.
+    methodology_version: i32,
+    /// The version of the response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
     routes: Vec<String>,
 }
 
 #[get("/")]
-async fn list_routes() -> Result<HttpResponse, ApiError> {
+async fn list_routes(config: Data<ServerConfig>) -> Result<HttpResponse, ApiError> {
+    let mut routes = Vec::from([
+        "/".to_string(),
+        "/list_platforms".to_string(),
+        "/list_markets".to_string(),
+        "/recently_resolved".to_string(),
+        "/calibration_plot".to_string(),
+        "/accuracy_plot".to_string(),
+        "/criteria".to_string(),
+        "/score_types".to_string(),
+        "/cohorts".to_string(),
+        "/cohort/{id}/scores".to_string(),
+        "/cohort/{id}/compare".to_string(),
+        "/freshness".to_string(),
+        "/period_scores".to_string(),
+        "/ratings".to_string(),
+        "/trader_leaderboard".to_string(),
+    ]);
+    if !config.read_only {
+        routes.extend([
+            "/exclusions".to_string(),
+            "/group_accuracy".to_string(),
+            "/group/{key}/markets".to_string(),
+            "/score_forecasts".to_string(),
+            "/suggest".to_string(),
+        ]);
+        if config.admin_token.is_some() {
+            routes.extend([
+                "/admin/refresh_views".to_string(),
+                "/admin/trigger_grade".to_string(),
+                "/admin/jobs".to_string(),
+                "/admin/jobs/{id}/cancel".to_string(),
+            ]);
+        }
+    }
     let response = IndexResponse {
         status: "OK".to_string(),
-        routes: Vec::from([
-            "/".to_string(),
-            "/list_platforms".to_string(),
-            "/list_markets".to_string(),
-            "/calibration_plot".to_string(),
-            "/accuracy_plot".to_string(),
-            "/group_accuracy".to_string(),
-        ]),
+        read_only: config.read_only,
+        methodology_version: METHODOLOGY_VERSION,
+        schema_version: SCHEMA_VERSION,
+        routes,
     };
     Ok(HttpResponse::Ok().json(response))
 }
@@ -71,6 +206,21 @@ async fn list_platforms(
 #[get("/list_markets")]
 async fn list_markets(
     query: Query<MarketListQueryParams>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // send to client
+    build_market_list(query, &exclusions, conn)
+}
+
+#[get("/recently_resolved")]
+async fn recently_resolved(
+    query: Query<RecentlyResolvedQueryParams>,
     pool: Data<Pool<ConnectionManager<PgConnection>>>,
 ) -> Result<HttpResponse, ApiError> {
     // get database connection from pool
@@ -79,12 +229,14 @@ async fn list_markets(
         .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
 
     // send to client
-    build_market_list(query, conn)
+    build_recently_resolved(query, conn)
 }
 
 #[get("/calibration_plot")]
 async fn calibration_plot(
     query: Query<CalibrationQueryParams>,
+    criteria: Data<CriteriaConfig>,
+    exclusions: Data<ExclusionList>,
     pool: Data<Pool<ConnectionManager<PgConnection>>>,
 ) -> Result<HttpResponse, ApiError> {
     // get database connection from pool
@@ -93,12 +245,31 @@ async fn calibration_plot(
         .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
 
     // build the plot
-    build_calibration_plot(query, conn)
+    build_calibration_plot(query, None, &criteria, &exclusions, conn)
+}
+
+#[post("/calibration_plot")]
+async fn calibration_plot_selected(
+    query: Query<CalibrationQueryParams>,
+    body: Json<MarketSelection>,
+    criteria: Data<CriteriaConfig>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // build the plot from only the selected markets
+    build_calibration_plot(query, Some(body.into_inner()), &criteria, &exclusions, conn)
 }
 
 #[get("/accuracy_plot")]
 async fn accuracy_plot(
     query: Query<AccuracyQueryParams>,
+    criteria: Data<CriteriaConfig>,
+    exclusions: Data<ExclusionList>,
     pool: Data<Pool<ConnectionManager<PgConnection>>>,
 ) -> Result<HttpResponse, ApiError> {
     // get database connection from pool
@@ -107,12 +278,47 @@ async fn accuracy_plot(
         .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
 
     // build the plot
-    build_accuracy_plot(query, conn)
+    build_accuracy_plot(query, None, &criteria, &exclusions, conn)
+}
+
+#[post("/accuracy_plot")]
+async fn accuracy_plot_selected(
+    query: Query<AccuracyQueryParams>,
+    body: Json<MarketSelection>,
+    criteria: Data<CriteriaConfig>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // build the plot from only the selected markets
+    build_accuracy_plot(query, Some(body.into_inner()), &criteria, &exclusions, conn)
+}
+
+#[get("/criteria")]
+async fn list_criteria(criteria: Data<CriteriaConfig>) -> Result<HttpResponse, ApiError> {
+    // send to client
+    build_criteria_list(&criteria)
+}
+
+#[get("/exclusions")]
+async fn list_exclusions(exclusions: Data<ExclusionList>) -> Result<HttpResponse, ApiError> {
+    // send to client
+    build_exclusion_list(&exclusions)
+}
+
+#[get("/score_types")]
+async fn list_score_types() -> Result<HttpResponse, ApiError> {
+    // send to client
+    build_score_type_list()
 }
 
 #[get("/group_accuracy")]
 async fn group_accuracy(
-    //query: Query<AccuracyQueryParams>,
+    query: Query<GroupQueryParams>,
     pool: Data<Pool<ConnectionManager<PgConnection>>>,
 ) -> Result<HttpResponse, ApiError> {
     // get database connection from pool
@@ -121,12 +327,342 @@ async fn group_accuracy(
         .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
 
     // build the plot
-    build_group_comparison(conn)
+    build_group_comparison(query, conn)
+}
+
+#[get("/group/{key:.+}/markets")]
+async fn group_markets(
+    path: Path<String>,
+    query: Query<GroupQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // get the group and its markets
+    build_group_markets(&path.into_inner(), query, conn)
+}
+
+#[post("/score_forecasts")]
+async fn score_forecasts(
+    req: HttpRequest,
+    body: String,
+    query: Query<GroupQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // score the uploaded forecasts against their markets
+    build_forecast_scores(req.content_type(), &body, query, conn)
+}
+
+#[get("/cohorts")]
+async fn list_cohorts(
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // get all cohorts from database
+    let cohorts = get_all_cohorts(conn)?;
+
+    // send to client
+    Ok(HttpResponse::Ok().json(cohorts))
+}
+
+#[post("/cohorts")]
+async fn create_cohort(
+    req: HttpRequest,
+    query: Query<CommonFilterParams>,
+    body: Json<CohortCreateBody>,
+    config: Data<ServerConfig>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // save the cohort
+    build_cohort_create(query, body.into_inner(), conn)
+}
+
+#[get("/cohort/{id}/scores")]
+async fn cohort_scores(
+    path: Path<i32>,
+    query: Query<CohortScoresQueryParams>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // score the cohort
+    build_cohort_scores(path.into_inner(), query, &exclusions, conn)
+}
+
+#[get("/cohort/{id}/compare")]
+async fn cohort_compare(
+    path: Path<i32>,
+    query: Query<CohortCompareQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // compare two recorded snapshots
+    build_cohort_compare(path.into_inner(), query, conn)
+}
+
+#[get("/freshness")]
+async fn check_freshness(
+    query: Query<FreshnessQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // check each stage
+    build_freshness(query, conn)
+}
+
+#[get("/period_scores")]
+async fn period_scores(
+    query: Query<PeriodScoreQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // get the saved scores
+    build_period_scores(query, conn)
+}
+
+#[get("/ratings")]
+async fn platform_ratings(
+    query: Query<RatingQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // get the saved ratings
+    build_ratings(query, conn)
+}
+
+#[get("/trader_leaderboard")]
+async fn trader_leaderboard(
+    query: Query<TraderScoreQueryParams>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // get the saved trader scores
+    build_trader_leaderboard(query, conn)
+}
+
+#[post("/cohort/{id}/scores")]
+async fn record_cohort_scores(
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<MethodologyQueryParams>,
+    config: Data<ServerConfig>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // score the cohort and save it to the history
+    build_cohort_score_record(path.into_inner(), query, &exclusions, conn)
+}
+
+#[post("/suggest")]
+async fn suggest(
+    req: HttpRequest,
+    body: Json<SuggestionBody>,
+    limiter: Data<RateLimiter>,
+    config: Data<ServerConfig>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // check and save the suggestion
+    let client = get_client_addr(&req, &config.trusted_proxies);
+    build_suggestion(body.into_inner(), client, &limiter, conn)
+}
+
+#[post("/admin/refresh_views")]
+async fn admin_refresh_views(
+    req: HttpRequest,
+    config: Data<ServerConfig>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // check the group links again
+    build_refresh_views(conn)
+}
+
+#[post("/admin/trigger_grade")]
+async fn admin_trigger_grade(
+    req: HttpRequest,
+    query: Query<GradeQueryParams>,
+    config: Data<ServerConfig>,
+    exclusions: Data<ExclusionList>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // record cohort scores in the background
+    build_trigger_grade(query, pool.get_ref().clone(), exclusions.into_inner())
+}
+
+#[get("/admin/jobs")]
+async fn admin_jobs(
+    req: HttpRequest,
+    config: Data<ServerConfig>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // list recent jobs
+    build_job_list(conn)
+}
+
+#[post("/admin/jobs/{id}/cancel")]
+async fn admin_cancel_job(
+    req: HttpRequest,
+    path: Path<i32>,
+    config: Data<ServerConfig>,
+    pool: Data<Pool<ConnectionManager<PgConnection>>>,
+) -> Result<HttpResponse, ApiError> {
+    check_admin_token(&req, &config)?;
+
+    // get database connection from pool
+    let conn = &mut pool
+        .get()
+        .map_err(|e| ApiError::new(500, format!("failed to get connection from pool: {e}")))?;
+
+    // clear the stuck job
+    build_job_cancel(path.into_inner(), conn)
+}
+
+/// Routes that only read from the database, available in every deployment.
+fn configure_read_routes(cfg: &mut ServiceConfig) {
+    cfg.service(list_routes)
+        .service(list_platforms)
+        .service(list_markets)
+        .service(recently_resolved)
+        .service(calibration_plot)
+        .service(calibration_plot_selected)
+        .service(accuracy_plot)
+        .service(accuracy_plot_selected)
+        .service(list_criteria)
+        .service(list_score_types)
+        .service(list_cohorts)
+        .service(cohort_scores)
+        .service(cohort_compare)
+        .service(check_freshness)
+        .service(period_scores)
+        .service(platform_ratings)
+        .service(trader_leaderboard);
+}
+
+/// Routes that write to the database or read the group mapping and exclusion files.
+fn configure_write_routes(cfg: &mut ServiceConfig) {
+    cfg.service(list_exclusions)
+        .service(group_accuracy)
+        .service(group_markets)
+        .service(score_forecasts)
+        .service(suggest);
+}
+
+/// Routes that let curators trigger pipeline work or save cohorts and their scores,
+/// only available when an admin token is set.
+fn configure_admin_routes(cfg: &mut ServiceConfig) {
+    cfg.service(create_cohort)
+        .service(record_cohort_scores)
+        .service(admin_refresh_views)
+        .service(admin_trigger_grade)
+        .service(admin_jobs)
+        .service(admin_cancel_job);
+}
+
+/// Register the routes enabled by the deployment options.
+/// A read-only mirror only gets the read routes, and the admin routes also need a token.
+fn configure_routes(cfg: &mut ServiceConfig, config: &ServerConfig) {
+    configure_read_routes(cfg);
+    if !config.read_only {
+        configure_write_routes(cfg);
+    }
+    if !config.read_only && config.admin_token.is_some() {
+        configure_admin_routes(cfg);
+    }
 }
 
 /// Server startup tasks.
 #[actix_web::main]
 async fn main() -> Result<(), std::io::Error> {
+    // grade markets from a file and exit instead of serving, see the README
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--grade-offline") {
+        let [_, _, input, output_dir] = args.as_slice() else {
+            eprintln!("Usage: {} --grade-offline <INPUT> <OUTPUT_DIR>", args[0]);
+            std::process::exit(2);
+        };
+        match grade_offline(
+            std::path::Path::new(input),
+            std::path::Path::new(output_dir),
+        ) {
+            Ok(cost) => println!("{}", serde_json::to_string_pretty(&cost).unwrap()),
+            Err(e) => {
+                eprintln!("Offline grade failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // build database pool
     let database_url =
         var("DATABASE_URL").expect("Required environment variable DATABASE_URL not set.");
@@ -135,23 +671,191 @@ async fn main() -> Result<(), std::io::Error> {
         .build(manager)
         .expect("Failed to create database connection pool.");
 
+    // export every saved score and exit instead of serving, see the README
+    if args.get(1).map(String::as_str) == Some("--export") {
+        let (output_dir, methodology_version) = match args.as_slice() {
+            [_, _, output_dir] => (output_dir, None),
+            [_, _, output_dir, version] => match version.parse() {
+                Ok(version) => (output_dir, Some(version)),
+                Err(_) => {
+                    eprintln!("Invalid methodology version: {version}");
+                    std::process::exit(2);
+                }
+            },
+            _ => {
+                eprintln!(
+                    "Usage: {} --export <OUTPUT_DIR> [METHODOLOGY_VERSION]",
+                    args[0]
+                );
+                std::process::exit(2);
+            }
+        };
+        let result = get_methodology_version(methodology_version).and_then(|version| {
+            let mut conn = pool
+                .get()
+                .map_err(|e| ApiError::new(500, format!("failed to get connection: {e}")))?;
+            let exclusions = ExclusionList::load()?;
+            export_scores(
+                &mut conn,
+                &exclusions,
+                version,
+                std::path::Path::new(output_dir),
+            )
+        });
+        match result {
+            Ok(manifest) => println!("{}", serde_json::to_string_pretty(&manifest).unwrap()),
+            Err(e) => {
+                eprintln!("Export failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let config = ServerConfig::from_env();
+
+    // check the group mapping file so curation mistakes show up before a request fails
+    if !config.read_only {
+        let mut conn = pool
+            .get()
+            .expect("Failed to get database connection from pool.");
+        match validate_groups(&mut conn) {
+            Ok(problems) => {
+                for problem in problems {
+                    eprintln!("Group validation: {problem}");
+                }
+            }
+            Err(e) => eprintln!("Group validation: {e}"),
+        }
+    }
+
+    // a read-only mirror has no local files, so it excludes nothing and offers the default points
+    let exclusions = match config.read_only {
+        true => ExclusionList::default(),
+        false => ExclusionList::load().expect("Failed to load the exclusion list."),
+    };
+    let exclusions = Data::new(exclusions);
+    let criteria = match config.read_only {
+        true => CriteriaConfig::default(),
+        false => load_criteria_config().expect("Failed to load the criteria config."),
+    };
+    let criteria = Data::new(criteria);
+
     // set up logging
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+    // shared across workers so the limit applies to the whole server
+    let suggestion_limiter = Data::new(RateLimiter::default());
+
     // start the actual server
     HttpServer::new(move || {
         App::new()
             .app_data(Data::new(pool.clone()))
+            .app_data(suggestion_limiter.clone())
+            .app_data(Data::new(config.clone()))
+            .app_data(exclusions.clone())
+            .app_data(criteria.clone())
             .wrap(actix_cors::Cors::permissive())
             .wrap(middleware::Logger::default())
-            .service(list_routes)
-            .service(list_platforms)
-            .service(list_markets)
-            .service(calibration_plot)
-            .service(accuracy_plot)
-            .service(group_accuracy)
+            .wrap(
+                middleware::DefaultHeaders::new()
+                    .add(("Schema-Version", SCHEMA_VERSION.to_string())),
+            )
+            .configure(|cfg| configure_routes(cfg, &config))
     })
     .bind(var("HTTP_BIND").unwrap_or(String::from("0.0.0.0:7041")))?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_response_matches_snapshot() {
+        let response = IndexResponse {
+            status: "OK".to_string(),
+            read_only: true,
+            methodology_version: 2,
+            schema_version: SCHEMA_VERSION,
+            routes: Vec::from(["/".to_string(), "/list_platforms".to_string()]),
+        };
+        let expected = serde_json::json!({
+            "status": "OK",
+            "read_only": true,
+            "methodology_version": 2,
+            "schema_version": 1,
+            "routes": ["/", "/list_platforms"],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+    /// Build the app the same way as `main` and send it one request, returning the status.
+    /// The pool never connects since none of the routes checked here reach the database.
+    async fn get_status(config: &ServerConfig, method: &str, path: &str) -> u16 {
+        let pool: Pool<ConnectionManager<PgConnection>> =
+            Pool::builder().build_unchecked(ConnectionManager::new("postgres://unused"));
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(Data::new(pool))
+                .app_data(Data::new(RateLimiter::default()))
+                .app_data(Data::new(config.clone()))
+                .app_data(Data::new(ExclusionList::default()))
+                .app_data(Data::new(CriteriaConfig::default()))
+                .configure(|cfg| configure_routes(cfg, config)),
+        )
+        .await;
+        let request = match method {
+            "POST" => actix_web::test::TestRequest::post(),
+            _ => actix_web::test::TestRequest::get(),
+        };
+        actix_web::test::call_service(&app, request.uri(path).to_request())
+            .await
+            .status()
+            .as_u16()
+    }
+
+    /// Every write and admin route, as (method, path).
+    const RESTRICTED_ROUTES: [(&str, &str); 11] = [
+        ("GET", "/exclusions"),
+        ("GET", "/group_accuracy"),
+        ("GET", "/group/manifold/abc123/markets"),
+        ("POST", "/score_forecasts"),
+        ("POST", "/suggest"),
+        ("POST", "/cohorts"),
+        ("POST", "/cohort/1/scores"),
+        ("POST", "/admin/refresh_views"),
+        ("POST", "/admin/trigger_grade"),
+        ("GET", "/admin/jobs"),
+        ("POST", "/admin/jobs/1/cancel"),
+    ];
+
+    #[actix_web::test]
+    async fn read_only_hides_write_and_admin_routes() {
+        let config = ServerConfig {
+            read_only: true,
+            admin_token: Some("secret".to_string()),
+            trusted_proxies: Vec::new(),
+        };
+        for (method, path) in RESTRICTED_ROUTES {
+            assert_eq!(
+                get_status(&config, method, path).await,
+                404,
+                "{method} {path}"
+            );
+        }
+        assert_eq!(get_status(&config, "GET", "/").await, 200);
+    }
+
+    #[actix_web::test]
+    async fn writable_serves_write_and_admin_routes() {
+        let config = ServerConfig {
+            read_only: false,
+            admin_token: Some("secret".to_string()),
+            trusted_proxies: Vec::new(),
+        };
+        assert_eq!(get_status(&config, "GET", "/exclusions").await, 200);
+        // admin routes are registered but refuse requests without the token
+        assert_eq!(get_status(&config, "GET", "/admin/jobs").await, 401);
+    }
+}
@@ -66,18 +66,55 @@ struct PlotMetadata {
 /// Full response for a plot.
 #[derive(Debug, Serialize)]
 struct AccuracyPlotResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
     query: AccuracyQueryParams,
     metadata: PlotMetadata,
     traces: Vec<Trace>,
 }
 
 /// A selector for how to score each market.
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "String", into = "String")]
 pub enum ScoringAttribute {
     ProbAtMidpoint,
     ProbAtClose,
     ProbTimeAvg,
+    /// One of the points from the criteria config file.
+    Point(CriterionPoint),
+    /// The Brier score of every daily probability instead of a single one.
+    TimeIntegrated(DayWeighting),
+}
+impl TryFrom<String> for ScoringAttribute {
+    type Error = String;
+    fn try_from(id: String) -> Result<ScoringAttribute, String> {
+        match id.as_str() {
+            "prob_at_midpoint" => Ok(ScoringAttribute::ProbAtMidpoint),
+            "prob_at_close" => Ok(ScoringAttribute::ProbAtClose),
+            "prob_time_avg" => Ok(ScoringAttribute::ProbTimeAvg),
+            "prob_each_date" => Ok(ScoringAttribute::TimeIntegrated(DayWeighting::Uniform)),
+            "prob_each_date_early" => Ok(ScoringAttribute::TimeIntegrated(DayWeighting::Early)),
+            "prob_each_date_late" => Ok(ScoringAttribute::TimeIntegrated(DayWeighting::Late)),
+            _ => CriterionPoint::parse(&id)
+                .map(ScoringAttribute::Point)
+                .ok_or(format!("unknown scoring attribute `{id}`")),
+        }
+    }
+}
+impl From<ScoringAttribute> for String {
+    fn from(attribute: ScoringAttribute) -> String {
+        match attribute {
+            ScoringAttribute::ProbAtMidpoint => "prob_at_midpoint".to_string(),
+            ScoringAttribute::ProbAtClose => "prob_at_close".to_string(),
+            ScoringAttribute::ProbTimeAvg => "prob_time_avg".to_string(),
+            ScoringAttribute::Point(point) => point.id(),
+            ScoringAttribute::TimeIntegrated(weighting) => match weighting {
+                DayWeighting::Uniform => "prob_each_date".to_string(),
+                DayWeighting::Early => "prob_each_date_early".to_string(),
+                DayWeighting::Late => "prob_each_date_late".to_string(),
+            },
+        }
+    }
 }
 pub trait YAxisMethods {
     /// Get the Brier score from the given reference point.
@@ -97,6 +134,8 @@ impl YAxisMethods for ScoringAttribute {
             }
             ScoringAttribute::ProbAtClose => self.get_brier_score(market, &market.prob_at_close),
             ScoringAttribute::ProbTimeAvg => self.get_brier_score(market, &market.prob_time_avg),
+            ScoringAttribute::Point(point) => self.get_brier_score(market, &point.get_prob(market)),
+            ScoringAttribute::TimeIntegrated(weighting) => market.brier_time_integrated(*weighting),
         }
     }
     fn get_title(&self) -> String {
@@ -106,6 +145,14 @@ impl YAxisMethods for ScoringAttribute {
             ScoringAttribute::ProbTimeAvg => {
                 "Brier Score from Time-Averaged Probability".to_string()
             }
+            ScoringAttribute::Point(point) => format!("Brier Score from {}", point.get_title()),
+            ScoringAttribute::TimeIntegrated(weighting) => match weighting {
+                DayWeighting::Uniform => "Time-Integrated Brier Score".to_string(),
+                DayWeighting::Early => {
+                    "Time-Integrated Brier Score, Early Days Weighted".to_string()
+                }
+                DayWeighting::Late => "Time-Integrated Brier Score, Late Days Weighted".to_string(),
+            },
         }
     }
 }
@@ -120,6 +167,9 @@ pub enum XAxisAttribute {
     OpenDays,
     VolumeUsd,
     NumTraders,
+    ProbDailyStddev,
+    ProbMidpointCrossings,
+    PctToConfidence,
 }
 pub trait XAxisMethods {
     /// Get the option name.
@@ -129,7 +179,7 @@ pub trait XAxisMethods {
     fn get_x_value(&self, market: &Market) -> f32;
 
     /// Get the minimum x-value from the markets.
-    fn get_minimum_x_value(&self, markets: &Vec<Market>) -> Result<f32, ApiError> {
+    fn get_minimum_x_value(&self, markets: &[Market]) -> Result<f32, ApiError> {
         markets
             .iter()
             .map(|market| self.get_x_value(market))
@@ -141,7 +191,7 @@ pub trait XAxisMethods {
     }
 
     /// Get the maximum x-value from the markets.
-    fn get_maximum_x_value(&self, markets: &Vec<Market>) -> Result<f32, ApiError> {
+    fn get_maximum_x_value(&self, markets: &[Market]) -> Result<f32, ApiError> {
         markets
             .iter()
             .map(|market| self.get_x_value(market))
@@ -153,10 +203,10 @@ pub trait XAxisMethods {
     }
 
     /// Get the default minimum to use for the x-axis.
-    fn get_bin_minimum(&self, markets: &Vec<Market>) -> f32;
+    fn get_bin_minimum(&self, markets: &[Market]) -> f32;
 
     /// Get the default maximum to use for the x-axis.
-    fn get_bin_maximum(&self, markets: &Vec<Market>) -> f32;
+    fn get_bin_maximum(&self, markets: &[Market]) -> f32;
 
     /// Generate a point for a market on the scatter plot.
     fn get_scatter_point(
@@ -189,18 +239,27 @@ impl XAxisMethods for XAxisAttribute {
         match self {
             XAxisAttribute::MarketDuration => rand::thread_rng().gen_range(0..100) as f32,
             XAxisAttribute::OpenDate => {
-                (Utc::now() - market.open_dt).num_seconds() as f32 / SECS_PER_DAY * -1.0
+                -((Utc::now() - market.open_dt).num_seconds() as f32 / SECS_PER_DAY)
             }
             XAxisAttribute::CloseDate => {
-                (Utc::now() - market.close_dt).num_seconds() as f32 / SECS_PER_DAY * -1.0
+                -((Utc::now() - market.close_dt).num_seconds() as f32 / SECS_PER_DAY)
             }
             XAxisAttribute::OpenDays => market.open_days,
             XAxisAttribute::VolumeUsd => market.volume_usd,
             XAxisAttribute::NumTraders => market.num_traders as f32,
+            XAxisAttribute::ProbDailyStddev => market.prob_daily_stddev,
+            XAxisAttribute::ProbMidpointCrossings => market.prob_midpoint_crossings as f32,
+            XAxisAttribute::PctToConfidence => match market.days_to_confidence {
+                // markets that were never confident count as the full duration
+                Some(days) if market.open_days > 0.0 => {
+                    (100.0 * days / market.open_days).min(100.0)
+                }
+                _ => 100.0,
+            },
         }
     }
 
-    fn get_bin_minimum(&self, markets: &Vec<Market>) -> f32 {
+    fn get_bin_minimum(&self, markets: &[Market]) -> f32 {
         match self {
             XAxisAttribute::MarketDuration => 0.0,
             XAxisAttribute::OpenDate => self
@@ -214,10 +273,13 @@ impl XAxisMethods for XAxisAttribute {
             XAxisAttribute::OpenDays => 0.0,
             XAxisAttribute::VolumeUsd => 0.0,
             XAxisAttribute::NumTraders => 0.0,
+            XAxisAttribute::ProbDailyStddev => 0.0,
+            XAxisAttribute::ProbMidpointCrossings => 0.0,
+            XAxisAttribute::PctToConfidence => 0.0,
         }
     }
 
-    fn get_bin_maximum(&self, markets: &Vec<Market>) -> f32 {
+    fn get_bin_maximum(&self, markets: &[Market]) -> f32 {
         match self {
             XAxisAttribute::MarketDuration => 100.0,
             XAxisAttribute::OpenDate => 0.0,
@@ -233,6 +295,11 @@ impl XAxisMethods for XAxisAttribute {
             XAxisAttribute::NumTraders => {
                 self.get_maximum_x_value(markets).unwrap_or(60.0).min(60.0)
             }
+            XAxisAttribute::ProbDailyStddev => 0.5,
+            XAxisAttribute::ProbMidpointCrossings => {
+                self.get_maximum_x_value(markets).unwrap_or(25.0).min(25.0)
+            }
+            XAxisAttribute::PctToConfidence => 100.0,
         }
     }
 
@@ -278,7 +345,7 @@ impl XAxisMethods for XAxisAttribute {
             XAxisAttribute::MarketDuration => {
                 // this is a hot loop since we iterate over all markets AND all bins
                 for bin in bins {
-                    let x_value = bin.middle.clone() as usize;
+                    let x_value = bin.middle as usize;
                     for market in markets.iter() {
                         let y_value = market.prob_each_pct.get(x_value).unwrap();
                         bin.brier_sum += scoring_attribute.get_brier_score(market, y_value);
@@ -313,6 +380,13 @@ impl XAxisMethods for XAxisAttribute {
             XAxisAttribute::OpenDays => "Market Open Length (days)".to_string(),
             XAxisAttribute::VolumeUsd => "Market Volume (USD)".to_string(),
             XAxisAttribute::NumTraders => "Number of Unique Traders".to_string(),
+            XAxisAttribute::ProbDailyStddev => {
+                "Standard Deviation of Daily Probability".to_string()
+            }
+            XAxisAttribute::ProbMidpointCrossings => "Number of Times Crossing 50%".to_string(),
+            XAxisAttribute::PctToConfidence => {
+                "Time Until Confident (percent of duration)".to_string()
+            }
         }
     }
 
@@ -324,6 +398,9 @@ impl XAxisMethods for XAxisAttribute {
             XAxisAttribute::OpenDays => "days".to_string(),
             XAxisAttribute::VolumeUsd => "USD".to_string(),
             XAxisAttribute::NumTraders => "traders".to_string(),
+            XAxisAttribute::ProbDailyStddev => "probability".to_string(),
+            XAxisAttribute::ProbMidpointCrossings => "crossings".to_string(),
+            XAxisAttribute::PctToConfidence => "percent".to_string(),
         }
     }
 }
@@ -351,12 +428,25 @@ fn generate_xaxis_bins(min: f32, max: f32, count: usize) -> Result<Vec<XAxisBin>
 /// Takes a set of markets and generates calibration plots for each.
 pub fn build_accuracy_plot(
     query: Query<AccuracyQueryParams>,
+    market_select: Option<MarketSelection>,
+    criteria: &CriteriaConfig,
+    excluded: &ExclusionList,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> Result<HttpResponse, ApiError> {
+    // make sure a point criterion is still in the config file
+    if let ScoringAttribute::Point(point) = &query.scoring_attribute {
+        criteria.check(point)?;
+    }
     // get rng thread
     let mut rng = rand::thread_rng();
     // get markets from database
-    let (markets, _) = get_markets_filtered(conn, Some(&query.filters), None)?;
+    let (markets, _) = get_markets_filtered(
+        conn,
+        excluded,
+        Some(&query.filters),
+        None,
+        market_select.as_ref(),
+    )?;
     // get maximum value for x-axis bins
     let bin_minimum = query.xaxis_attribute.get_bin_minimum(&markets);
     let bin_maximum = query.xaxis_attribute.get_bin_maximum(&markets);
@@ -440,6 +530,7 @@ pub fn build_accuracy_plot(
     };
 
     let response = AccuracyPlotResponse {
+        schema_version: SCHEMA_VERSION,
         query: query.into_inner(),
         metadata,
         traces,
@@ -447,3 +538,106 @@ pub fn build_accuracy_plot(
 
     Ok(HttpResponse::Ok().json(response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_platform() -> Platform {
+        Platform {
+            name: "manifold".to_string(),
+            name_fmt: "Manifold".to_string(),
+            description: "A play-money market".to_string(),
+            avatar_url: "/images/manifold.svg".to_string(),
+            site_url: "https://manifold.markets".to_string(),
+            color: "#4337c9".to_string(),
+            color_accent: "#8c84f5".to_string(),
+            capabilities: serde_json::json!({}),
+        }
+    }
+
+    fn sample_platform_json() -> serde_json::Value {
+        serde_json::json!({
+            "name": "manifold",
+            "name_fmt": "Manifold",
+            "description": "A play-money market",
+            "avatar_url": "/images/manifold.svg",
+            "site_url": "https://manifold.markets",
+            "color": "#4337c9",
+            "color_accent": "#8c84f5",
+            "capabilities": {},
+        })
+    }
+
+    /// The echoed query with every filter unset, see the snapshot in `market_list`.
+    fn query_json(fields: serde_json::Value) -> serde_json::Value {
+        let mut query = serde_json::to_value(CommonFilterParams::default()).unwrap();
+        let query_fields = query.as_object_mut().unwrap();
+        query_fields.extend(fields.as_object().unwrap().clone());
+        query
+    }
+
+    #[test]
+    fn accuracy_plot_response_matches_snapshot() {
+        let query = Query::<AccuracyQueryParams>::from_query("scoring_attribute=prob_at_close")
+            .unwrap()
+            .into_inner();
+        let response = AccuracyPlotResponse {
+            schema_version: SCHEMA_VERSION,
+            query,
+            metadata: PlotMetadata {
+                title: "Accuracy".to_string(),
+                x_title: "Open Days".to_string(),
+                x_min: 0.0,
+                x_max: 12.5,
+                y_title: "Brier Score".to_string(),
+            },
+            traces: Vec::from([Trace {
+                platform: sample_platform(),
+                market_points: Vec::from([Point {
+                    x: 2.0,
+                    y: 0.25,
+                    point_title: Some("Will it rain?".to_string()),
+                    point_label: "Brier: 0.25".to_string(),
+                }]),
+                accuracy_line: Vec::from([Point {
+                    x: 1.5,
+                    y: 0.125,
+                    point_title: None,
+                    point_label: "Average: 0.125".to_string(),
+                }]),
+            }]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "query": query_json(serde_json::json!({
+                "scoring_attribute": "prob_at_close",
+                "xaxis_attribute": "open_days",
+                "num_market_points": 1000,
+            })),
+            "metadata": {
+                "title": "Accuracy",
+                "x_title": "Open Days",
+                "x_min": 0.0,
+                "x_max": 12.5,
+                "y_title": "Brier Score",
+            },
+            "traces": [{
+                "platform": sample_platform_json(),
+                "market_points": [{
+                    "x": 2.0,
+                    "y": 0.25,
+                    "point_title": "Will it rain?",
+                    "point_label": "Brier: 0.25",
+                }],
+                "accuracy_line": [{
+                    "x": 1.5,
+                    "y": 0.125,
+                    "point_title": null,
+                    "point_label": "Average: 0.125",
+                }],
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
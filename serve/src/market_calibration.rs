@@ -11,6 +11,7 @@ pub struct CalibrationQueryParams {
     #[serde(default = "default_bin_attribute")]
     bin_attribute: BinAttribute,
     bin_attribute_x_pct: Option<usize>,
+    bin_attribute_x_days: Option<usize>,
     #[serde(default = "default_bin_size")]
     bin_size: f32,
     #[serde(default = "default_weight_attribute")]
@@ -53,6 +54,8 @@ struct Point {
 struct Trace {
     platform: Platform,
     points: Vec<Point>,
+    /// How far the platform is from perfect calibration, with the same criterion and weights.
+    calibration_error: CalibrationError,
 }
 
 /// Metadata to help label a plot.
@@ -66,19 +69,143 @@ struct PlotMetadata {
 /// Full response for a calibration plot.
 #[derive(Debug, Serialize)]
 struct CalibrationPlotResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
     query: CalibrationQueryParams,
     metadata: PlotMetadata,
     traces: Vec<Trace>,
 }
 
+/// A parameter a client must send along with a criterion.
+#[derive(Debug, Serialize)]
+struct CriterionParameter {
+    name: String,
+    description: String,
+    min: usize,
+    max: usize,
+}
+
+/// Definition of a probability criterion, used to populate client selectors.
+#[derive(Debug, Serialize)]
+struct CriterionDefinition {
+    id: BinAttribute,
+    title: String,
+    description: String,
+    parameters: Vec<CriterionParameter>,
+}
+
 /// A selector for how to bin the markets along the x-axis.
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "String", into = "String")]
 pub enum BinAttribute {
     ProbAtMidpoint,
     ProbAtClose,
     ProbTimeAvg,
     ProbAtPct,
+    ProbAfterOpenDays,
+    ProbMin,
+    ProbMax,
+    /// One of the points from the criteria config file.
+    Point(CriterionPoint),
+}
+impl TryFrom<String> for BinAttribute {
+    type Error = String;
+    fn try_from(id: String) -> Result<BinAttribute, String> {
+        match id.as_str() {
+            "prob_at_midpoint" => Ok(BinAttribute::ProbAtMidpoint),
+            "prob_at_close" => Ok(BinAttribute::ProbAtClose),
+            "prob_time_avg" => Ok(BinAttribute::ProbTimeAvg),
+            "prob_at_pct" => Ok(BinAttribute::ProbAtPct),
+            "prob_after_open_days" => Ok(BinAttribute::ProbAfterOpenDays),
+            "prob_min" => Ok(BinAttribute::ProbMin),
+            "prob_max" => Ok(BinAttribute::ProbMax),
+            _ => CriterionPoint::parse(&id)
+                .map(BinAttribute::Point)
+                .ok_or(format!("unknown bin attribute `{id}`")),
+        }
+    }
+}
+impl From<BinAttribute> for String {
+    fn from(attribute: BinAttribute) -> String {
+        match attribute {
+            BinAttribute::ProbAtMidpoint => "prob_at_midpoint".to_string(),
+            BinAttribute::ProbAtClose => "prob_at_close".to_string(),
+            BinAttribute::ProbTimeAvg => "prob_time_avg".to_string(),
+            BinAttribute::ProbAtPct => "prob_at_pct".to_string(),
+            BinAttribute::ProbAfterOpenDays => "prob_after_open_days".to_string(),
+            BinAttribute::ProbMin => "prob_min".to_string(),
+            BinAttribute::ProbMax => "prob_max".to_string(),
+            BinAttribute::Point(point) => point.id(),
+        }
+    }
+}
+impl BinAttribute {
+    /// Every criterion, in the order they should be shown to users.
+    /// The configured points come after the built-in criteria.
+    fn all(config: &CriteriaConfig) -> Vec<BinAttribute> {
+        let mut criteria = Vec::from([
+            BinAttribute::ProbAtMidpoint,
+            BinAttribute::ProbAtClose,
+            BinAttribute::ProbTimeAvg,
+            BinAttribute::ProbAtPct,
+            BinAttribute::ProbAfterOpenDays,
+            BinAttribute::ProbMin,
+            BinAttribute::ProbMax,
+        ]);
+        criteria.extend(config.points().into_iter().map(BinAttribute::Point));
+        criteria
+    }
+    /// Get a plain-language definition of how the probability is taken.
+    fn get_description(&self) -> String {
+        match self {
+            BinAttribute::ProbAtMidpoint => {
+                "The market probability at the halfway point between the open and close times."
+                    .to_string()
+            }
+            BinAttribute::ProbAtClose => {
+                "The last market probability before the market closed.".to_string()
+            }
+            BinAttribute::ProbTimeAvg => {
+                "The market probability averaged over its entire duration, weighted by time."
+                    .to_string()
+            }
+            BinAttribute::ProbAtPct => {
+                "The market probability at a chosen percentage of the way through its duration."
+                    .to_string()
+            }
+            BinAttribute::ProbAfterOpenDays => {
+                "The market probability a chosen number of days after it opened, or at close if it closed sooner."
+                    .to_string()
+            }
+            BinAttribute::ProbMin => {
+                "The lowest market probability reached at any point while it was open.".to_string()
+            }
+            BinAttribute::ProbMax => {
+                "The highest market probability reached at any point while it was open."
+                    .to_string()
+            }
+            BinAttribute::Point(point) => point.get_description(),
+        }
+    }
+    /// Get any extra parameters needed to use this criterion.
+    fn get_parameters(&self) -> Vec<CriterionParameter> {
+        match self {
+            BinAttribute::ProbAtPct => Vec::from([CriterionParameter {
+                name: "bin_attribute_x_pct".to_string(),
+                description: "Percent of the market duration to take the probability at."
+                    .to_string(),
+                min: 0,
+                max: 100,
+            }]),
+            BinAttribute::ProbAfterOpenDays => Vec::from([CriterionParameter {
+                name: "bin_attribute_x_days".to_string(),
+                description: "Days after the market opened to take the probability at.".to_string(),
+                min: 0,
+                max: 365,
+            }]),
+            _ => Vec::new(),
+        }
+    }
 }
 pub trait XAxisMethods {
     /// Get the value to use for the x-axis (bin).
@@ -86,20 +213,29 @@ pub trait XAxisMethods {
         &self,
         market: &Market,
         bin_attribute_x_pct: Option<usize>,
+        bin_attribute_x_days: Option<usize>,
     ) -> Result<f32, ApiError>;
     /// Get the title to use for the y-axis.
-    fn get_title(&self, bin_attribute_x_pct: Option<usize>) -> String;
+    fn get_title(
+        &self,
+        bin_attribute_x_pct: Option<usize>,
+        bin_attribute_x_days: Option<usize>,
+    ) -> String;
 }
 impl XAxisMethods for BinAttribute {
     fn get_x_value(
         &self,
         market: &Market,
         bin_attribute_x_pct: Option<usize>,
+        bin_attribute_x_days: Option<usize>,
     ) -> Result<f32, ApiError> {
         match self {
             BinAttribute::ProbAtMidpoint => Ok(market.prob_at_midpoint),
             BinAttribute::ProbAtClose => Ok(market.prob_at_close),
             BinAttribute::ProbTimeAvg => Ok(market.prob_time_avg),
+            BinAttribute::ProbMin => Ok(market.prob_min),
+            BinAttribute::ProbMax => Ok(market.prob_max),
+            BinAttribute::Point(point) => Ok(point.get_prob(market)),
             BinAttribute::ProbAtPct => match bin_attribute_x_pct {
                 Some(pct) => match market.prob_each_pct.get(pct) {
                     Some(x_value) => Ok(x_value.to_owned()),
@@ -118,17 +254,36 @@ impl XAxisMethods for BinAttribute {
                             .to_string(),
                 }),
             },
+            BinAttribute::ProbAfterOpenDays => match bin_attribute_x_days {
+                Some(days) => Ok(market.prob_after_open_days(days as i64)),
+                None => Err(ApiError::new(
+                    400,
+                    "Value for `bin_attribute_x_days` is required when `prob_after_open_days` is set."
+                        .to_string(),
+                )),
+            },
         }
     }
-    fn get_title(&self, bin_attribute_x_pct: Option<usize>) -> String {
+    fn get_title(
+        &self,
+        bin_attribute_x_pct: Option<usize>,
+        bin_attribute_x_days: Option<usize>,
+    ) -> String {
         match self {
             BinAttribute::ProbAtMidpoint => "Probability at Market Midpoint".to_string(),
             BinAttribute::ProbAtClose => "Probability at Market Close".to_string(),
             BinAttribute::ProbTimeAvg => "Market Time-Averaged Probability".to_string(),
+            BinAttribute::ProbMin => "Market Minimum Probability".to_string(),
+            BinAttribute::ProbMax => "Market Maximum Probability".to_string(),
+            BinAttribute::Point(point) => point.get_title(),
             BinAttribute::ProbAtPct => match bin_attribute_x_pct {
                 Some(pct) => format!("Probability at {pct}% of Market Duration"),
                 _ => "Probability at User-Defined Percent".to_string(),
             },
+            BinAttribute::ProbAfterOpenDays => match bin_attribute_x_days {
+                Some(days) => format!("Probability {days} Days After Market Open"),
+                _ => "Probability at User-Defined Days After Open".to_string(),
+            },
         }
     }
 }
@@ -193,10 +348,23 @@ fn generate_xaxis_bins(bin_size: &f32) -> Result<Vec<XAxisBin>, ApiError> {
 /// Takes a set of markets and generates calibration plots for each.
 pub fn build_calibration_plot(
     query: Query<CalibrationQueryParams>,
+    market_select: Option<MarketSelection>,
+    criteria: &CriteriaConfig,
+    excluded: &ExclusionList,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> Result<HttpResponse, ApiError> {
+    // make sure a point criterion is still in the config file
+    if let BinAttribute::Point(point) = &query.bin_attribute {
+        criteria.check(point)?;
+    }
     // get markets from database
-    let (markets, _) = get_markets_filtered(conn, Some(&query.filters), None)?;
+    let (markets, _) = get_markets_filtered(
+        conn,
+        excluded,
+        Some(&query.filters),
+        None,
+        market_select.as_ref(),
+    )?;
     // sort by platform
     let markets_by_platform = categorize_markets_by_platform(markets);
 
@@ -204,14 +372,17 @@ pub fn build_calibration_plot(
     for (platform, market_list) in markets_by_platform {
         // generate x-axis bins
         let mut bins = generate_xaxis_bins(&query.bin_size)?;
+        let mut forecasts = Vec::with_capacity(market_list.len());
 
         // get weighted average values for all markets
         // this is a hot loop since we iterate over all markets
         for market in market_list.iter() {
             // get specified market values
-            let market_x_value = query
-                .bin_attribute
-                .get_x_value(market, query.bin_attribute_x_pct)?;
+            let market_x_value = query.bin_attribute.get_x_value(
+                market,
+                query.bin_attribute_x_pct,
+                query.bin_attribute_x_days,
+            )?;
             let market_y_value = query.weight_attribute.get_y_value(market);
             let market_weight_value = query.weight_attribute.get_weight(market);
 
@@ -231,6 +402,7 @@ pub fn build_calibration_plot(
             bin.y_axis_numerator += market_weight_value * market_y_value;
             bin.y_axis_denominator += market_weight_value;
             bin.count += 1;
+            forecasts.push((market_x_value, market_y_value, market_weight_value));
         }
 
         // get platform data
@@ -268,7 +440,11 @@ pub fn build_calibration_plot(
             .collect();
 
         // save it all to the trace and push it to result
-        traces.push(Trace { platform, points })
+        traces.push(Trace {
+            platform,
+            points,
+            calibration_error: get_calibration_error(forecasts.into_iter()),
+        })
     }
 
     // sort the market lists by platform name so it's consistent
@@ -277,11 +453,14 @@ pub fn build_calibration_plot(
     // get plot and axis titles
     let metadata = PlotMetadata {
         title: "Calibration Plot".to_string(),
-        x_title: query.bin_attribute.get_title(query.bin_attribute_x_pct),
+        x_title: query
+            .bin_attribute
+            .get_title(query.bin_attribute_x_pct, query.bin_attribute_x_days),
         y_title: query.weight_attribute.get_title(),
     };
 
     let response = CalibrationPlotResponse {
+        schema_version: SCHEMA_VERSION,
         query: query.into_inner(),
         metadata,
         traces,
@@ -289,3 +468,115 @@ pub fn build_calibration_plot(
 
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// Lists every probability criterion with its definition and parameters,
+/// including the points from the criteria config file.
+pub fn build_criteria_list(config: &CriteriaConfig) -> Result<HttpResponse, ApiError> {
+    let criteria: Vec<CriterionDefinition> = BinAttribute::all(config)
+        .into_iter()
+        .map(|attribute| CriterionDefinition {
+            title: attribute.get_title(None, None),
+            description: attribute.get_description(),
+            parameters: attribute.get_parameters(),
+            id: attribute,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(criteria))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_platform() -> Platform {
+        Platform {
+            name: "manifold".to_string(),
+            name_fmt: "Manifold".to_string(),
+            description: "A play-money market".to_string(),
+            avatar_url: "/images/manifold.svg".to_string(),
+            site_url: "https://manifold.markets".to_string(),
+            color: "#4337c9".to_string(),
+            color_accent: "#8c84f5".to_string(),
+            capabilities: serde_json::json!({}),
+        }
+    }
+
+    fn sample_platform_json() -> serde_json::Value {
+        serde_json::json!({
+            "name": "manifold",
+            "name_fmt": "Manifold",
+            "description": "A play-money market",
+            "avatar_url": "/images/manifold.svg",
+            "site_url": "https://manifold.markets",
+            "color": "#4337c9",
+            "color_accent": "#8c84f5",
+            "capabilities": {},
+        })
+    }
+
+    /// The echoed query with every filter unset, see the snapshot in `market_list`.
+    fn query_json(fields: serde_json::Value) -> serde_json::Value {
+        let mut query = serde_json::to_value(CommonFilterParams::default()).unwrap();
+        let query_fields = query.as_object_mut().unwrap();
+        query_fields.extend(fields.as_object().unwrap().clone());
+        query
+    }
+
+    #[test]
+    fn calibration_plot_response_matches_snapshot() {
+        let query = Query::<CalibrationQueryParams>::from_query("bin_size=0.125")
+            .unwrap()
+            .into_inner();
+        let response = CalibrationPlotResponse {
+            schema_version: SCHEMA_VERSION,
+            query,
+            metadata: PlotMetadata {
+                title: "Calibration".to_string(),
+                x_title: "Probability at Market Midpoint".to_string(),
+                y_title: "Resolution, Unweighted".to_string(),
+            },
+            traces: Vec::from([Trace {
+                platform: sample_platform(),
+                points: Vec::from([Point {
+                    x: 0.0625,
+                    y: 0.125,
+                    r: 1.5,
+                    point_title: "Manifold".to_string(),
+                    point_label: "4 markets".to_string(),
+                }]),
+                calibration_error: CalibrationError {
+                    expected: 0.0625,
+                    reliability: 0.00390625,
+                },
+            }]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "query": query_json(serde_json::json!({
+                "bin_attribute": "prob_at_midpoint",
+                "bin_attribute_x_pct": null,
+                "bin_attribute_x_days": null,
+                "bin_size": 0.125,
+                "weight_attribute": "none",
+            })),
+            "metadata": {
+                "title": "Calibration",
+                "x_title": "Probability at Market Midpoint",
+                "y_title": "Resolution, Unweighted",
+            },
+            "traces": [{
+                "platform": sample_platform_json(),
+                "points": [{
+                    "x": 0.0625,
+                    "y": 0.125,
+                    "r": 1.5,
+                    "point_title": "Manifold",
+                    "point_label": "4 markets",
+                }],
+                "calibration_error": {"expected": 0.0625, "reliability": 0.00390625},
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
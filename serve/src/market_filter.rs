@@ -4,13 +4,22 @@ use super::*;
 //https://github.com/nox/serde_urlencoded/issues/33
 use serde_with::{serde_as, DisplayFromStr};
 
+const MAX_SELECTED_MARKETS: usize = 10_000;
+/// Platforms whose resolutions are inferred from the final price instead of a settlement.
+/// Scoring these against their own closing prices would be near-perfect by construction,
+/// so their markets are left out of every list, plot, and score.
+pub const UNSETTLED_PLATFORMS: [&str; 1] = ["predictit"];
+
 /// Filter parameters common to all queries.
 #[serde_as]
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Default, Deserialize, Clone, Serialize)]
 pub struct CommonFilterParams {
     title_contains: Option<String>,
+    description_contains: Option<String>,
     platform_select: Option<String>,
     category_select: Option<String>,
+    linked_group_select: Option<String>,
+    region_select: Option<String>,
     #[serde_as(as = "Option<DisplayFromStr>")]
     open_ts_min: Option<i64>,
     #[serde_as(as = "Option<DisplayFromStr>")]
@@ -68,25 +77,91 @@ fn default_limit() -> Option<i64> {
     Some(1000)
 }
 
+/// A single market, identified the same way as in the group mapping file.
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Hash)]
+pub struct MarketRef {
+    pub platform: String,
+    pub platform_id: String,
+}
+
+/// An explicit list of markets sent in a request body, used to plot
+/// subsets that can't be expressed with the common filters.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct MarketSelection {
+    pub markets: Vec<MarketRef>,
+}
+
 /// Build a query from the database, applying filters conditionally.
 /// If no filters are given, this will get all markets.
+/// If a market selection is given, only those markets are considered.
 pub fn get_markets_filtered(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    common_params: Option<&CommonFilterParams>,
+    list_params: Option<&PageSortParams>,
+    market_select: Option<&MarketSelection>,
+) -> Result<(Vec<Market>, usize), ApiError> {
+    // numeric questions can't be scored as probabilities, so leave them out
+    get_markets_filtered_by_type(
+        conn,
+        excluded,
+        &["binary"],
+        common_params,
+        list_params,
+        market_select,
+    )
+}
+
+/// Same as `get_markets_filtered`, but with the question types to include.
+pub fn get_markets_filtered_by_type(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    question_types: &[&str],
     common_params: Option<&CommonFilterParams>,
     list_params: Option<&PageSortParams>,
+    market_select: Option<&MarketSelection>,
 ) -> Result<(Vec<Market>, usize), ApiError> {
-    let mut query = market::table.into_boxed();
+    let mut query = market::table
+        .filter(market::question_type.eq_any(question_types))
+        .filter(market::platform.ne_all(UNSETTLED_PLATFORMS))
+        .into_boxed();
+
+    if let Some(selection) = market_select {
+        if selection.markets.len() > MAX_SELECTED_MARKETS {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "too many markets selected: {} (maximum {MAX_SELECTED_MARKETS})",
+                    selection.markets.len()
+                ),
+            ));
+        }
+        // narrow down by ID here, exact platform matches are checked after loading
+        let platform_ids: Vec<&String> = selection.markets.iter().map(|m| &m.platform_id).collect();
+        query = query.filter(market::platform_id.eq_any(platform_ids))
+    }
 
     if let Some(params) = common_params {
         if let Some(title_contains) = &params.title_contains {
             query = query.filter(market::title.ilike("%".to_string() + title_contains + "%"))
         }
+        if let Some(description_contains) = &params.description_contains {
+            query = query.filter(
+                market::description_excerpt.ilike("%".to_string() + description_contains + "%"),
+            )
+        }
         if let Some(platform_select) = &params.platform_select {
             query = query.filter(market::platform.eq(platform_select))
         }
         if let Some(category_select) = &params.category_select {
             query = query.filter(market::category.eq(category_select))
         }
+        if let Some(region_select) = &params.region_select {
+            query = query.filter(market::region.eq(region_select))
+        }
+        if let Some(linked_group_select) = &params.linked_group_select {
+            query = query.filter(market::linked_group.eq(linked_group_select))
+        }
         if let Some(ts) = params.open_ts_min {
             if let Some(dt) = DateTime::from_timestamp(ts, 0) {
                 query = query.filter(market::open_dt.ge(dt))
@@ -198,6 +273,14 @@ pub fn get_markets_filtered(
                     false => query = query.order(market::close_dt.asc()),
                     true => query = query.order(market::close_dt.desc()),
                 },
+                "trading_end_dt" => match params.sort_desc {
+                    false => query = query.order(market::trading_end_dt.asc()),
+                    true => query = query.order(market::trading_end_dt.desc()),
+                },
+                "resolved_dt" => match params.sort_desc {
+                    false => query = query.order(market::resolved_dt.asc()),
+                    true => query = query.order(market::resolved_dt.desc()),
+                },
                 "open_days" => match params.sort_desc {
                     false => query = query.order(market::open_days.asc()),
                     true => query = query.order(market::open_days.desc()),
@@ -246,7 +329,24 @@ pub fn get_markets_filtered(
     let mut markets = query
         .select(Market::as_select())
         .load::<Market>(conn)
-        .map_err(|e| ApiError::new(500, format!("failed to query markets: {e}")))?;
+        .map_err(|e| ApiError::new(500, format!("failed to query markets: {e}")))?
+        .into_iter()
+        .map(Market::unpack_prob_daily)
+        .collect::<Vec<_>>();
+
+    // drop markets on the exclusion list
+    markets.retain(|m| !excluded.contains(m));
+
+    // drop markets that share an ID with a selected market on another platform
+    if let Some(selection) = market_select {
+        let selected: HashSet<&MarketRef> = selection.markets.iter().collect();
+        markets.retain(|m| {
+            selected.contains(&MarketRef {
+                platform: m.platform.clone(),
+                platform_id: m.platform_id.clone(),
+            })
+        });
+    }
 
     // get the number of markets for pagination
     let count = markets.len();
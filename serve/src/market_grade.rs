@@ -0,0 +1,196 @@
+//! Fingerprints of every market as of the last grade, so an incremental grade only rescores
+//! the markets that changed since then. Binary markets are cheap to score, so only the slow
+//! numeric scores are reused, and the yearly scores are only recomputed where a market changed.
+
+use super::*;
+use chrono::Datelike;
+use std::time::Instant;
+
+/// The markets being graded this run and how they differ from the last grade.
+pub struct GradeState {
+    /// Whether only the changed markets are rescored. This is off for a full grade and for
+    /// the first incremental grade, since there's nothing saved to compare against.
+    pub incremental: bool,
+    /// Every binary market under this methodology, loaded once for the whole run.
+    pub markets: Vec<Market>,
+    /// The fingerprints saved by the last grade.
+    previous: HashMap<(String, String), MarketGrade>,
+    /// The fingerprint of every market now, including the numeric ones.
+    current: HashMap<(String, String), MarketGrade>,
+}
+
+/// Get a stable hash of every market field the grade reads, so any change to one of them
+/// causes the market to be rescored. This uses FNV-1a, which doesn't change between builds.
+pub fn get_content_hash(market: &Market) -> i64 {
+    let content = serde_json::json!([
+        market.category,
+        market.close_dt,
+        market.question_type,
+        market.resolution,
+        market.prob_at_midpoint,
+        market.prob_at_close,
+        market.prob_time_avg,
+        market.quantiles_each_date,
+    ])
+    .to_string();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash as i64
+}
+
+impl GradeState {
+    /// Load every market and compare it against the last grade. The CRPS of each numeric
+    /// market is computed here, once for the whole run, unless it's unchanged and already saved.
+    /// The time spent is added to `cost`.
+    pub fn load(
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        excluded: &ExclusionList,
+        methodology_version: i32,
+        incremental: bool,
+        cost: &mut GradeCost,
+    ) -> Result<GradeState, ApiError> {
+        let start = Instant::now();
+        let previous: HashMap<(String, String), MarketGrade> = match incremental {
+            true => get_market_grades(conn, methodology_version)?
+                .into_iter()
+                .map(|grade| ((grade.platform.clone(), grade.platform_id.clone()), grade))
+                .collect(),
+            false => HashMap::new(),
+        };
+        let incremental = incremental && !previous.is_empty();
+        let filters = CommonFilterParams::default();
+        let markets = get_cohort_markets(conn, excluded, &filters, methodology_version)?;
+        let numeric_markets =
+            get_cohort_numeric_markets(conn, excluded, &filters, methodology_version)?;
+        cost.add_stage("load", start);
+
+        let graded_dt = Utc::now();
+        let mut current = HashMap::new();
+        let numeric = numeric_markets.iter().map(|market| (market, true));
+        for (market, is_numeric) in markets.iter().map(|market| (market, false)).chain(numeric) {
+            let key = (market.platform.clone(), market.platform_id.clone());
+            let content_hash = get_content_hash(market);
+            let saved = previous
+                .get(&key)
+                .filter(|grade| incremental && grade.content_hash == content_hash);
+            if saved.is_none() {
+                cost.num_markets_changed += 1;
+            }
+
+            // each numeric market is integrated over every daily forecast, so time them one at a time
+            let crps_time_avg = match (is_numeric, saved) {
+                (false, _) => None,
+                (true, Some(grade)) => grade.crps_time_avg,
+                (true, None) => {
+                    let start = Instant::now();
+                    let score = market.crps_time_avg();
+                    cost.add_market(market, start);
+                    cost.add_stage("numeric_scores", start);
+                    score
+                }
+            };
+            current.insert(
+                key,
+                MarketGrade {
+                    platform: market.platform.clone(),
+                    platform_id: market.platform_id.clone(),
+                    methodology_version,
+                    content_hash,
+                    category: market.category.clone(),
+                    close_year: market.close_dt.year(),
+                    crps_time_avg,
+                    graded_dt,
+                },
+            );
+        }
+        cost.incremental = incremental;
+        cost.num_markets_removed = previous
+            .keys()
+            .filter(|key| !current.contains_key(*key))
+            .count();
+
+        Ok(GradeState {
+            incremental,
+            markets,
+            previous,
+            current,
+        })
+    }
+
+    /// Get the saved or freshly computed CRPS of a numeric market.
+    pub fn get_crps(&self, market: &Market) -> Option<f32> {
+        self.current
+            .get(&(market.platform.clone(), market.platform_id.clone()))
+            .and_then(|grade| grade.crps_time_avg)
+    }
+
+    /// Check whether a market is new or changed since the last grade.
+    fn is_changed(&self, key: &(String, String)) -> bool {
+        match (self.previous.get(key), self.current.get(key)) {
+            (Some(before), Some(after)) => before.content_hash != after.content_hash,
+            _ => true,
+        }
+    }
+
+    /// List every platform, category, and year whose yearly scores could have changed, which
+    /// are the ones any changed or removed market was counted in, before or after the change.
+    /// Every category together is listed under `All`.
+    pub fn get_changed_periods(&self) -> HashSet<(String, String, i32)> {
+        let changed_after = self
+            .current
+            .iter()
+            .filter(|(key, _)| self.is_changed(key))
+            .map(|(_, grade)| grade);
+        let changed_before = self
+            .previous
+            .iter()
+            .filter(|(key, _)| self.is_changed(key))
+            .map(|(_, grade)| grade);
+        changed_after
+            .chain(changed_before)
+            .flat_map(|grade| {
+                [grade.category.as_str(), "All"].map(|category| {
+                    (
+                        grade.platform.clone(),
+                        category.to_string(),
+                        grade.close_year,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Save the fingerprints for the next incremental grade, only writing the changed ones
+    /// if this grade was incremental. The time taken is added to `cost`.
+    pub fn save(
+        &self,
+        conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+        methodology_version: i32,
+        cost: &mut GradeCost,
+    ) -> Result<usize, ApiError> {
+        let start = Instant::now();
+        let count = if self.incremental {
+            let changed: Vec<MarketGrade> = self
+                .current
+                .iter()
+                .filter(|(key, _)| self.is_changed(key))
+                .map(|(_, grade)| grade.clone())
+                .collect();
+            let removed: Vec<(String, String)> = self
+                .previous
+                .keys()
+                .filter(|key| !self.current.contains_key(*key))
+                .cloned()
+                .collect();
+            save_market_grades(conn, methodology_version, &changed, &removed, false)?
+        } else {
+            let grades: Vec<MarketGrade> = self.current.values().cloned().collect();
+            save_market_grades(conn, methodology_version, &grades, &[], true)?
+        };
+        cost.add_stage("save", start);
+        Ok(count)
+    }
+}
@@ -10,6 +10,8 @@ pub struct MarketListQueryParams {
 
 #[derive(Debug, Serialize)]
 pub struct MarketListResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
     query: MarketListQueryParams,
     total_markets: usize,
     markets: Vec<Market>,
@@ -17,16 +19,251 @@ pub struct MarketListResponse {
 
 pub fn build_market_list(
     query: Query<MarketListQueryParams>,
+    excluded: &ExclusionList,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> Result<HttpResponse, ApiError> {
     // get markets from database
-    let (markets, total_markets) =
-        get_markets_filtered(conn, Some(&query.filters), Some(&query.list_params))?;
+    let (markets, total_markets) = get_markets_filtered(
+        conn,
+        excluded,
+        Some(&query.filters),
+        Some(&query.list_params),
+        None,
+    )?;
 
     let response = MarketListResponse {
+        schema_version: SCHEMA_VERSION,
         query: query.into_inner(),
         total_markets,
         markets,
     };
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// The longest window that can be requested from `/recently_resolved`, in days.
+const RECENTLY_RESOLVED_MAX_DAYS: i64 = 90;
+
+/// The most markets returned from `/recently_resolved`.
+const RECENTLY_RESOLVED_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecentlyResolvedQueryParams {
+    /// How many days back to look for resolved markets (default 7).
+    pub days: Option<i64>,
+}
+
+/// A resolved market with its probability and Brier score at each criterion.
+#[derive(Debug, Serialize)]
+struct RecentlyResolvedMarket {
+    title: String,
+    platform: String,
+    platform_id: String,
+    url: String,
+    category: String,
+    resolved_dt: Option<DateTime<Utc>>,
+    resolution: f32,
+    prob_at_midpoint: f32,
+    prob_at_close: f32,
+    prob_time_avg: f32,
+    brier_at_midpoint: f32,
+    brier_at_close: f32,
+    brier_time_avg: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecentlyResolvedResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    query: RecentlyResolvedQueryParams,
+    total_markets: usize,
+    markets: Vec<RecentlyResolvedMarket>,
+}
+
+/// List the markets that resolved in the last few days, newest first, with their final scores.
+/// Only the summary fields are loaded so this stays light enough for the front page.
+pub fn build_recently_resolved(
+    query: Query<RecentlyResolvedQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let days = query.days.unwrap_or(7);
+    if !(1..=RECENTLY_RESOLVED_MAX_DAYS).contains(&days) {
+        return Err(ApiError::new(
+            400,
+            format!("days must be between 1 and {RECENTLY_RESOLVED_MAX_DAYS}, got {days}"),
+        ));
+    }
+    let since = Utc::now() - chrono::Duration::days(days);
+
+    // numeric questions can't be scored as probabilities, so leave them out
+    let rows = market::table
+        .filter(market::question_type.eq("binary"))
+        .filter(market::resolved_dt.ge(since))
+        .order(market::resolved_dt.desc())
+        .limit(RECENTLY_RESOLVED_LIMIT)
+        .select((
+            market::title,
+            market::platform,
+            market::platform_id,
+            market::url,
+            market::category,
+            market::resolved_dt,
+            market::resolution,
+            market::prob_at_midpoint,
+            market::prob_at_close,
+            market::prob_time_avg,
+        ))
+        .load::<(
+            String,
+            String,
+            String,
+            String,
+            String,
+            Option<DateTime<Utc>>,
+            f32,
+            f32,
+            f32,
+            f32,
+        )>(conn)
+        .map_err(|e| ApiError::new(500, format!("failed to query markets: {e}")))?;
+
+    let markets: Vec<RecentlyResolvedMarket> = rows
+        .into_iter()
+        .map(
+            |(
+                title,
+                platform,
+                platform_id,
+                url,
+                category,
+                resolved_dt,
+                resolution,
+                prob_at_midpoint,
+                prob_at_close,
+                prob_time_avg,
+            )| RecentlyResolvedMarket {
+                title,
+                platform,
+                platform_id,
+                url,
+                category,
+                resolved_dt,
+                resolution,
+                prob_at_midpoint,
+                prob_at_close,
+                prob_time_avg,
+                brier_at_midpoint: (resolution - prob_at_midpoint).powi(2),
+                brier_at_close: (resolution - prob_at_close).powi(2),
+                brier_time_avg: (resolution - prob_time_avg).powi(2),
+            },
+        )
+        .collect();
+
+    let response = RecentlyResolvedResponse {
+        schema_version: SCHEMA_VERSION,
+        query: query.into_inner(),
+        total_markets: markets.len(),
+        markets,
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The query is echoed back as given, with every filter that wasn't sent as null.
+    #[test]
+    fn market_list_response_matches_snapshot() {
+        let query = Query::<MarketListQueryParams>::from_query(
+            "platform_select=manifold&volume_usd_min=12.5&sort_attribute=open_dt&sort_desc=true",
+        )
+        .unwrap();
+        let response = MarketListResponse {
+            schema_version: SCHEMA_VERSION,
+            query: query.into_inner(),
+            total_markets: 0,
+            markets: Vec::new(),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "query": {
+                "title_contains": null,
+                "description_contains": null,
+                "platform_select": "manifold",
+                "category_select": null,
+                "linked_group_select": null,
+                "region_select": null,
+                "open_ts_min": null,
+                "open_ts_max": null,
+                "close_ts_min": null,
+                "close_ts_max": null,
+                "open_days_min": null,
+                "open_days_max": null,
+                "volume_usd_min": "12.5",
+                "volume_usd_max": null,
+                "num_traders_min": null,
+                "num_traders_max": null,
+                "prob_at_midpoint_min": null,
+                "prob_at_midpoint_max": null,
+                "prob_at_close_min": null,
+                "prob_at_close_max": null,
+                "prob_time_avg_min": null,
+                "prob_time_avg_max": null,
+                "resolution_min": null,
+                "resolution_max": null,
+                "limit": "1000",
+                "offset": null,
+                "sort_attribute": "open_dt",
+                "sort_desc": "true",
+            },
+            "total_markets": 0,
+            "markets": [],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+
+    #[test]
+    fn recently_resolved_response_matches_snapshot() {
+        let response = RecentlyResolvedResponse {
+            schema_version: SCHEMA_VERSION,
+            query: RecentlyResolvedQueryParams { days: Some(7) },
+            total_markets: 1,
+            markets: Vec::from([RecentlyResolvedMarket {
+                title: "Will it rain?".to_string(),
+                platform: "manifold".to_string(),
+                platform_id: "abc123".to_string(),
+                url: "https://manifold.markets/user/will-it-rain".to_string(),
+                category: "Climate".to_string(),
+                resolved_dt: Some("2024-01-04T00:00:00Z".parse().unwrap()),
+                resolution: 1.0,
+                prob_at_midpoint: 0.5,
+                prob_at_close: 0.75,
+                prob_time_avg: 0.625,
+                brier_at_midpoint: 0.25,
+                brier_at_close: 0.0625,
+                brier_time_avg: 0.140625,
+            }]),
+        };
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "query": {"days": 7},
+            "total_markets": 1,
+            "markets": [{
+                "title": "Will it rain?",
+                "platform": "manifold",
+                "platform_id": "abc123",
+                "url": "https://manifold.markets/user/will-it-rain",
+                "category": "Climate",
+                "resolved_dt": "2024-01-04T00:00:00Z",
+                "resolution": 1.0,
+                "prob_at_midpoint": 0.5,
+                "prob_at_close": 0.75,
+                "prob_time_avg": 0.625,
+                "brier_at_midpoint": 0.25,
+                "brier_at_close": 0.0625,
+                "brier_time_avg": 0.140625,
+            }],
+        });
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
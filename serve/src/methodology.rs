@@ -0,0 +1,72 @@
+//! Versioning for the cohort scoring methodology.
+//! Recorded scores keep the version they were computed with, and older versions
+//! can be compiled in with their feature flag to reproduce published scores.
+
+use super::*;
+
+#[cfg(feature = "methodology-v1")]
+mod v1;
+
+/// The methodology used unless an older one is pinned.
+///
+/// - Version 1: every question type is scored as a probability.
+/// - Version 2: numeric questions are left out of the Brier scores, since their positions
+///   aren't probabilities, and are scored with the CRPS instead.
+pub const METHODOLOGY_VERSION: i32 = 2;
+
+/// Parameters to pin scoring to an older methodology.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MethodologyQueryParams {
+    pub methodology_version: Option<i32>,
+}
+
+/// Get the methodology version to score with, checking that this build supports it.
+pub fn get_methodology_version(requested: Option<i32>) -> Result<i32, ApiError> {
+    match requested.unwrap_or(METHODOLOGY_VERSION) {
+        METHODOLOGY_VERSION => Ok(METHODOLOGY_VERSION),
+        #[cfg(feature = "methodology-v1")]
+        1 => Ok(1),
+        version => Err(ApiError::new(
+            400,
+            format!("methodology version {version} is not available in this build"),
+        )),
+    }
+}
+
+/// Get the markets to score for a cohort under a methodology version.
+pub fn get_cohort_markets(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    filters: &CommonFilterParams,
+    version: i32,
+) -> Result<Vec<Market>, ApiError> {
+    match version {
+        #[cfg(feature = "methodology-v1")]
+        1 => v1::get_cohort_markets(conn, excluded, filters),
+        _ => get_markets_filtered(conn, excluded, Some(filters), None, None)
+            .map(|(markets, _)| markets),
+    }
+}
+
+/// Get the numeric and date questions to score with the CRPS for a cohort.
+/// Version 1 scored these as probabilities, so it has none.
+pub fn get_cohort_numeric_markets(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    filters: &CommonFilterParams,
+    version: i32,
+) -> Result<Vec<Market>, ApiError> {
+    match version {
+        #[cfg(feature = "methodology-v1")]
+        1 => Ok(Vec::new()),
+        _ => get_markets_filtered_by_type(
+            conn,
+            excluded,
+            &["numeric", "date"],
+            Some(filters),
+            None,
+            None,
+        )
+        .map(|(markets, _)| markets),
+    }
+}
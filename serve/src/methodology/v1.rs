@@ -0,0 +1,20 @@
+//! The first cohort scoring methodology, kept to reproduce scores recorded under it.
+
+use super::*;
+
+/// Get the markets to score, including numeric questions.
+pub fn get_cohort_markets(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    filters: &CommonFilterParams,
+) -> Result<Vec<Market>, ApiError> {
+    get_markets_filtered_by_type(
+        conn,
+        excluded,
+        &["binary", "numeric"],
+        Some(filters),
+        None,
+        None,
+    )
+    .map(|(markets, _)| markets)
+}
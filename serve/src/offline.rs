@@ -0,0 +1,139 @@
+//! Grading from a file of markets instead of the database, so scores can be computed in CI,
+//! for local experiments, or for a static build of the site without a running database.
+//! The input is what fetch prints with `--output stdout`, or one market per line.
+
+use super::*;
+use std::path::Path;
+use std::time::Instant;
+
+/// The version of the market format printed by fetch that this can read.
+const MARKET_SCHEMA_VERSION: u32 = 1;
+
+/// Markets printed by fetch, tagged with the schema version.
+#[derive(Debug, Deserialize)]
+struct MarketFile {
+    schema_version: u32,
+    markets: Vec<Market>,
+}
+
+/// Scores written to the output directory, in the same shape as the API responses.
+#[derive(Debug, Serialize)]
+struct ScoreFile<T> {
+    /// The version of this file format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    methodology_version: i32,
+    scores: Vec<T>,
+}
+
+/// Read every market from a file, either as a whole fetch output or one market per line.
+fn load_markets(path: &Path) -> Result<Vec<Market>, ApiError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| ApiError::new(500, format!("failed to read {}: {e}", path.display())))?;
+    let markets = match serde_json::from_str::<MarketFile>(&contents) {
+        Ok(file) => {
+            if file.schema_version != MARKET_SCHEMA_VERSION {
+                return Err(ApiError::new(
+                    400,
+                    format!(
+                        "unsupported market schema version {} (expected {MARKET_SCHEMA_VERSION})",
+                        file.schema_version
+                    ),
+                ));
+            }
+            file.markets
+        }
+        Err(_) => contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str::<Market>(line).map_err(|e| {
+                    ApiError::new(
+                        400,
+                        format!("failed to parse market on line {}: {e}", i + 1),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    Ok(markets.into_iter().map(Market::unpack_prob_daily).collect())
+}
+
+/// Get the average resolution of the binary markets in each platform and category,
+/// the same as `get_base_rates` does from the database.
+fn get_offline_base_rates(markets: &[Market]) -> HashMap<(String, String), f32> {
+    let mut totals: HashMap<(String, String), (f32, usize)> = HashMap::new();
+    for market in markets {
+        let entry = totals
+            .entry((market.platform.clone(), market.category.clone()))
+            .or_default();
+        entry.0 += market.resolution;
+        entry.1 += 1;
+    }
+    totals
+        .into_iter()
+        .map(|(key, (sum, count))| (key, sum / count as f32))
+        .collect()
+}
+
+/// Write a list of scores to a JSON file in the output directory.
+fn write_scores<T: Serialize>(
+    output_dir: &Path,
+    name: &str,
+    scores: Vec<T>,
+) -> Result<(), ApiError> {
+    let path = output_dir.join(name);
+    let file = File::create(&path)
+        .map_err(|e| ApiError::new(500, format!("failed to create {}: {e}", path.display())))?;
+    serde_json::to_writer_pretty(
+        file,
+        &ScoreFile {
+            schema_version: SCHEMA_VERSION,
+            methodology_version: METHODOLOGY_VERSION,
+            scores,
+        },
+    )
+    .map_err(|e| ApiError::new(500, format!("failed to write {}: {e}", path.display())))
+}
+
+/// Score every market in the input file under the current methodology and write the platform
+/// scores to `scores.json` and the yearly scores to `period_scores.json` in the output
+/// directory. Markets on the exclusion list or from platforms without settled resolutions are
+/// left out, the same as in a normal grade.
+/// Ratings need the group mapping and saved history, so they aren't computed.
+pub fn grade_offline(input: &Path, output_dir: &Path) -> Result<GradeCost, ApiError> {
+    let mut cost = GradeCost::default();
+    let start = Instant::now();
+    let excluded = ExclusionList::load()?;
+    let (markets, numeric_markets): (Vec<Market>, Vec<Market>) = load_markets(input)?
+        .into_iter()
+        .filter(|m| !excluded.contains(m))
+        .filter(|m| !UNSETTLED_PLATFORMS.contains(&m.platform.as_str()))
+        .filter(|m| matches!(m.question_type.as_str(), "binary" | "numeric" | "date"))
+        .partition(|m| m.question_type == "binary");
+    let base_rates = get_offline_base_rates(&markets);
+    cost.add_stage("load", start);
+
+    let yearly_scores = score_periods(&markets, None, Utc::now(), METHODOLOGY_VERSION);
+    let scores = score_markets(
+        0,
+        markets,
+        numeric_markets,
+        &base_rates,
+        METHODOLOGY_VERSION,
+        None,
+        &mut cost,
+    );
+
+    let start = Instant::now();
+    std::fs::create_dir_all(output_dir).map_err(|e| {
+        ApiError::new(
+            500,
+            format!("failed to create {}: {e}", output_dir.display()),
+        )
+    })?;
+    write_scores(output_dir, "scores.json", scores)?;
+    write_scores(output_dir, "period_scores.json", yearly_scores)?;
+    cost.add_stage("save", start);
+    Ok(cost)
+}
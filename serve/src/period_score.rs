@@ -0,0 +1,143 @@
+//! Platform scores for each calendar year, so the site can chart whether platforms are
+//! improving instead of only showing all-time averages.
+//! These are computed by the grade job and saved, since scoring every market is slow.
+
+use super::*;
+use chrono::Datelike;
+use std::time::Instant;
+
+/// Parameters for listing yearly scores.
+#[derive(Debug, Deserialize)]
+pub struct PeriodScoreQueryParams {
+    platform: Option<String>,
+    /// A market category, or `All` for every category together.
+    category: Option<String>,
+    methodology_version: Option<i32>,
+}
+
+/// Full response for the yearly scores.
+#[derive(Debug, Serialize)]
+struct PeriodScoreResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    methodology_version: i32,
+    scores: Vec<PeriodScore>,
+}
+
+/// Score a list of markets that closed in the same year.
+fn score_period(
+    markets: &[&Market],
+    platform: &str,
+    category: &str,
+    year: i32,
+    scored_dt: DateTime<Utc>,
+    methodology_version: i32,
+) -> PeriodScore {
+    let count = markets.len() as f32;
+    let average_brier = |prob: fn(&Market) -> f32| {
+        markets
+            .iter()
+            .map(|m| (m.resolution - prob(m)).powf(2.0))
+            .sum::<f32>()
+            / count
+    };
+    PeriodScore {
+        scored_dt,
+        platform: platform.to_string(),
+        category: category.to_string(),
+        year,
+        num_markets: markets.len() as i32,
+        brier_at_midpoint: average_brier(|m| m.prob_at_midpoint),
+        brier_at_close: average_brier(|m| m.prob_at_close),
+        brier_time_avg: average_brier(|m| m.prob_time_avg),
+        methodology_version,
+    }
+}
+
+/// Score every platform and category for each year markets closed in, then save the results
+/// in place of the last set for this methodology version. In an incremental grade, only the
+/// years with a changed market are scored and replaced. The time taken is added to `cost`.
+/// Returns the number of scores that were saved.
+pub fn record_period_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    methodology_version: i32,
+    state: &GradeState,
+    cost: &mut GradeCost,
+) -> Result<usize, ApiError> {
+    let start = Instant::now();
+    let changed_periods = state.incremental.then(|| state.get_changed_periods());
+    let scores = score_periods(
+        &state.markets,
+        changed_periods.as_ref(),
+        Utc::now(),
+        methodology_version,
+    );
+    let count = match changed_periods {
+        Some(changed) => {
+            let stale: Vec<(String, String, i32)> = changed.into_iter().collect();
+            merge_period_scores(conn, methodology_version, &scores, &stale)?
+        }
+        None => replace_period_scores(conn, methodology_version, &scores)?,
+    };
+    cost.add_stage("period_scores", start);
+    Ok(count)
+}
+
+/// Score every platform and category for each year the markets closed in, or only the years
+/// listed in `changed_periods` if it's given. Every category together is scored as `All`.
+pub fn score_periods(
+    markets: &[Market],
+    changed_periods: Option<&HashSet<(String, String, i32)>>,
+    scored_dt: DateTime<Utc>,
+    methodology_version: i32,
+) -> Vec<PeriodScore> {
+    // sort the markets into each platform, category, and year, plus every category together
+    let mut periods: HashMap<(&str, &str, i32), Vec<&Market>> = HashMap::new();
+    for market in markets {
+        let year = market.close_dt.year();
+        for category in [market.category.as_str(), "All"] {
+            periods
+                .entry((market.platform.as_str(), category, year))
+                .or_default()
+                .push(market);
+        }
+    }
+
+    periods
+        .into_iter()
+        .filter(|((platform, category, year), _)| match changed_periods {
+            Some(changed) => changed.contains(&(platform.to_string(), category.to_string(), *year)),
+            None => true,
+        })
+        .map(|((platform, category, year), market_list)| {
+            score_period(
+                &market_list,
+                platform,
+                category,
+                year,
+                scored_dt,
+                methodology_version,
+            )
+        })
+        .collect()
+}
+
+/// Get the saved yearly scores, optionally for one platform or category.
+pub fn build_period_scores(
+    query: Query<PeriodScoreQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let methodology_version = get_methodology_version(query.methodology_version)?;
+    let scores = get_period_scores(
+        conn,
+        methodology_version,
+        query.platform.as_deref(),
+        query.category.as_deref(),
+    )?;
+
+    Ok(HttpResponse::Ok().json(PeriodScoreResponse {
+        schema_version: SCHEMA_VERSION,
+        methodology_version,
+        scores,
+    }))
+}
@@ -0,0 +1,161 @@
+//! Elo ratings for each platform, built from head-to-head results on linked questions.
+//! Questions are counted in the order they closed, so the saved history shows how each
+//! platform's rating moved over time.
+
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+
+/// The rating every platform starts with.
+const INITIAL_RATING: f32 = 1500.0;
+/// The most a platform's rating can move on a single question.
+const K_FACTOR: f32 = 32.0;
+/// A rating difference of this much means the higher platform is expected to win 10 to 1.
+const RATING_SCALE: f32 = 400.0;
+
+/// Parameters for getting platform ratings.
+#[derive(Debug, Deserialize)]
+pub struct RatingQueryParams {
+    platform: Option<String>,
+    /// A market category, or `All` for every category together.
+    category: Option<String>,
+    methodology_version: Option<i32>,
+}
+
+/// Full response for the platform ratings.
+#[derive(Debug, Serialize)]
+struct RatingResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    methodology_version: i32,
+    /// The latest rating of each platform in each category.
+    current: Vec<PlatformRating>,
+    /// Every rating change, oldest first.
+    history: Vec<PlatformRating>,
+}
+
+/// Get the chance a platform beats an opponent on a question, given their ratings.
+fn get_expected_score(rating: f32, opponent_rating: f32) -> f32 {
+    1.0 / (1.0 + 10.0_f32.powf((opponent_rating - rating) / RATING_SCALE))
+}
+
+/// Rate every platform on the linked questions in a category, one question at a time.
+/// Each platform plays every other platform on the question, with the lower relative Brier
+/// score winning. The K-factor is split between opponents, so a question moves a platform's
+/// rating by at most `K_FACTOR` no matter how many platforms covered it.
+fn rate_platforms(
+    results: &[GroupResult],
+    category: &str,
+    scored_dt: DateTime<Utc>,
+    methodology_version: i32,
+) -> Vec<PlatformRating> {
+    let mut ratings: HashMap<&String, (f32, i32)> = HashMap::new();
+    let mut history = Vec::new();
+    for result in results {
+        if category != "All" && result.category != category {
+            continue;
+        }
+        if result.platform_scores.len() < 2 {
+            continue;
+        }
+        let k = K_FACTOR / (result.platform_scores.len() - 1) as f32;
+
+        // compute every change from the ratings before this question
+        let changes: Vec<f32> = result
+            .platform_scores
+            .iter()
+            .map(|(platform, score)| {
+                let rating = ratings.get(platform).map_or(INITIAL_RATING, |r| r.0);
+                result
+                    .platform_scores
+                    .iter()
+                    .filter(|(opponent, _)| opponent != platform)
+                    .map(|(opponent, opponent_score)| {
+                        let opponent_rating = ratings.get(opponent).map_or(INITIAL_RATING, |r| r.0);
+                        let actual = match score.partial_cmp(opponent_score) {
+                            Some(std::cmp::Ordering::Less) => 1.0,
+                            Some(std::cmp::Ordering::Greater) => 0.0,
+                            _ => 0.5,
+                        };
+                        k * (actual - get_expected_score(rating, opponent_rating))
+                    })
+                    .sum()
+            })
+            .collect();
+
+        for ((platform, _), change) in result.platform_scores.iter().zip(changes) {
+            let entry = ratings.entry(platform).or_insert((INITIAL_RATING, 0));
+            entry.0 += change;
+            entry.1 += 1;
+            history.push(PlatformRating {
+                scored_dt,
+                platform: platform.clone(),
+                category: category.to_string(),
+                group_key: result.group_key.clone(),
+                rated_dt: result.close_dt,
+                rating: entry.0,
+                num_groups: entry.1,
+                methodology_version,
+            });
+        }
+    }
+    history
+}
+
+/// Rate every platform overall and in each category, then save the histories in place of
+/// the last ones for this methodology version. The time taken is added to `cost`.
+/// Returns the number of ratings that were saved.
+pub fn record_platform_ratings(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    methodology_version: i32,
+    cost: &mut GradeCost,
+) -> Result<usize, ApiError> {
+    let start = Instant::now();
+    let results = get_group_results(conn)?;
+    let scored_dt = Utc::now();
+
+    let categories: BTreeSet<&str> = results
+        .iter()
+        .map(|result| result.category.as_str())
+        .collect();
+    let mut ratings = rate_platforms(&results, "All", scored_dt, methodology_version);
+    for category in categories {
+        ratings.extend(rate_platforms(
+            &results,
+            category,
+            scored_dt,
+            methodology_version,
+        ));
+    }
+    let count = replace_platform_ratings(conn, methodology_version, &ratings)?;
+    cost.add_stage("ratings", start);
+    Ok(count)
+}
+
+/// Get the current platform ratings and their history, optionally for one platform or category.
+pub fn build_ratings(
+    query: Query<RatingQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let methodology_version = get_methodology_version(query.methodology_version)?;
+    let history = get_platform_ratings(
+        conn,
+        methodology_version,
+        query.platform.as_deref(),
+        query.category.as_deref(),
+    )?;
+
+    // the history is oldest first, so the last rating seen for each platform is its current one
+    let mut latest: BTreeMap<(&String, &String), &PlatformRating> = BTreeMap::new();
+    for rating in &history {
+        latest.insert((&rating.category, &rating.platform), rating);
+    }
+    let current = latest.into_values().cloned().collect();
+
+    Ok(HttpResponse::Ok().json(RatingResponse {
+        schema_version: SCHEMA_VERSION,
+        methodology_version,
+        current,
+        history,
+    }))
+}
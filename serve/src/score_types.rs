@@ -0,0 +1,178 @@
+use super::*;
+
+/// Definition of a score type, used to label scales in the client.
+#[derive(Debug, Serialize)]
+struct ScoreTypeDefinition {
+    id: ScoreType,
+    title: String,
+    description: String,
+    /// Whether a lower score is better.
+    lower_is_better: bool,
+    /// The lowest possible score, or `None` if it's unbounded below.
+    min: Option<f32>,
+    max: f32,
+    /// The score a forecaster would get by always predicting 50%, if fixed.
+    uninformed_value: Option<f32>,
+    /// Whether the score is measured against other markets instead of the outcome alone.
+    relative: bool,
+}
+
+/// Every kind of score the server reports.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ScoreType {
+    AbsoluteBrier,
+    TimeIntegratedBrier,
+    RelativeBrier,
+    RelativeBrierEarly,
+    RelativeBrierMiddle,
+    RelativeBrierLate,
+    AdjustedBrier,
+    BrierSkill,
+    Crps,
+    MultiBrier,
+    RankedProbability,
+}
+impl ScoreType {
+    /// Every score type, in the order they should be shown to users.
+    fn all() -> Vec<ScoreType> {
+        Vec::from([
+            ScoreType::AbsoluteBrier,
+            ScoreType::TimeIntegratedBrier,
+            ScoreType::RelativeBrier,
+            ScoreType::RelativeBrierEarly,
+            ScoreType::RelativeBrierMiddle,
+            ScoreType::RelativeBrierLate,
+            ScoreType::AdjustedBrier,
+            ScoreType::BrierSkill,
+            ScoreType::Crps,
+            ScoreType::MultiBrier,
+            ScoreType::RankedProbability,
+        ])
+    }
+    /// Get the full definition of this score type.
+    fn get_definition(self) -> ScoreTypeDefinition {
+        match self {
+            ScoreType::AbsoluteBrier => ScoreTypeDefinition {
+                id: self,
+                title: "Brier Score".to_string(),
+                description: "The squared difference between the predicted probability and the outcome. Used by the accuracy plot, cohort scores, and the group comparison.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 1.0,
+                uninformed_value: Some(0.25),
+                relative: false,
+            },
+            ScoreType::TimeIntegratedBrier => ScoreTypeDefinition {
+                id: self,
+                title: "Time-Integrated Brier Score".to_string(),
+                description: "The Brier score of each daily probability, averaged over the market's whole life instead of taken at a single point. Used by the accuracy plot, which can also weight early or late days more heavily.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 1.0,
+                uninformed_value: Some(0.25),
+                relative: false,
+            },
+            ScoreType::RelativeBrier => ScoreTypeDefinition {
+                id: self,
+                title: "Relative Brier Score".to_string(),
+                description: "The Brier score minus the median Brier score of all markets on the same question, averaged over the days they were all open. Negative means more accurate than the other platforms.".to_string(),
+                lower_is_better: true,
+                min: Some(-1.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: true,
+            },
+            ScoreType::RelativeBrierEarly => ScoreTypeDefinition {
+                id: self,
+                title: "Relative Brier Score (Early)".to_string(),
+                description: "The relative Brier score over the first third of the days all markets on the question were open. Only reported by the group comparison when time slices are requested.".to_string(),
+                lower_is_better: true,
+                min: Some(-1.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: true,
+            },
+            ScoreType::RelativeBrierMiddle => ScoreTypeDefinition {
+                id: self,
+                title: "Relative Brier Score (Middle)".to_string(),
+                description: "The relative Brier score over the middle third of the days all markets on the question were open. Only reported by the group comparison when time slices are requested.".to_string(),
+                lower_is_better: true,
+                min: Some(-1.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: true,
+            },
+            ScoreType::RelativeBrierLate => ScoreTypeDefinition {
+                id: self,
+                title: "Relative Brier Score (Late)".to_string(),
+                description: "The relative Brier score over the last third of the days all markets on the question were open. Only reported by the group comparison when time slices are requested.".to_string(),
+                lower_is_better: true,
+                min: Some(-1.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: true,
+            },
+            ScoreType::AdjustedBrier => ScoreTypeDefinition {
+                id: self,
+                title: "Difficulty-Adjusted Brier Score".to_string(),
+                description: "The Brier score a platform would be expected to get on a question of average difficulty, fit from every linked question at once so platforms that take on harder questions aren't penalized. Only reported by the group comparison.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 1.0,
+                uninformed_value: Some(0.25),
+                relative: true,
+            },
+            ScoreType::BrierSkill => ScoreTypeDefinition {
+                id: self,
+                title: "Brier Skill Score".to_string(),
+                description: "How much better the Brier score is than always predicting the historical resolution rate of the market's platform and category, as a fraction of the way to perfect. Categories where most markets resolve the same way are harder to look good in. Recorded by cohort scores for the time-averaged probability.".to_string(),
+                lower_is_better: false,
+                min: None,
+                max: 1.0,
+                uninformed_value: None,
+                relative: true,
+            },
+            ScoreType::Crps => ScoreTypeDefinition {
+                id: self,
+                title: "Continuous Ranked Probability Score".to_string(),
+                description: "The squared distance between the forecast distribution and the outcome, integrated over the question range and averaged over each day. Used by cohort scores for numeric and date questions, which can't be scored as probabilities.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: false,
+            },
+            ScoreType::MultiBrier => ScoreTypeDefinition {
+                id: self,
+                title: "Multi-Class Brier Score".to_string(),
+                description: "The squared difference between the forecast and the outcome summed over every option of a multiple-choice question, using each option's time-averaged probability rescaled so they sum to 100%. Recorded by cohort scores for questions saved as one market per option, so the whole distribution is scored instead of only the winner.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 2.0,
+                uninformed_value: None,
+                relative: false,
+            },
+            ScoreType::RankedProbability => ScoreTypeDefinition {
+                id: self,
+                title: "Ranked Probability Score".to_string(),
+                description: "The squared difference between the cumulative forecast and outcome over the ordered options of a question, such as ranges of a number, averaged over the options. Probability on an option next to the outcome costs less than on one far from it. Recorded by cohort scores for questions with ordered options.".to_string(),
+                lower_is_better: true,
+                min: Some(0.0),
+                max: 1.0,
+                uninformed_value: None,
+                relative: false,
+            },
+        }
+    }
+}
+
+/// Lists every score type with its direction and range.
+pub fn build_score_type_list() -> Result<HttpResponse, ApiError> {
+    let score_types: Vec<ScoreTypeDefinition> = ScoreType::all()
+        .into_iter()
+        .map(|score_type| score_type.get_definition())
+        .collect();
+
+    Ok(HttpResponse::Ok().json(score_types))
+}
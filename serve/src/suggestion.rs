@@ -0,0 +1,172 @@
+use super::*;
+use serde_json::json;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const SUGGESTION_RATELIMIT: usize = 5;
+const SUGGESTION_RATELIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+const SUGGESTION_MESSAGE_MAX_LENGTH: usize = 2000;
+const SUGGESTION_MARKETS_MAX: usize = 10;
+/// Most clients tracked at once, so a flood of addresses can't grow the limiter without bound.
+const SUGGESTION_RATELIMIT_MAX_CLIENTS: usize = 10_000;
+
+/// The type of change a user is suggesting.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// These markets ask the same question and should be grouped.
+    LinkMarkets,
+    /// This market has the wrong category.
+    Miscategorized,
+    /// This market has the wrong resolution.
+    Misresolved,
+}
+impl SuggestionKind {
+    /// Get the minimum number of markets needed for this kind of suggestion.
+    fn min_markets(&self) -> usize {
+        match self {
+            SuggestionKind::LinkMarkets => 2,
+            SuggestionKind::Miscategorized => 1,
+            SuggestionKind::Misresolved => 1,
+        }
+    }
+}
+
+/// Body sent when submitting a suggestion.
+#[derive(Debug, Deserialize)]
+pub struct SuggestionBody {
+    kind: SuggestionKind,
+    markets: Vec<MarketRef>,
+    #[serde(default)]
+    message: String,
+    /// Hidden form field that real users leave empty.
+    #[serde(default)]
+    website: String,
+}
+
+/// A simple in-memory limiter that allows a number of requests per client in a sliding window.
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    requests: Mutex<HashMap<IpAddr, Vec<Instant>>>,
+}
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> RateLimiter {
+        RateLimiter {
+            limit,
+            window,
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a request from this client, returning false if it is over the limit.
+    fn check(&self, client: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut requests = self.requests.lock().unwrap();
+        // drop old entries so the map doesn't grow forever
+        requests.retain(|_, times| {
+            times.retain(|t| now.duration_since(*t) < self.window);
+            !times.is_empty()
+        });
+        if !requests.contains_key(&client) && requests.len() >= SUGGESTION_RATELIMIT_MAX_CLIENTS {
+            return false;
+        }
+        let times = requests.entry(client).or_default();
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push(now);
+        true
+    }
+}
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(SUGGESTION_RATELIMIT, SUGGESTION_RATELIMIT_WINDOW)
+    }
+}
+
+/// Get the address of the client that sent a request, for rate limiting.
+/// The forwarded address headers can be set by anyone, so they're only used when the
+/// connection comes from one of the trusted reverse proxies.
+pub fn get_client_addr(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+    let forwarded = req.connection_info().realip_remote_addr()?.to_string();
+    forwarded
+        .parse::<std::net::SocketAddr>()
+        .map(|addr| addr.ip())
+        .or_else(|_| forwarded.parse())
+        .ok()
+}
+
+/// Validate and save a user suggestion for review.
+pub fn build_suggestion(
+    body: SuggestionBody,
+    client: Option<IpAddr>,
+    limiter: &RateLimiter,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    // bots fill in every field, so quietly accept and drop these
+    if !body.website.is_empty() {
+        return Ok(HttpResponse::Accepted().json(json!({ "status": "received" })));
+    }
+
+    let client = client.ok_or(ApiError::new(
+        400,
+        "could not determine client address".to_string(),
+    ))?;
+    if !limiter.check(client) {
+        return Err(ApiError::new(
+            429,
+            "too many suggestions, please try again later".to_string(),
+        ));
+    }
+
+    if body.markets.len() < body.kind.min_markets() || body.markets.len() > SUGGESTION_MARKETS_MAX {
+        return Err(ApiError::new(
+            400,
+            format!(
+                "this suggestion needs between {} and {SUGGESTION_MARKETS_MAX} markets",
+                body.kind.min_markets()
+            ),
+        ));
+    }
+    if body.message.chars().count() > SUGGESTION_MESSAGE_MAX_LENGTH {
+        return Err(ApiError::new(
+            400,
+            format!("message is longer than {SUGGESTION_MESSAGE_MAX_LENGTH} characters"),
+        ));
+    }
+    // make sure every market exists so moderators don't chase typos
+    for market_ref in body.markets.iter() {
+        get_market_by_platform_id(conn, &market_ref.platform, &market_ref.platform_id).map_err(
+            |_| {
+                ApiError::new(
+                    400,
+                    format!(
+                        "market not found: {}/{}",
+                        market_ref.platform, market_ref.platform_id
+                    ),
+                )
+            },
+        )?;
+    }
+
+    let kind = serde_json::to_value(&body.kind)
+        .map_err(|e| ApiError::new(500, format!("failed to serialize suggestion kind: {e}")))?;
+    let markets = serde_json::to_value(&body.markets)
+        .map_err(|e| ApiError::new(500, format!("failed to serialize suggestion markets: {e}")))?;
+    let id = insert_suggestion(
+        conn,
+        NewSuggestion {
+            kind: kind.as_str().unwrap_or_default().to_string(),
+            markets,
+            message: body.message.trim().to_string(),
+        },
+    )?;
+
+    Ok(HttpResponse::Accepted().json(json!({ "status": "received", "id": id })))
+}
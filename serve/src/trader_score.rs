@@ -0,0 +1,135 @@
+//! A leaderboard of individual traders, scored from the prices they bought at on resolved
+//! markets. Fetch only saves trader bets when run with `--trader-bets`, and trader IDs are
+//! anonymized before they're saved, so this is empty unless that was turned on.
+
+use super::*;
+use std::time::Instant;
+
+/// The fewest markets a trader needs to show up on the leaderboard, if not set in the query.
+const DEFAULT_MIN_MARKETS: i32 = 10;
+/// How many traders to return, if not set in the query.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Parameters for getting the trader leaderboard.
+#[derive(Debug, Deserialize)]
+pub struct TraderScoreQueryParams {
+    min_markets: Option<i32>,
+    limit: Option<i64>,
+    methodology_version: Option<i32>,
+}
+
+/// Full response for the trader leaderboard.
+#[derive(Debug, Serialize)]
+struct TraderScoreResponse {
+    /// The version of this response format, see `SCHEMA_VERSION`.
+    schema_version: u32,
+    methodology_version: i32,
+    min_markets: i32,
+    traders: Vec<TraderScore>,
+}
+
+/// Running totals for one trader while their markets are scored.
+#[derive(Debug, Default)]
+struct TraderTotals {
+    num_markets: usize,
+    num_bets: usize,
+    brier: f32,
+    relative_brier: f32,
+}
+
+/// Score every trader on the resolved markets they bought on, then save the results in place
+/// of the last set for this methodology version. Each market counts once per trader no matter
+/// how many times they bought, with the trader scored on the average Brier score of their bet
+/// prices. The time taken is added to `cost`.
+/// Returns the number of traders that were saved.
+pub fn record_trader_scores(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    excluded: &ExclusionList,
+    methodology_version: i32,
+    cost: &mut GradeCost,
+) -> Result<usize, ApiError> {
+    let start = Instant::now();
+    let trader_bets = get_all_trader_bets(conn)?;
+    if trader_bets.is_empty() {
+        // trader bets are opt-in, so don't clear a leaderboard that was built earlier
+        return Ok(0);
+    }
+    let markets = get_cohort_markets(
+        conn,
+        excluded,
+        &CommonFilterParams::default(),
+        methodology_version,
+    )?;
+    let markets: HashMap<(&str, &str), &Market> = markets
+        .iter()
+        .map(|m| ((m.platform.as_str(), m.platform_id.as_str()), m))
+        .collect();
+    let scored_dt = Utc::now();
+
+    let mut totals: HashMap<(&str, &str), TraderTotals> = HashMap::new();
+    for bets in &trader_bets {
+        // skip markets that were excluded or aren't scored as binary questions
+        let Some(market) = markets.get(&(bets.platform.as_str(), bets.platform_id.as_str())) else {
+            continue;
+        };
+        if bets.bet_probs.is_empty() {
+            continue;
+        }
+        let brier = bets
+            .bet_probs
+            .iter()
+            .map(|prob| (market.resolution - prob).powf(2.0))
+            .sum::<f32>()
+            / bets.bet_probs.len() as f32;
+        let market_brier = (market.resolution - market.prob_time_avg).powf(2.0);
+
+        let entry = totals
+            .entry((bets.platform.as_str(), bets.trader_id.as_str()))
+            .or_default();
+        entry.num_markets += 1;
+        entry.num_bets += bets.bet_probs.len();
+        entry.brier += brier;
+        entry.relative_brier += brier - market_brier;
+    }
+
+    let scores: Vec<TraderScore> = totals
+        .into_iter()
+        .map(|((platform, trader_id), totals)| TraderScore {
+            scored_dt,
+            platform: platform.to_string(),
+            trader_id: trader_id.to_string(),
+            num_markets: totals.num_markets as i32,
+            num_bets: totals.num_bets as i32,
+            brier: totals.brier / totals.num_markets as f32,
+            relative_brier: totals.relative_brier / totals.num_markets as f32,
+            methodology_version,
+        })
+        .collect();
+    let count = replace_trader_scores(conn, methodology_version, &scores)?;
+    cost.add_stage("trader_scores", start);
+    Ok(count)
+}
+
+/// Get the traders with the best relative scores.
+pub fn build_trader_leaderboard(
+    query: Query<TraderScoreQueryParams>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<HttpResponse, ApiError> {
+    let methodology_version = get_methodology_version(query.methodology_version)?;
+    let min_markets = query.min_markets.unwrap_or(DEFAULT_MIN_MARKETS);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit <= 0 {
+        return Err(ApiError::new(
+            400,
+            format!("limit must be positive, got {limit}"),
+        ));
+    }
+    let traders = get_trader_scores(conn, methodology_version, min_markets, limit)?;
+
+    Ok(HttpResponse::Ok().json(TraderScoreResponse {
+        schema_version: SCHEMA_VERSION,
+        methodology_version,
+        min_markets,
+        traders,
+    }))
+}